@@ -0,0 +1,11 @@
+-- Add up migration script here
+-- 送信待ちのメール
+-- メールサーバーがない環境では，ここに溜まったメールを確認する
+CREATE TABLE IF NOT EXISTS mail_outbox (
+    id UUID PRIMARY KEY,
+    recipient VARCHAR NOT NULL,
+    subject VARCHAR NOT NULL,
+    body VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    sent_at TIMESTAMP
+);
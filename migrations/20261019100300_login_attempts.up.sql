@@ -0,0 +1,22 @@
+-- Add up migration script here
+-- ログイン失敗の回数とブロック期限
+-- keyはメールアドレスごと(email:...)とIPアドレスごと(ip:...)の2種類
+CREATE TABLE IF NOT EXISTS login_attempts (
+    key VARCHAR PRIMARY KEY,
+    failed_count INTEGER NOT NULL DEFAULT 0,
+    last_failed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    blocked_until TIMESTAMP
+);
+
+-- ログイン失敗の監査ログ
+CREATE TABLE IF NOT EXISTS login_audit_events (
+    id UUID PRIMARY KEY,
+    user_id UUID,
+    email VARCHAR NOT NULL,
+    ip_address VARCHAR,
+    event VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS login_audit_events_email_idx ON login_audit_events (email, created_at);
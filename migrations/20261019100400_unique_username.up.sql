@@ -0,0 +1,13 @@
+-- Add up migration script here
+-- 大文字小文字だけが異なるユーザー名が既にある場合は，後から登録した方の名前を変更する
+UPDATE users AS u
+SET username = u.username || '_' || right(u.id::text, 6)
+WHERE EXISTS (
+    SELECT 1
+    FROM users AS o
+    WHERE lower(o.username) = lower(u.username)
+        AND (o.created_at, o.id) < (u.created_at, u.id)
+);
+
+-- ユーザー名は大文字小文字を区別せずに一意とする
+CREATE UNIQUE INDEX users_username_lower_key ON users (lower(username));
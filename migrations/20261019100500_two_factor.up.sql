@@ -0,0 +1,35 @@
+-- Add up migration script here
+-- TOTPによる2要素認証の設定
+-- 秘密鍵はアプリケーション側でAES-256-GCMにより暗号化したもの(nonce || 暗号文)を保存する
+-- enabled_atがNULLの間は登録の確認待ち
+CREATE TABLE IF NOT EXISTS user_totp (
+    user_id UUID PRIMARY KEY,
+    secret_ciphertext BYTEA NOT NULL,
+    enabled_at TIMESTAMP,
+    -- 同じコードを2回使えないよう，最後に使われた時間ステップを記録する
+    last_used_step BIGINT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- 認証アプリを使えなくなったときのためのリカバリーコード
+-- コードそのものは保存せず，SHA-256のハッシュのみ保存する
+CREATE TABLE IF NOT EXISTS totp_recovery_codes (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    code_hash VARCHAR NOT NULL,
+    used_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    UNIQUE (user_id, code_hash)
+);
+
+-- パスワードの確認後，2要素目の確認を待っているログイン
+CREATE TABLE IF NOT EXISTS two_factor_challenges (
+    token_hash VARCHAR PRIMARY KEY,
+    user_id UUID NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    failed_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
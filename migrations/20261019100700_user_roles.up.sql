@@ -0,0 +1,4 @@
+-- Add up migration script here
+ALTER TABLE users
+    ADD COLUMN role VARCHAR NOT NULL DEFAULT 'user'
+    CONSTRAINT users_role_check CHECK (role IN ('user', 'moderator', 'admin'));
@@ -0,0 +1,11 @@
+-- Add up migration script here
+-- 猶予期間が過ぎるまでは取り消せるよう，削除の予約として記録する
+CREATE TABLE IF NOT EXISTS account_deletions (
+    user_id UUID PRIMARY KEY,
+    mode VARCHAR NOT NULL CHECK (mode IN ('anonymize', 'cascade')),
+    requested_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    scheduled_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS account_deletions_scheduled_at_idx ON account_deletions (scheduled_at);
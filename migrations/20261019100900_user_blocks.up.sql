@@ -0,0 +1,28 @@
+-- Add up migration script here
+-- ブロックしたユーザーからのフォローやいいねを拒否する
+CREATE TABLE IF NOT EXISTS user_blocks (
+    id UUID PRIMARY KEY,
+    blocker_id UUID NOT NULL,
+    blocked_id UUID NOT NULL,
+
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (blocker_id, blocked_id),
+    CHECK (blocker_id <> blocked_id),
+    FOREIGN KEY (blocker_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (blocked_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS user_blocks_blocked_id_idx ON user_blocks (blocked_id);
+
+-- ミュートしたユーザーの記事を一覧やフィードに表示しない 相手には影響しない
+CREATE TABLE IF NOT EXISTS user_mutes (
+    id UUID PRIMARY KEY,
+    muter_id UUID NOT NULL,
+    muted_id UUID NOT NULL,
+
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (muter_id, muted_id),
+    CHECK (muter_id <> muted_id),
+    FOREIGN KEY (muter_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (muted_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE user_follows DROP CONSTRAINT IF EXISTS user_follows_no_self_follow;
+ALTER TABLE user_follows DROP CONSTRAINT IF EXISTS user_follows_follower_followee_key;
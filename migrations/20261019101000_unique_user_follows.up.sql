@@ -0,0 +1,14 @@
+-- Add up migration script here
+-- 重複したフォローは最初の1件だけを残す
+DELETE FROM user_follows a
+USING user_follows b
+WHERE a.follower_id = b.follower_id
+    AND a.followee_id = b.followee_id
+    AND (a.created_at, a.id) > (b.created_at, b.id);
+
+DELETE FROM user_follows WHERE follower_id = followee_id;
+
+ALTER TABLE user_follows
+    ADD CONSTRAINT user_follows_follower_followee_key UNIQUE (follower_id, followee_id);
+ALTER TABLE user_follows
+    ADD CONSTRAINT user_follows_no_self_follow CHECK (follower_id <> followee_id);
@@ -0,0 +1,17 @@
+-- Add up migration script here
+ALTER TABLE users ADD COLUMN is_private BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- 非公開アカウントへのフォローは，承認されるまでここに保留する
+CREATE TABLE IF NOT EXISTS follow_requests (
+    id UUID PRIMARY KEY,
+    requester_id UUID NOT NULL,
+    target_id UUID NOT NULL,
+
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (requester_id, target_id),
+    CHECK (requester_id <> target_id),
+    FOREIGN KEY (requester_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (target_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS follow_requests_target_id_idx ON follow_requests (target_id, created_at);
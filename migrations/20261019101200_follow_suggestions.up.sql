@@ -0,0 +1,19 @@
+-- Add up migration script here
+
+-- おすすめユーザーの計算に使う，記事を書いたユーザーごとの集計
+-- 記事が増えるたびに集計し直すと重いので，定期的にREFRESHする
+CREATE MATERIALIZED VIEW IF NOT EXISTS user_activity_stats AS
+SELECT
+  a.author_id AS user_id,
+  COALESCE(
+    array_agg(DISTINCT at.tag_id) FILTER (WHERE at.tag_id IS NOT NULL),
+    '{}'
+  ) AS tag_ids,
+  COUNT(DISTINCT a.id) AS article_count,
+  MAX(a.created_at) AS last_published_at
+FROM articles a
+LEFT JOIN article_tags at ON at.article_id = a.id
+GROUP BY a.author_id;
+
+-- REFRESH MATERIALIZED VIEW CONCURRENTLYにはユニークインデックスが必要
+CREATE UNIQUE INDEX IF NOT EXISTS user_activity_stats_user_id_idx ON user_activity_stats (user_id);
@@ -0,0 +1,8 @@
+-- Add up migration script here
+
+-- RealWorldの仕様にないプロフィール項目 どれも省略できる
+ALTER TABLE users ADD COLUMN IF NOT EXISTS display_name VARCHAR;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS location VARCHAR;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS pronouns VARCHAR;
+-- ウェブサイトやSNSのURL
+ALTER TABLE users ADD COLUMN IF NOT EXISTS links TEXT[] NOT NULL DEFAULT '{}';
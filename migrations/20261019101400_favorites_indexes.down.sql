@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS favorites_user_id_active_idx;
+DROP INDEX IF EXISTS favorites_article_id_active_idx;
@@ -0,0 +1,11 @@
+-- Add up migration script here
+
+-- 記事をいいねしたユーザーの一覧 論理削除されたものは含めない
+CREATE INDEX IF NOT EXISTS favorites_article_id_active_idx
+  ON favorites (article_id, created_at DESC)
+  WHERE is_deleted = FALSE;
+
+-- ユーザーがいいねした記事の一覧
+CREATE INDEX IF NOT EXISTS favorites_user_id_active_idx
+  ON favorites (user_id, created_at DESC)
+  WHERE is_deleted = FALSE;
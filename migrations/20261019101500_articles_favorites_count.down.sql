@@ -0,0 +1,11 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS update_article_favorites_count ON favorites;
+DROP FUNCTION IF EXISTS update_article_favorites_count();
+
+ALTER TABLE articles DROP COLUMN IF EXISTS favorites_count;
+
+DROP TRIGGER IF EXISTS update_articles_modtime ON articles;
+CREATE TRIGGER update_articles_modtime
+BEFORE UPDATE ON articles
+FOR EACH ROW
+EXECUTE PROCEDURE update_timestamp();
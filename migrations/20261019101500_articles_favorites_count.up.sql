@@ -0,0 +1,44 @@
+-- Add up migration script here
+
+-- いいね数で記事のupdated_atが変わらないよう，本文などが更新されたときだけ動かす
+DROP TRIGGER IF EXISTS update_articles_modtime ON articles;
+CREATE TRIGGER update_articles_modtime
+BEFORE UPDATE OF title, description, body, slug, author_id ON articles
+FOR EACH ROW
+EXECUTE PROCEDURE update_timestamp();
+
+ALTER TABLE articles
+  ADD COLUMN IF NOT EXISTS favorites_count INTEGER NOT NULL DEFAULT 0;
+
+UPDATE articles a
+SET favorites_count = (
+  SELECT COUNT(*) FROM favorites f
+  WHERE f.article_id = a.id AND f.is_deleted = FALSE
+);
+
+-- favoritesの行が有効になったとき，無効になったときだけいいね数を増減させる
+-- すでにいいねしている記事に再度いいねしても数は変わらない
+CREATE OR REPLACE FUNCTION update_article_favorites_count()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'INSERT' THEN
+    IF NOT NEW.is_deleted THEN
+      UPDATE articles SET favorites_count = favorites_count + 1 WHERE id = NEW.article_id;
+    END IF;
+  ELSIF TG_OP = 'DELETE' THEN
+    IF NOT OLD.is_deleted THEN
+      UPDATE articles SET favorites_count = favorites_count - 1 WHERE id = OLD.article_id;
+    END IF;
+  ELSIF OLD.is_deleted AND NOT NEW.is_deleted THEN
+    UPDATE articles SET favorites_count = favorites_count + 1 WHERE id = NEW.article_id;
+  ELSIF NOT OLD.is_deleted AND NEW.is_deleted THEN
+    UPDATE articles SET favorites_count = favorites_count - 1 WHERE id = NEW.article_id;
+  END IF;
+  RETURN NULL;
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER update_article_favorites_count
+AFTER INSERT OR DELETE OR UPDATE OF is_deleted ON favorites
+FOR EACH ROW
+EXECUTE PROCEDURE update_article_favorites_count();
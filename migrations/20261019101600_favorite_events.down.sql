@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS record_favorite_event ON favorites;
+DROP FUNCTION IF EXISTS record_favorite_event();
+DROP INDEX IF EXISTS favorites_deleted_idx;
+DROP TABLE IF EXISTS favorite_events;
@@ -0,0 +1,66 @@
+-- Add up migration script here
+
+-- いいねの履歴 追記するだけで更新はしない
+-- favoritesは現在の状態だけを持つ
+CREATE TABLE IF NOT EXISTS favorite_events (
+    id BIGSERIAL PRIMARY KEY,
+    -- 退会したユーザーのイベントはNULLになる
+    user_id UUID,
+    article_id INTEGER NOT NULL,
+    kind VARCHAR NOT NULL CHECK (kind IN ('favorited', 'unfavorited')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL,
+    FOREIGN KEY (article_id) REFERENCES articles(id) ON DELETE CASCADE
+);
+
+-- 記事ごとの集計用
+CREATE INDEX IF NOT EXISTS favorite_events_article_id_created_at_idx
+  ON favorite_events (article_id, created_at);
+
+-- 解除済みのいいねを掃除するときに，最後のイベントを探す
+CREATE INDEX IF NOT EXISTS favorite_events_user_id_article_id_idx
+  ON favorite_events (user_id, article_id, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS favorites_deleted_idx
+  ON favorites (article_id)
+  WHERE is_deleted = TRUE;
+
+-- これまでのいいねを履歴に移す
+INSERT INTO favorite_events (user_id, article_id, kind, created_at)
+SELECT user_id, article_id, 'favorited', created_at FROM favorites;
+
+-- 解除した日時は残っていないので，移行した日時で記録する
+INSERT INTO favorite_events (user_id, article_id, kind)
+SELECT user_id, article_id, 'unfavorited' FROM favorites WHERE is_deleted = TRUE;
+
+CREATE OR REPLACE FUNCTION record_favorite_event()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'INSERT' THEN
+    IF NOT NEW.is_deleted THEN
+      INSERT INTO favorite_events (user_id, article_id, kind)
+      VALUES (NEW.user_id, NEW.article_id, 'favorited');
+    END IF;
+  ELSIF TG_OP = 'DELETE' THEN
+    -- 有効ないいねが消えたときだけ記録する 解除済みの行を消すのは掃除なので記録しない
+    -- 記事ごと消えた場合は記録しない 退会などで消えた場合はユーザーを残さない
+    IF NOT OLD.is_deleted AND EXISTS (SELECT 1 FROM articles WHERE id = OLD.article_id) THEN
+      INSERT INTO favorite_events (user_id, article_id, kind)
+      VALUES (NULL, OLD.article_id, 'unfavorited');
+    END IF;
+  ELSIF OLD.is_deleted <> NEW.is_deleted THEN
+    INSERT INTO favorite_events (user_id, article_id, kind)
+    VALUES (
+      NEW.user_id,
+      NEW.article_id,
+      CASE WHEN NEW.is_deleted THEN 'unfavorited' ELSE 'favorited' END
+    );
+  END IF;
+  RETURN NULL;
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER record_favorite_event
+AFTER INSERT OR DELETE OR UPDATE OF is_deleted ON favorites
+FOR EACH ROW
+EXECUTE PROCEDURE record_favorite_event();
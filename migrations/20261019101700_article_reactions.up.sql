@@ -0,0 +1,17 @@
+-- Add up migration script here
+
+-- 記事へのリアクション 1人が同じ種類のリアクションを付けられるのは1つだけ
+-- 種類の一覧は設定で決まるので，ここでは制約をかけない
+CREATE TABLE IF NOT EXISTS article_reactions (
+    user_id UUID NOT NULL,
+    article_id INTEGER NOT NULL,
+    kind VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, article_id, kind),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (article_id) REFERENCES articles(id) ON DELETE CASCADE
+);
+
+-- 記事ごとの集計用
+CREATE INDEX IF NOT EXISTS article_reactions_article_id_kind_idx
+  ON article_reactions (article_id, kind);
@@ -0,0 +1,4 @@
+-- Add up migration script here
+
+-- 匿名化して退会したユーザー 発行済みのトークンを拒否するのに使う
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
@@ -0,0 +1,2 @@
+-- Add down migration script here
+-- 変更したユーザー名は元に戻さない
@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- /profiles/suggestionsと重なるので，予約する前に登録されたsuggestionsというユーザーの名前を変更する
+UPDATE users
+SET username = username || '_' || right(id::text, 6)
+WHERE lower(username) = 'suggestions';
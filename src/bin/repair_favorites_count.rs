@@ -0,0 +1,21 @@
+//! 記事のいいね数を`favorites`テーブルから数え直す
+//!
+//! トリガーを外して手作業でデータを直した後などに使う
+//!
+//! ```sh
+//! DATABASE_URL=postgres://... cargo run --bin repair_favorites_count
+//! ```
+use realworld_axum_betashuttle::{
+    core::favorites::dao_trait::FavoritesDaoTrait, dao::favorites::FavoriteDao,
+};
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    let database_url =
+        std::env::var("DATABASE_URL").map_err(|_| anyhow::anyhow!("DATABASE_URL must be set"))?;
+    let pool = sqlx::PgPool::connect(&database_url).await?;
+
+    let repaired = FavoriteDao::new(pool).repair_favorites_counts().await?;
+    println!("repaired favorites_count of {} articles", repaired);
+    Ok(())
+}
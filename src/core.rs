@@ -1,5 +1,14 @@
+pub mod access_tokens;
+pub mod account_data;
 pub mod articles;
+pub mod email_verifications;
 pub mod favorites;
+pub mod login_attempts;
+pub mod login_audits;
+pub mod password_resets;
 pub mod profiles;
+pub mod reactions;
 pub mod tags;
+pub mod two_factor;
+pub mod uploads;
 pub mod users;
@@ -0,0 +1,3 @@
+pub mod dao_trait;
+pub mod dto;
+pub mod entity;
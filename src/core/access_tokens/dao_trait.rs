@@ -0,0 +1,29 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::async_trait;
+use uuid::Uuid;
+
+use crate::error::ConduitResult;
+
+use super::entity::AccessTokenEntity;
+
+pub type DynAccessTokensDao = Arc<dyn AccessTokensDaoTrait + Send + Sync>;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait AccessTokensDaoTrait {
+    /// トークンはハッシュ化してから渡すこと ttlがNoneなら失効しない
+    async fn create_access_token(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        token_hash: &str,
+        scopes: Vec<String>,
+        ttl: Option<Duration>,
+    ) -> ConduitResult<AccessTokenEntity>;
+    async fn get_access_tokens(&self, user_id: Uuid) -> ConduitResult<Vec<AccessTokenEntity>>;
+    /// 削除できた場合はtrueを返す 他のユーザーのトークンは削除しない
+    async fn delete_access_token(&self, user_id: Uuid, token_id: Uuid) -> ConduitResult<bool>;
+    /// 有効期限内のトークンの最終使用日時を更新して返す
+    async fn use_access_token(&self, token_hash: &str) -> ConduitResult<Option<AccessTokenEntity>>;
+}
@@ -0,0 +1,93 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use super::entity::{AccessTokenEntity, TokenScope};
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateAccessTokenReq {
+    #[serde(rename = "accessToken")]
+    #[validate(nested)]
+    pub access_token: NewAccessToken,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct NewAccessToken {
+    #[validate(required, length(min = 1, max = 100))]
+    pub name: Option<String>,
+    #[validate(required, length(min = 1), custom(function = "validate_scopes"))]
+    pub scopes: Option<Vec<String>>,
+    // 省略した場合は失効しない
+    #[serde(rename = "expiresInDays")]
+    #[validate(range(min = 1, max = 365))]
+    pub expires_in_days: Option<u32>,
+}
+
+fn validate_scopes(scopes: &[String]) -> Result<(), ValidationError> {
+    for scope in scopes {
+        if TokenScope::from_str(scope).is_err() {
+            let allowed = TokenScope::ALL.map(|s| s.as_str()).join(", ");
+            return Err(ValidationError::new("unknown_scope")
+                .with_message(format!("unknown scope: {} (allowed: {})", scope, allowed).into()));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccessToken {
+    pub id: Uuid,
+    pub name: String,
+    pub scopes: Vec<String>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<String>,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+impl From<AccessTokenEntity> for AccessToken {
+    fn from(entity: AccessTokenEntity) -> Self {
+        Self {
+            id: entity.id,
+            name: entity.name,
+            scopes: entity.scopes,
+            expires_at: entity.expires_at.map(|t| t.to_string()),
+            last_used_at: entity.last_used_at.map(|t| t.to_string()),
+            created_at: entity.created_at.to_string(),
+        }
+    }
+}
+
+/// 作成時のみトークンそのものを返す
+#[derive(Debug, Serialize)]
+pub struct CreateAccessTokenRes {
+    #[serde(rename = "accessToken")]
+    pub access_token: CreatedAccessToken,
+}
+
+#[derive(Serialize)]
+pub struct CreatedAccessToken {
+    #[serde(flatten)]
+    pub access_token: AccessToken,
+    pub token: String,
+}
+
+// トークンはログに出さない
+impl std::fmt::Debug for CreatedAccessToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CreatedAccessToken")
+            .field("access_token", &self.access_token)
+            .field("token", &crate::redact::Redacted::new(()))
+            .finish()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccessTokensRes {
+    #[serde(rename = "accessTokens")]
+    pub access_tokens: Vec<AccessToken>,
+}
@@ -0,0 +1,74 @@
+use std::{fmt::Display, str::FromStr};
+
+use sqlx::{prelude::FromRow, types::time::PrimitiveDateTime};
+use uuid::Uuid;
+
+/// JWTと区別するため，アクセストークンの先頭に付ける
+pub const ACCESS_TOKEN_PREFIX: &str = "cdt_";
+
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct AccessTokenEntity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<PrimitiveDateTime>,
+    pub last_used_at: Option<PrimitiveDateTime>,
+    pub created_at: PrimitiveDateTime,
+}
+
+impl AccessTokenEntity {
+    pub fn has_scope(&self, scope: TokenScope) -> bool {
+        self.scopes.iter().any(|s| s == scope.as_str())
+    }
+}
+
+/// アクセストークンで許可する操作
+/// ルートごとに必要なスコープを指定し，指定のないルートではアクセストークンを使えない
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenScope {
+    // ログインが必要な読み取り
+    Read,
+    // 記事の作成・更新・削除
+    ArticlesWrite,
+    // いいね・リアクションとその取り消し
+    FavoritesWrite,
+    // フォロー・フォローの解除
+    ProfilesWrite,
+}
+
+impl TokenScope {
+    pub const ALL: [TokenScope; 4] = [
+        Self::Read,
+        Self::ArticlesWrite,
+        Self::FavoritesWrite,
+        Self::ProfilesWrite,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::ArticlesWrite => "articles:write",
+            Self::FavoritesWrite => "favorites:write",
+            Self::ProfilesWrite => "profiles:write",
+        }
+    }
+}
+
+impl FromStr for TokenScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| format!("unknown scope: {}", s))
+    }
+}
+
+impl Display for TokenScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
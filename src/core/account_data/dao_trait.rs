@@ -0,0 +1,48 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::async_trait;
+use uuid::Uuid;
+
+use crate::error::ConduitResult;
+
+use super::entity::{
+    AccountDeletionEntity, DeletionMode, ExportedArticleEntity, ExportedFavoriteEntity,
+    ExportedFollowEntity,
+};
+
+pub type DynAccountDataDao = Arc<dyn AccountDataDaoTrait + Send + Sync>;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait AccountDataDaoTrait {
+    async fn get_authored_articles(
+        &self,
+        user_id: Uuid,
+    ) -> ConduitResult<Vec<ExportedArticleEntity>>;
+    async fn get_favorited_articles(
+        &self,
+        user_id: Uuid,
+    ) -> ConduitResult<Vec<ExportedFavoriteEntity>>;
+    /// ユーザーがフォローしている人
+    async fn get_following(&self, user_id: Uuid) -> ConduitResult<Vec<ExportedFollowEntity>>;
+    /// ユーザーをフォローしている人
+    async fn get_followers(&self, user_id: Uuid) -> ConduitResult<Vec<ExportedFollowEntity>>;
+
+    /// 猶予期間の後に削除するよう予約する
+    /// すでに予約されている場合は，既存の予約をそのまま返す
+    async fn schedule_deletion(
+        &self,
+        user_id: Uuid,
+        mode: DeletionMode,
+        grace_period: Duration,
+    ) -> ConduitResult<AccountDeletionEntity>;
+    async fn get_deletion(&self, user_id: Uuid) -> ConduitResult<Option<AccountDeletionEntity>>;
+    /// 予約を取り消せた場合はtrueを返す
+    async fn cancel_deletion(&self, user_id: Uuid) -> ConduitResult<bool>;
+    /// 猶予期間が過ぎた予約を，予定日時の古い順に返す
+    async fn get_due_deletions(&self, limit: i64) -> ConduitResult<Vec<AccountDeletionEntity>>;
+    /// 個人情報とフォロー・いいねなどを消し，記事は匿名のユーザーのものとして残す
+    async fn anonymize_user(&self, user_id: Uuid) -> ConduitResult<()>;
+    /// ユーザーを削除する 記事やいいねなどは外部キーによって一緒に削除される
+    async fn delete_user(&self, user_id: Uuid) -> ConduitResult<()>;
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{core::profiles::dto::ProfileExtension, redact::Redacted};
+
+use super::entity::{
+    AccountDeletionEntity, DeletionMode, ExportedArticleEntity, ExportedFavoriteEntity,
+    ExportedFollowEntity,
+};
+
+/// 退会時は，本人であることをパスワードで再確認する
+#[derive(Debug, Deserialize, Validate)]
+pub struct DeleteAccountReq {
+    #[validate(nested)]
+    pub user: DeleteAccount,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct DeleteAccount {
+    #[validate(required)]
+    pub password: Option<Redacted<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountDeletionRes {
+    #[serde(rename = "accountDeletion")]
+    pub account_deletion: AccountDeletion,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountDeletion {
+    pub mode: DeletionMode,
+    #[serde(rename = "requestedAt")]
+    pub requested_at: String,
+    #[serde(rename = "scheduledAt")]
+    pub scheduled_at: String,
+}
+
+impl From<AccountDeletionEntity> for AccountDeletion {
+    fn from(entity: AccountDeletionEntity) -> Self {
+        Self {
+            mode: entity.mode(),
+            requested_at: entity.requested_at.to_string(),
+            scheduled_at: entity.scheduled_at.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountExportRes {
+    pub export: AccountExport,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountExport {
+    #[serde(rename = "exportedAt")]
+    pub exported_at: String,
+    pub profile: ExportedProfile,
+    pub articles: Vec<ExportedArticle>,
+    pub favorites: Vec<ExportedFavorite>,
+    pub following: Vec<ExportedFollow>,
+    pub followers: Vec<ExportedFollow>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportedProfile {
+    pub username: String,
+    pub email: String,
+    #[serde(rename = "emailVerified")]
+    pub email_verified: bool,
+    pub bio: String,
+    pub image: Option<String>,
+    #[serde(flatten)]
+    pub extension: ProfileExtension,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportedArticle {
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub body: String,
+    #[serde(rename = "tagList")]
+    pub tag_list: Vec<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+}
+
+impl From<ExportedArticleEntity> for ExportedArticle {
+    fn from(entity: ExportedArticleEntity) -> Self {
+        Self {
+            slug: entity.slug,
+            title: entity.title,
+            description: entity.description,
+            body: entity.body,
+            tag_list: entity.tag_list,
+            created_at: entity.created_at.to_string(),
+            updated_at: entity.updated_at.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportedFavorite {
+    pub slug: String,
+    pub title: String,
+    #[serde(rename = "favoritedAt")]
+    pub favorited_at: String,
+}
+
+impl From<ExportedFavoriteEntity> for ExportedFavorite {
+    fn from(entity: ExportedFavoriteEntity) -> Self {
+        Self {
+            slug: entity.slug,
+            title: entity.title,
+            favorited_at: entity.created_at.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportedFollow {
+    pub username: String,
+    #[serde(rename = "since")]
+    pub created_at: String,
+}
+
+impl From<ExportedFollowEntity> for ExportedFollow {
+    fn from(entity: ExportedFollowEntity) -> Self {
+        Self {
+            username: entity.username,
+            created_at: entity.created_at.to_string(),
+        }
+    }
+}
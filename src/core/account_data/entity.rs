@@ -0,0 +1,84 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{prelude::FromRow, types::time::PrimitiveDateTime};
+use uuid::Uuid;
+
+/// 退会時に，ユーザーが書いた記事などをどう扱うか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeletionMode {
+    // ユーザーを匿名化し，記事は残す
+    #[default]
+    Anonymize,
+    // ユーザーごと記事やいいねもすべて削除する
+    Cascade,
+}
+
+impl DeletionMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Anonymize => "anonymize",
+            Self::Cascade => "cascade",
+        }
+    }
+}
+
+impl FromStr for DeletionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "anonymize" => Ok(Self::Anonymize),
+            "cascade" => Ok(Self::Cascade),
+            _ => Err(format!("unknown deletion mode: {}", s)),
+        }
+    }
+}
+
+impl Display for DeletionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct AccountDeletionEntity {
+    pub user_id: Uuid,
+    pub mode: String,
+    pub requested_at: PrimitiveDateTime,
+    pub scheduled_at: PrimitiveDateTime,
+}
+
+impl AccountDeletionEntity {
+    pub fn mode(&self) -> DeletionMode {
+        self.mode.parse().unwrap_or_default()
+    }
+}
+
+/// エクスポート用 ユーザーが書いた記事
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct ExportedArticleEntity {
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub body: String,
+    pub tag_list: Vec<String>,
+    pub created_at: PrimitiveDateTime,
+    pub updated_at: PrimitiveDateTime,
+}
+
+/// エクスポート用 ユーザーがいいねした記事
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct ExportedFavoriteEntity {
+    pub slug: String,
+    pub title: String,
+    pub created_at: PrimitiveDateTime,
+}
+
+/// エクスポート用 フォローしている・されているユーザー
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct ExportedFollowEntity {
+    pub username: String,
+    pub created_at: PrimitiveDateTime,
+}
@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
-use super::{super::profiles::dto::Profile, entity::ArticleEntity};
+use super::{
+    super::{profiles::dto::Profile, reactions::dto::ArticleReaction},
+    entity::ArticleEntity,
+};
 
 #[derive(Debug, Clone, Validate, Deserialize, PartialEq)]
 pub struct NewArticle {
@@ -58,6 +61,9 @@ pub struct Article {
     #[serde(rename = "favoritesCount")]
     pub favorites_count: i32,
     pub author: Profile,
+    // 一覧などリアクションを集計していない場合はNoneにして省く
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reactions: Option<Vec<ArticleReaction>>,
 }
 impl Article {
     pub(crate) fn from_entity(
@@ -78,6 +84,7 @@ impl Article {
             favorited: is_favorite_current_user,
             favorites_count,
             author: profile,
+            reactions: None,
         }
     }
 }
@@ -92,6 +99,35 @@ pub struct GetArticleRes {
     pub article: Article,
 }
 
+pub const DEFAULT_ARTICLES_LIMIT: i64 = 20;
+pub const MAX_ARTICLES_LIMIT: i64 = 100;
+
+/// 記事一覧のページング
+#[derive(Debug, Default, Deserialize)]
+pub struct ArticlesQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl ArticlesQuery {
+    pub fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_ARTICLES_LIMIT)
+            .clamp(1, MAX_ARTICLES_LIMIT)
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0).max(0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArticlesRes {
+    pub articles: Vec<Article>,
+    #[serde(rename = "articlesCount")]
+    pub articles_count: i64,
+}
+
 #[derive(Debug, Clone, Validate, Deserialize)]
 pub struct UpdateArticle {
     #[validate(length(min = 1))]
@@ -11,4 +11,6 @@ pub struct ArticleEntity {
     pub description: String,
     pub body: String,
     pub author_id: Uuid,
+    // favoritesのトリガーで更新される
+    pub favorites_count: i32,
 }
@@ -0,0 +1,29 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::async_trait;
+use uuid::Uuid;
+
+use crate::error::ConduitResult;
+
+use super::entity::EmailVerificationTokenEntity;
+
+pub type DynEmailVerificationsDao = Arc<dyn EmailVerificationsDaoTrait + Send + Sync>;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait EmailVerificationsDaoTrait {
+    /// トークンはハッシュ化してから渡すこと
+    /// 同じユーザーの未使用のトークンは無効になる
+    async fn create_verification_token(
+        &self,
+        user_id: Uuid,
+        email: &str,
+        token_hash: &str,
+        ttl: Duration,
+    ) -> ConduitResult<EmailVerificationTokenEntity>;
+    /// 未使用かつ有効期限内のトークンを使用済みにして返す
+    async fn consume_verification_token(
+        &self,
+        token_hash: &str,
+    ) -> ConduitResult<Option<EmailVerificationTokenEntity>>;
+}
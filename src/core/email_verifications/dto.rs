@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct VerifyEmailReq {
+    #[validate(nested)]
+    pub user: VerifyEmail,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct VerifyEmail {
+    #[validate(required, length(min = 1))]
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerifyEmailRes {
+    pub message: String,
+}
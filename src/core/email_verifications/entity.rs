@@ -0,0 +1,13 @@
+use sqlx::{prelude::FromRow, types::time::PrimitiveDateTime};
+use uuid::Uuid;
+
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct EmailVerificationTokenEntity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub email: String,
+    pub token_hash: String,
+    pub expires_at: PrimitiveDateTime,
+    pub used_at: Option<PrimitiveDateTime>,
+    pub created_at: PrimitiveDateTime,
+}
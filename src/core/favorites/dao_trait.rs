@@ -1,25 +1,64 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use axum::async_trait;
 use uuid::Uuid;
 
-use crate::error::ConduitResult;
+use crate::{core::users::entity::UserEntity, error::ConduitResult};
 
-use super::entity::FavoritesEntity;
+use super::entity::{FavoriteStatsEntity, FavoritedArticleEntity, FavoritesEntity};
 
 pub type DynFavoritesDao = Arc<dyn FavoritesDaoTrait + Send + Sync>;
 
 #[cfg_attr(test, mockall::automock)]
 #[async_trait]
 pub trait FavoritesDaoTrait {
+    /// すでにいいねしている場合は，既存のいいねをそのまま返す
     async fn add_favorite(&self, user_id: Uuid, article_id: i32) -> ConduitResult<FavoritesEntity>;
     async fn get_favorites_by_article_id(
         &self,
         article_id: i32,
     ) -> ConduitResult<Vec<FavoritesEntity>>;
+    /// 一度もいいねしていなかった場合はNoneを返す
+    /// すでに解除していた場合は，解除済みのいいねをそのまま返す
     async fn remove_favorite(
         &self,
         user_id: Uuid,
         article_id: i32,
-    ) -> ConduitResult<FavoritesEntity>;
+    ) -> ConduitResult<Option<FavoritesEntity>>;
+
+    /// 記事をいいねしているユーザーを，いいねした新しい順に返す
+    async fn get_favoriting_users(
+        &self,
+        article_id: i32,
+        limit: i64,
+        offset: i64,
+    ) -> ConduitResult<Vec<UserEntity>>;
+    /// articlesに保存しているいいね数を返す
+    async fn count_favorites(&self, article_id: i32) -> ConduitResult<i32>;
+    /// いいね数をfavoritesから数え直す 数を直した記事の件数を返す
+    async fn repair_favorites_counts(&self) -> ConduitResult<u64>;
+    /// user_idのユーザーがいいねした記事を，いいねした新しい順に返す
+    /// viewer_idのユーザーが見られない非公開アカウントの記事と，ミュートしている作者の記事は含めない
+    async fn get_favorited_articles(
+        &self,
+        user_id: Uuid,
+        viewer_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> ConduitResult<Vec<FavoritedArticleEntity>>;
+    async fn count_favorited_articles(
+        &self,
+        user_id: Uuid,
+        viewer_id: Option<Uuid>,
+    ) -> ConduitResult<i64>;
+
+    /// 解除してから`older_than`以上経ったいいねを消す 消した件数を返す
+    /// 履歴はfavorite_eventsに残る
+    async fn purge_deleted_favorites(&self, older_than: Duration) -> ConduitResult<u64>;
+    /// 直近`days`日分の1日ごとの集計を，古い順に返す いいねのない日も含める
+    async fn get_favorite_stats(
+        &self,
+        article_id: i32,
+        days: i32,
+    ) -> ConduitResult<Vec<FavoriteStatsEntity>>;
 }
@@ -1,7 +1,9 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::core::articles::dto::Article;
 
+use super::entity::FavoriteStatsEntity;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct AddFavoriteRes {
     pub article: Article,
@@ -11,3 +13,51 @@ pub struct AddFavoriteRes {
 pub struct DeleteFavoriteRes {
     pub article: Article,
 }
+
+pub const DEFAULT_STATS_DAYS: i32 = 30;
+pub const MAX_STATS_DAYS: i32 = 365;
+
+/// いいねの集計期間
+#[derive(Debug, Default, Deserialize)]
+pub struct FavoriteStatsQuery {
+    pub days: Option<i32>,
+}
+
+impl FavoriteStatsQuery {
+    pub fn days(&self) -> i32 {
+        self.days
+            .unwrap_or(DEFAULT_STATS_DAYS)
+            .clamp(1, MAX_STATS_DAYS)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FavoriteDayStats {
+    pub date: String,
+    pub favorited: i64,
+    pub unfavorited: i64,
+    pub total: i64,
+}
+
+impl FavoriteDayStats {
+    pub fn from_entity(entity: FavoriteStatsEntity) -> Self {
+        Self {
+            date: entity.day.to_string(),
+            favorited: entity.favorited,
+            unfavorited: entity.unfavorited,
+            total: entity.total,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FavoriteStats {
+    #[serde(rename = "favoritesCount")]
+    pub favorites_count: i32,
+    pub days: Vec<FavoriteDayStats>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FavoriteStatsRes {
+    pub stats: FavoriteStats,
+}
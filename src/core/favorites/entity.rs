@@ -1,4 +1,7 @@
-use sqlx::{prelude::FromRow, types::time::PrimitiveDateTime};
+use sqlx::{
+    prelude::FromRow,
+    types::time::{Date, PrimitiveDateTime},
+};
 use uuid::Uuid;
 
 #[derive(FromRow, Debug, Clone, PartialEq)]
@@ -8,3 +11,37 @@ pub struct FavoritesEntity {
     pub created_at: PrimitiveDateTime,
     pub is_deleted: bool,
 }
+
+/// いいねした記事の一覧用 タグといいね数もまとめて取得する
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct FavoritedArticleEntity {
+    pub id: i32,
+    pub created_at: PrimitiveDateTime,
+    pub updated_at: PrimitiveDateTime,
+    pub title: String,
+    pub slug: String,
+    pub description: String,
+    pub body: String,
+    pub author_id: Uuid,
+    // 記事ごとに作者を取得し直さないよう，作者のプロフィールもまとめて取得する
+    pub author_username: String,
+    pub author_bio: String,
+    pub author_image: Option<String>,
+    pub author_is_private: bool,
+    pub tag_list: Vec<String>,
+    pub favorites_count: i32,
+    // 一覧を見ているユーザーがいいねしているか
+    pub favorited: bool,
+    // 一覧を見ているユーザーが作者をフォローしているか
+    pub following: bool,
+}
+
+/// 記事の1日ごとのいいねの集計
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct FavoriteStatsEntity {
+    pub day: Date,
+    pub favorited: i64,
+    pub unfavorited: i64,
+    // その日の終わりの時点のいいね数
+    pub total: i64,
+}
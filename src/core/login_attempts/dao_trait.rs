@@ -0,0 +1,33 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::async_trait;
+
+use crate::error::ConduitResult;
+
+pub type DynLoginAttemptsDao = Arc<dyn LoginAttemptsDaoTrait + Send + Sync>;
+
+/// ログイン失敗の回数とブロック状態を保存する
+/// 複数インスタンスで共有するならPostgres，単体ならインメモリの実装を使う
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait LoginAttemptsDaoTrait {
+    /// パスワードを確認する前に，試行を失敗として先に数えておく
+    /// ブロック中か，すでに`max_failures`回失敗していればNoneを返す
+    /// 同時に来た試行が確認前のチェックをすり抜けないよう，確認と加算は1回でまとめて行う
+    /// 最後の失敗から`window`以上経っていれば，1から数え直す
+    async fn try_begin_attempt(
+        &self,
+        key: &str,
+        window: Duration,
+        max_failures: i32,
+    ) -> ConduitResult<Option<i32>>;
+    /// 先に数えておいた試行が成功だったので，1回分戻す
+    async fn release_attempt(&self, key: &str) -> ConduitResult<()>;
+    /// `duration`の間ブロックする
+    /// 結果が前後して届くことがあるので，すでにそれより長くブロックしていれば縮めない
+    async fn block(&self, key: &str, duration: Duration) -> ConduitResult<()>;
+    /// ブロック中であれば残り時間を返す
+    async fn blocked_for(&self, key: &str) -> ConduitResult<Option<Duration>>;
+    /// 失敗回数とブロックをリセットする
+    async fn reset(&self, key: &str) -> ConduitResult<()>;
+}
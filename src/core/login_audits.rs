@@ -0,0 +1,2 @@
+pub mod dao_trait;
+pub mod entity;
@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use axum::async_trait;
+use uuid::Uuid;
+
+use crate::error::ConduitResult;
+
+use super::entity::{LoginAuditEvent, LoginAuditEventEntity};
+
+pub type DynLoginAuditsDao = Arc<dyn LoginAuditsDaoTrait + Send + Sync>;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait LoginAuditsDaoTrait {
+    async fn record_login_event(
+        &self,
+        event: LoginAuditEvent,
+        email: &str,
+        user_id: Option<Uuid>,
+        ip_address: Option<String>,
+    ) -> ConduitResult<LoginAuditEventEntity>;
+    async fn get_login_events_by_email(
+        &self,
+        email: &str,
+    ) -> ConduitResult<Vec<LoginAuditEventEntity>>;
+}
@@ -0,0 +1,32 @@
+use sqlx::{prelude::FromRow, types::time::PrimitiveDateTime};
+use uuid::Uuid;
+
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct LoginAuditEventEntity {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub email: String,
+    pub ip_address: Option<String>,
+    pub event: String,
+    pub created_at: PrimitiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoginAuditEvent {
+    // パスワード違い，または存在しないメールアドレス
+    Failed,
+    // 失敗が続いたためにロックした
+    Locked,
+    // ブロック中にログインを試みた
+    Throttled,
+}
+
+impl LoginAuditEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Failed => "failed",
+            Self::Locked => "locked",
+            Self::Throttled => "throttled",
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::async_trait;
+use uuid::Uuid;
+
+use crate::error::ConduitResult;
+
+use super::entity::PasswordResetTokenEntity;
+
+pub type DynPasswordResetsDao = Arc<dyn PasswordResetsDaoTrait + Send + Sync>;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait PasswordResetsDaoTrait {
+    /// トークンはハッシュ化してから渡すこと
+    async fn create_reset_token(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        ttl: Duration,
+    ) -> ConduitResult<PasswordResetTokenEntity>;
+    /// 未使用かつ有効期限内のトークンを，使用済みにせずに返す
+    /// 該当するトークンがなければNoneを返す
+    async fn find_reset_token(
+        &self,
+        token_hash: &str,
+    ) -> ConduitResult<Option<PasswordResetTokenEntity>>;
+    /// 未使用かつ有効期限内のトークンを使用済みにして返す
+    /// 該当するトークンがなければNoneを返す
+    async fn consume_reset_token(
+        &self,
+        token_hash: &str,
+    ) -> ConduitResult<Option<PasswordResetTokenEntity>>;
+    /// ユーザーの未使用のトークンをすべて削除する
+    async fn delete_unused_tokens(&self, user_id: Uuid) -> ConduitResult<()>;
+}
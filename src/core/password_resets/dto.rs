@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::redact::Redacted;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PasswordResetReq {
+    #[validate(nested)]
+    pub user: PasswordResetUser,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PasswordResetUser {
+    #[validate(email, required)]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PasswordResetConfirmReq {
+    #[validate(nested)]
+    pub user: PasswordResetConfirmUser,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PasswordResetConfirmUser {
+    #[validate(required, length(min = 1))]
+    pub token: Option<String>,
+    #[validate(required)]
+    pub password: Option<Redacted<String>>,
+}
+
+/// メールアドレスが存在するかどうかに関わらず同じ内容を返す
+#[derive(Debug, Serialize)]
+pub struct PasswordResetRes {
+    pub message: String,
+}
@@ -5,7 +5,12 @@ use uuid::Uuid;
 
 use crate::error::ConduitResult;
 
-use super::entity::UserFollowEntity;
+use super::{
+    super::users::entity::UserEntity,
+    entity::{
+        FollowCountsEntity, FollowRequestEntity, UserBlockEntity, UserFollowEntity, UserMuteEntity,
+    },
+};
 
 pub type DynProfilesDao = Arc<dyn ProfilesDaoTrait + Send + Sync>;
 
@@ -15,21 +20,97 @@ pub type DynProfilesDao = Arc<dyn ProfilesDaoTrait + Send + Sync>;
 #[async_trait]
 pub trait ProfilesDaoTrait {
     async fn get_user_followees(&self, user_id: Uuid) -> ConduitResult<Vec<UserFollowEntity>>;
+    /// すでにフォローしている場合は，既存のフォローをそのまま返す
     async fn following_user(
         &self,
         follower_id: Uuid,
         following_id: Uuid,
     ) -> ConduitResult<UserFollowEntity>;
 
+    /// フォローしていなかった場合はNoneを返す
     async fn unfollow_user(
         &self,
         follower_id: Uuid,
         following_id: Uuid,
-    ) -> ConduitResult<UserFollowEntity>;
+    ) -> ConduitResult<Option<UserFollowEntity>>;
 
     async fn is_follow(
         &self,
         follower_id: Uuid,
         following_id: Uuid,
     ) -> ConduitResult<Option<UserFollowEntity>>;
+
+    /// user_idのユーザーをフォローしているユーザーを，フォローの新しい順に返す
+    async fn get_followers(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> ConduitResult<Vec<UserEntity>>;
+    /// user_idのユーザーがフォローしているユーザーを，フォローの新しい順に返す
+    async fn get_following(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> ConduitResult<Vec<UserEntity>>;
+    async fn get_follow_counts(&self, user_id: Uuid) -> ConduitResult<FollowCountsEntity>;
+    /// おすすめのユーザーを，スコアの高い順に返す
+    /// 共通のフォロー，いいねした記事とのタグの重なり，最近の投稿からスコアを付ける
+    /// 自分自身，フォロー済みのユーザー，どちらかがブロックしているユーザーは含めない
+    async fn get_follow_suggestions(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> ConduitResult<Vec<UserEntity>>;
+    /// おすすめの計算に使う集計を更新する
+    async fn refresh_follow_suggestions(&self) -> ConduitResult<()>;
+
+    /// 非公開アカウントへのフォローリクエストを作る すでにある場合はそのまま返す
+    async fn request_follow(
+        &self,
+        requester_id: Uuid,
+        target_id: Uuid,
+    ) -> ConduitResult<FollowRequestEntity>;
+    async fn has_follow_request(&self, requester_id: Uuid, target_id: Uuid) -> ConduitResult<bool>;
+    /// target_idのユーザーへのフォローリクエストを送ったユーザーを，古い順に返す
+    async fn get_follow_requests(
+        &self,
+        target_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> ConduitResult<Vec<UserEntity>>;
+    async fn count_follow_requests(&self, target_id: Uuid) -> ConduitResult<i64>;
+    /// リクエストを消してフォローを作る リクエストがなければNoneを返す
+    async fn approve_follow_request(
+        &self,
+        requester_id: Uuid,
+        target_id: Uuid,
+    ) -> ConduitResult<Option<UserFollowEntity>>;
+    /// リクエストを消せた場合はtrueを返す 拒否と取り消しの両方に使う
+    async fn delete_follow_request(
+        &self,
+        requester_id: Uuid,
+        target_id: Uuid,
+    ) -> ConduitResult<bool>;
+
+    /// ブロックし，お互いのフォローとフォローリクエストを消す すでにブロックしている場合はそのまま返す
+    async fn block_user(
+        &self,
+        blocker_id: Uuid,
+        blocked_id: Uuid,
+    ) -> ConduitResult<UserBlockEntity>;
+    /// ブロックを解除できた場合はtrueを返す
+    async fn unblock_user(&self, blocker_id: Uuid, blocked_id: Uuid) -> ConduitResult<bool>;
+    /// blocker_idのユーザーがblocked_idのユーザーをブロックしているか
+    async fn is_blocking(&self, blocker_id: Uuid, blocked_id: Uuid) -> ConduitResult<bool>;
+    /// どちらか一方でもブロックしていればtrueを返す
+    async fn is_blocked_between(&self, user_a: Uuid, user_b: Uuid) -> ConduitResult<bool>;
+
+    /// すでにミュートしている場合はそのまま返す
+    async fn mute_user(&self, muter_id: Uuid, muted_id: Uuid) -> ConduitResult<UserMuteEntity>;
+    /// ミュートを解除できた場合はtrueを返す
+    async fn unmute_user(&self, muter_id: Uuid, muted_id: Uuid) -> ConduitResult<bool>;
+    /// 記事の一覧やフィードから除外するユーザーのID
+    async fn get_muted_user_ids(&self, muter_id: Uuid) -> ConduitResult<Vec<Uuid>>;
 }
@@ -1,12 +1,27 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use super::super::users::entity::UserEntity;
+use super::entity::FollowCountsEntity;
+
 #[derive(Debug, Default, Serialize, PartialEq, Clone)]
 pub struct Profile {
     pub username: String,
     pub bio: String,
     pub image: Option<String>,
     pub following: bool,
+    #[serde(rename = "isPrivate")]
+    pub is_private: bool,
+    // 非公開アカウントへのフォローリクエストが承認待ちか
+    #[serde(rename = "followRequested")]
+    pub follow_requested: bool,
+    // プロフィールのエンドポイントでのみ返す 記事の作者などでは省略する
+    #[serde(rename = "followersCount", skip_serializing_if = "Option::is_none")]
+    pub followers_count: Option<i64>,
+    #[serde(rename = "followingCount", skip_serializing_if = "Option::is_none")]
+    pub following_count: Option<i64>,
+    // `?fields=extended`を指定したときだけ返す
+    #[serde(flatten)]
+    pub extension: Option<ProfileExtension>,
 }
 
 impl Profile {
@@ -16,11 +31,111 @@ impl Profile {
             bio: user.bio,
             image: user.image,
             following,
+            is_private: user.is_private,
+            follow_requested: false,
+            followers_count: None,
+            following_count: None,
+            extension: None,
+        }
+    }
+
+    pub fn with_extension(self, extension: Option<ProfileExtension>) -> Self {
+        Self { extension, ..self }
+    }
+
+    pub fn with_follow_requested(self, follow_requested: bool) -> Self {
+        Self {
+            follow_requested,
+            ..self
+        }
+    }
+
+    pub fn with_counts(self, counts: FollowCountsEntity) -> Self {
+        Self {
+            followers_count: Some(counts.followers_count),
+            following_count: Some(counts.following_count),
+            ..self
+        }
+    }
+}
+
+/// RealWorldの仕様にないプロフィール項目
+/// 仕様どおりのクライアントに影響しないよう，指定されたときだけ返す
+#[derive(Debug, Default, Serialize, PartialEq, Clone)]
+pub struct ProfileExtension {
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+    pub location: Option<String>,
+    pub pronouns: Option<String>,
+    pub links: Vec<String>,
+}
+
+impl From<&UserEntity> for ProfileExtension {
+    fn from(user: &UserEntity) -> Self {
+        Self {
+            display_name: user.display_name.clone(),
+            location: user.location.clone(),
+            pronouns: user.pronouns.clone(),
+            links: user.links.clone(),
         }
     }
 }
 
+/// 返すフィールドの指定 `?fields=extended`で拡張項目を含める
+#[derive(Debug, Default, Deserialize)]
+pub struct ProfileFieldsQuery {
+    pub fields: Option<String>,
+}
+
+impl ProfileFieldsQuery {
+    pub fn extended(&self) -> bool {
+        self.fields
+            .as_deref()
+            .is_some_and(|fields| fields.split(',').any(|f| f.trim() == "extended"))
+    }
+
+    /// 指定されている場合だけ，ユーザーの拡張項目を取り出す
+    pub fn extension(&self, user: &UserEntity) -> Option<ProfileExtension> {
+        self.extended().then(|| ProfileExtension::from(user))
+    }
+}
+
 #[derive(Debug, Default, Serialize)]
 pub struct ProfileRes {
     pub profile: Profile,
 }
+
+pub const DEFAULT_PROFILES_LIMIT: i64 = 20;
+pub const MAX_PROFILES_LIMIT: i64 = 100;
+
+/// フォロワー・フォロー中の一覧のページング
+#[derive(Debug, Default, Deserialize)]
+pub struct ProfilesQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl ProfilesQuery {
+    pub fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_PROFILES_LIMIT)
+            .clamp(1, MAX_PROFILES_LIMIT)
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0).max(0)
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ProfilesRes {
+    pub profiles: Vec<Profile>,
+    #[serde(rename = "profilesCount")]
+    pub profiles_count: i64,
+}
+
+/// おすすめのユーザー 上位の一部だけを返すので，件数は付けない
+#[derive(Debug, Default, Serialize)]
+pub struct FollowSuggestionsRes {
+    pub profiles: Vec<Profile>,
+}
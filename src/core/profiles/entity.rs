@@ -8,3 +8,33 @@ pub struct UserFollowEntity {
     pub follower_id: Uuid,
     pub followee_id: Uuid,
 }
+
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct UserBlockEntity {
+    pub id: Uuid,
+    pub created_at: PrimitiveDateTime,
+    pub blocker_id: Uuid,
+    pub blocked_id: Uuid,
+}
+
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct UserMuteEntity {
+    pub id: Uuid,
+    pub created_at: PrimitiveDateTime,
+    pub muter_id: Uuid,
+    pub muted_id: Uuid,
+}
+
+#[derive(FromRow, Debug, Clone, Copy, Default, PartialEq)]
+pub struct FollowCountsEntity {
+    pub followers_count: i64,
+    pub following_count: i64,
+}
+
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct FollowRequestEntity {
+    pub id: Uuid,
+    pub created_at: PrimitiveDateTime,
+    pub requester_id: Uuid,
+    pub target_id: Uuid,
+}
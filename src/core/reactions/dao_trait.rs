@@ -0,0 +1,37 @@
+use std::sync::Arc;
+
+use axum::async_trait;
+use uuid::Uuid;
+
+use crate::error::ConduitResult;
+
+use super::entity::{ReactionCountEntity, ReactionEntity};
+
+pub type DynReactionsDao = Arc<dyn ReactionsDaoTrait + Send + Sync>;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait ReactionsDaoTrait {
+    /// すでに同じ種類のリアクションをしている場合は，既存のリアクションをそのまま返す
+    async fn add_reaction(
+        &self,
+        user_id: Uuid,
+        article_id: i32,
+        kind: &str,
+    ) -> ConduitResult<ReactionEntity>;
+    /// リアクションしていなかった場合はNoneを返す
+    async fn remove_reaction(
+        &self,
+        user_id: Uuid,
+        article_id: i32,
+        kind: &str,
+    ) -> ConduitResult<Option<ReactionEntity>>;
+    async fn get_reaction_counts(&self, article_id: i32)
+        -> ConduitResult<Vec<ReactionCountEntity>>;
+    /// user_idのユーザーが記事に付けたリアクションの種類を返す
+    async fn get_user_reactions(
+        &self,
+        user_id: Uuid,
+        article_id: i32,
+    ) -> ConduitResult<Vec<String>>;
+}
@@ -0,0 +1,16 @@
+use serde::Serialize;
+
+/// 記事に付いたリアクション 設定されている種類ごとに1つ
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ArticleReaction {
+    pub kind: String,
+    pub emoji: String,
+    pub count: i64,
+    // 呼び出したユーザーがこの種類のリアクションをしているか
+    pub reacted: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReactionsRes {
+    pub reactions: Vec<ArticleReaction>,
+}
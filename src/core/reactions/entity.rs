@@ -0,0 +1,17 @@
+use sqlx::{prelude::FromRow, types::time::PrimitiveDateTime};
+use uuid::Uuid;
+
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct ReactionEntity {
+    pub user_id: Uuid,
+    pub article_id: i32,
+    pub kind: String,
+    pub created_at: PrimitiveDateTime,
+}
+
+/// 記事に付いたリアクションの種類ごとの件数
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct ReactionCountEntity {
+    pub kind: String,
+    pub count: i64,
+}
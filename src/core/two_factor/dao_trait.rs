@@ -0,0 +1,50 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::async_trait;
+use uuid::Uuid;
+
+use crate::error::ConduitResult;
+
+use super::entity::{TwoFactorChallengeEntity, UserTotpEntity};
+
+pub type DynTwoFactorDao = Arc<dyn TwoFactorDaoTrait + Send + Sync>;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait TwoFactorDaoTrait {
+    /// 確認待ちのTOTPの設定とリカバリーコードを保存する 以前の確認待ちの設定は置き換える
+    /// 既に有効になっている場合は何もせずfalseを返す
+    /// リカバリーコードはハッシュ化してから渡すこと
+    async fn create_pending_totp(
+        &self,
+        user_id: Uuid,
+        secret_ciphertext: Vec<u8>,
+        recovery_code_hashes: Vec<String>,
+    ) -> ConduitResult<bool>;
+    async fn get_totp(&self, user_id: Uuid) -> ConduitResult<Option<UserTotpEntity>>;
+    /// 確認に使ったコードの時間ステップを記録して有効にする
+    async fn enable_totp(&self, user_id: Uuid, step: i64) -> ConduitResult<()>;
+    /// 時間ステップが最後に使われたものより新しければ記録してtrueを返す
+    /// 同じコードの再利用を防ぐためのもの
+    async fn use_totp_step(&self, user_id: Uuid, step: i64) -> ConduitResult<bool>;
+    /// TOTPの設定とリカバリーコードを削除する
+    async fn delete_totp(&self, user_id: Uuid) -> ConduitResult<()>;
+    /// 未使用のリカバリーコードを使用済みにする 該当するコードがなければfalseを返す
+    async fn consume_recovery_code(&self, user_id: Uuid, code_hash: &str) -> ConduitResult<bool>;
+
+    /// トークンはハッシュ化してから渡すこと
+    async fn create_challenge(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        ttl: Duration,
+    ) -> ConduitResult<TwoFactorChallengeEntity>;
+    /// 有効期限内のチャレンジを返す
+    async fn get_challenge(
+        &self,
+        token_hash: &str,
+    ) -> ConduitResult<Option<TwoFactorChallengeEntity>>;
+    /// 失敗回数を1増やし，増やした後の回数を返す
+    async fn record_challenge_failure(&self, token_hash: &str) -> ConduitResult<i32>;
+    async fn delete_challenge(&self, token_hash: &str) -> ConduitResult<()>;
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::redact::Redacted;
+
+/// 登録を始めたときに1度だけ返す内容
+#[derive(Serialize)]
+pub struct TwoFactorEnrollmentRes {
+    #[serde(rename = "twoFactor")]
+    pub two_factor: TwoFactorEnrollment,
+}
+
+#[derive(Serialize)]
+pub struct TwoFactorEnrollment {
+    // 認証アプリに読み込ませるURI QRコードにして表示する
+    #[serde(rename = "otpauthUri")]
+    pub otpauth_uri: String,
+    // URIを読み込めない場合に手入力するための秘密鍵(base32)
+    pub secret: String,
+    #[serde(rename = "recoveryCodes")]
+    pub recovery_codes: Vec<String>,
+}
+
+// 秘密鍵とリカバリーコードはログに出さない
+impl std::fmt::Debug for TwoFactorEnrollment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TwoFactorEnrollment")
+            .field("otpauth_uri", &Redacted::new(()))
+            .field("secret", &Redacted::new(()))
+            .field("recovery_codes", &Redacted::new(()))
+            .finish()
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorCodeReq {
+    #[serde(rename = "twoFactor")]
+    #[validate(nested)]
+    pub two_factor: TwoFactorCode,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorCode {
+    // 認証アプリのコードか，リカバリーコード
+    #[validate(required)]
+    pub code: Option<Redacted<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorStatusRes {
+    #[serde(rename = "twoFactor")]
+    pub two_factor: TwoFactorStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+}
+
+/// パスワードの確認後，2要素目の確認を求めるときのレスポンス
+#[derive(Debug, Serialize)]
+pub struct TwoFactorChallengeRes {
+    #[serde(rename = "twoFactor")]
+    pub two_factor: TwoFactorChallenge,
+}
+
+#[derive(Serialize)]
+pub struct TwoFactorChallenge {
+    #[serde(rename = "challengeToken")]
+    pub challenge_token: String,
+    // 有効期限(秒)
+    #[serde(rename = "expiresIn")]
+    pub expires_in: u64,
+}
+
+impl std::fmt::Debug for TwoFactorChallenge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TwoFactorChallenge")
+            .field("challenge_token", &Redacted::new(()))
+            .field("expires_in", &self.expires_in)
+            .finish()
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorLoginReq {
+    #[validate(nested)]
+    pub user: TwoFactorLoginUser,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TwoFactorLoginUser {
+    #[serde(rename = "challengeToken")]
+    #[validate(required)]
+    pub challenge_token: Option<Redacted<String>>,
+    #[validate(required)]
+    pub code: Option<Redacted<String>>,
+}
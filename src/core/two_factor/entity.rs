@@ -0,0 +1,27 @@
+use sqlx::{prelude::FromRow, types::time::PrimitiveDateTime};
+use uuid::Uuid;
+
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct UserTotpEntity {
+    pub user_id: Uuid,
+    // 暗号化済みの秘密鍵
+    pub secret_ciphertext: Vec<u8>,
+    pub enabled_at: Option<PrimitiveDateTime>,
+    pub last_used_step: Option<i64>,
+    pub created_at: PrimitiveDateTime,
+}
+
+impl UserTotpEntity {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled_at.is_some()
+    }
+}
+
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct TwoFactorChallengeEntity {
+    pub token_hash: String,
+    pub user_id: Uuid,
+    pub expires_at: PrimitiveDateTime,
+    pub failed_count: i32,
+    pub created_at: PrimitiveDateTime,
+}
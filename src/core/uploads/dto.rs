@@ -0,0 +1,27 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct UploadRes {
+    pub upload: Upload,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Upload {
+    pub id: String,
+    // originalのURL プロフィール画像や記事の画像にはこれを設定する
+    pub url: String,
+    #[serde(rename = "contentType")]
+    pub content_type: String,
+    pub width: u32,
+    pub height: u32,
+    pub variants: BTreeMap<String, UploadVariant>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadVariant {
+    pub url: String,
+    pub width: u32,
+    pub height: u32,
+}
@@ -1,6 +1,9 @@
 use std::sync::Arc;
 
-use super::{dto::PasswdHashedNewUser, entity::UserEntity};
+use super::{
+    dto::PasswdHashedNewUser,
+    entity::{Role, UserEntity},
+};
 use crate::error::ConduitResult;
 use axum::async_trait;
 use uuid::Uuid;
@@ -12,7 +15,22 @@ pub type DynUsersDao = Arc<dyn UsersDaoTrait + Send + Sync>;
 pub trait UsersDaoTrait {
     async fn create_user(&self, new_user: PasswdHashedNewUser) -> ConduitResult<UserEntity>;
     async fn get_user_by_id(&self, user_id: Uuid) -> ConduitResult<UserEntity>;
+    /// ユーザーが存在し，退会していなければtrueを返す 発行済みのトークンの確認に使う
+    async fn is_active_user(&self, user_id: Uuid) -> ConduitResult<bool>;
+    /// 退会したユーザーは含めない ログインやパスワードの再設定では存在しないものとして扱う
     async fn get_user_by_email(&self, email: &str) -> ConduitResult<Option<UserEntity>>;
     async fn get_user_by_username(&self, username: &str) -> ConduitResult<Option<UserEntity>>;
+    /// メールアドレスが変更された場合は，確認済みの状態がリセットされる
     async fn update_user(&self, user: UserEntity) -> ConduitResult<UserEntity>;
+    /// メールアドレスが`email`のままであれば確認済みにする
+    /// 確認メール送信後にメールアドレスが変わっていた場合はNoneを返す
+    async fn mark_email_verified(
+        &self,
+        user_id: Uuid,
+        email: &str,
+    ) -> ConduitResult<Option<UserEntity>>;
+    /// パスワードのハッシュだけを差し替える ハッシュの設定を更新したときの再ハッシュ用
+    async fn update_password(&self, user_id: Uuid, password: &str) -> ConduitResult<()>;
+    /// ユーザーの権限を変更する 権限の確認は呼び出し側で行う
+    async fn update_role(&self, user_id: Uuid, role: Role) -> ConduitResult<UserEntity>;
 }
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use validator::{Validate, ValidateUrl, ValidationError};
 
+use crate::{core::profiles::dto::ProfileExtension, redact::Redacted};
+
+use super::entity::Role;
 
 #[derive(Debug, Clone, Serialize, Default, PartialEq)]
 pub struct User {
@@ -9,6 +12,17 @@ pub struct User {
     pub username: String,
     pub bio: String,
     pub image: Option<String>,
+    #[serde(rename = "isPrivate")]
+    pub is_private: bool,
+    // `?fields=extended`を指定したときだけ返す
+    #[serde(flatten)]
+    pub extension: Option<ProfileExtension>,
+}
+
+impl User {
+    pub fn with_extension(self, extension: Option<ProfileExtension>) -> Self {
+        Self { extension, ..self }
+    }
 }
 
 #[derive(Debug, Clone, Validate, Deserialize)]
@@ -29,16 +43,27 @@ pub struct NewUser {
     #[validate(email, required)]
     pub email: Option<String>,
     #[validate(required)]
-    pub password: Option<String>,
+    pub password: Option<Redacted<String>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct PasswdHashedNewUser {
     pub username: String,
     pub email: String,
     pub password: String,
 }
 
+// ハッシュ化済みとはいえ，パスワードはログに出さない
+impl std::fmt::Debug for PasswdHashedNewUser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PasswdHashedNewUser")
+            .field("username", &self.username)
+            .field("email", &self.email)
+            .field("password", &Redacted::new(()))
+            .finish()
+    }
+}
+
 impl PasswdHashedNewUser {
     pub fn new(username: String, email: String, password: String) -> Self {
         Self {
@@ -65,7 +90,7 @@ pub struct LoginUser {
     #[validate(email, required)]
     pub email: Option<String>,
     #[validate(required)]
-    pub password: Option<String>,
+    pub password: Option<Redacted<String>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -88,8 +113,141 @@ pub struct UpdateUserRes {
 pub struct UpdateUser {
     #[validate(email)]
     pub email: Option<String>,
-    pub password: Option<String>,
+    pub password: Option<Redacted<String>>,
     pub username: Option<String>,
     pub bio: Option<String>,
+    // 空文字を送ると消せる
+    #[validate(length(max = 2048), custom(function = "validate_image_url"))]
     pub image: Option<String>,
+    #[serde(rename = "isPrivate")]
+    pub is_private: Option<bool>,
+    // 以下は空文字を送ると消せる
+    #[serde(rename = "displayName")]
+    #[validate(length(max = 50))]
+    pub display_name: Option<String>,
+    #[validate(length(max = 100))]
+    pub location: Option<String>,
+    #[validate(length(max = 30))]
+    pub pronouns: Option<String>,
+    // 送った内容で置き換える 空の配列を送ると消せる
+    #[validate(length(max = 5), custom(function = "validate_links"))]
+    pub links: Option<Vec<String>>,
+}
+
+// javascript:などのスキームを埋め込まれないよう，httpとhttpsだけを許可する
+fn validate_http_url(url: &str) -> Result<(), ValidationError> {
+    let lower = url.to_ascii_lowercase();
+    if !url.validate_url() || !(lower.starts_with("https://") || lower.starts_with("http://")) {
+        return Err(ValidationError::new("invalid_url")
+            .with_message(format!("must be an http or https url: {}", url).into()));
+    }
+    Ok(())
+}
+
+// アップロードした画像は/api/uploads/以下のパスで指定できる
+fn validate_image_url(url: &str) -> Result<(), ValidationError> {
+    // 空文字は画像を消したいものとして扱うので，URLとしては確認しない
+    if url.trim().is_empty() {
+        return Ok(());
+    }
+    let is_upload = url.strip_prefix("/api/uploads/").is_some_and(|key| {
+        !key.is_empty()
+            && !key.starts_with('.')
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    });
+    if is_upload {
+        return Ok(());
+    }
+    validate_http_url(url)
+}
+
+fn validate_links(links: &[String]) -> Result<(), ValidationError> {
+    for link in links {
+        if link.len() > 2048 {
+            return Err(ValidationError::new("link_too_long")
+                .with_message("links must be at most 2048 characters".into()));
+        }
+        validate_http_url(link)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateRoleReq {
+    #[validate(nested)]
+    pub user: UpdateRole,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateRole {
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserRoleRes {
+    pub user: UserRole,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserRole {
+    pub username: String,
+    pub role: Role,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update_user(image: Option<&str>, links: Option<Vec<&str>>) -> UpdateUser {
+        UpdateUser {
+            email: None,
+            password: None,
+            username: None,
+            bio: None,
+            image: image.map(str::to_string),
+            is_private: None,
+            display_name: None,
+            location: None,
+            pronouns: None,
+            links: links.map(|links| links.into_iter().map(str::to_string).collect()),
+        }
+    }
+
+    #[test]
+    fn accepts_http_urls() {
+        let user = update_user(
+            Some("https://example.com/avatar.png"),
+            Some(vec!["https://example.com", "http://example.org/me"]),
+        );
+        assert!(user.validate().is_ok());
+        let user = update_user(Some("/api/uploads/0192-original.png"), None);
+        assert!(user.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_other_schemes() {
+        assert!(update_user(Some("javascript:alert(1)"), None)
+            .validate()
+            .is_err());
+        assert!(update_user(None, Some(vec!["ftp://example.com"]))
+            .validate()
+            .is_err());
+        assert!(update_user(None, Some(vec!["not a url"]))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn accepts_empty_image() {
+        assert!(update_user(Some(""), None).validate().is_ok());
+        assert!(update_user(Some(" "), None).validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_too_many_links() {
+        let links = vec!["https://example.com"; 6];
+        assert!(update_user(None, Some(links)).validate().is_err());
+    }
 }
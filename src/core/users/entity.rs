@@ -1,10 +1,15 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
 use sqlx::prelude::*;
 use sqlx::types::time::PrimitiveDateTime;
 use uuid::Uuid;
 
+use crate::redact::Redacted;
+
 use super::dto::{UpdateUser, User};
 
-#[derive(FromRow, Debug, Clone, PartialEq)]
+#[derive(FromRow, Clone, PartialEq)]
 pub struct UserEntity {
     pub id: Uuid,
     pub created_at: PrimitiveDateTime,
@@ -14,9 +19,52 @@ pub struct UserEntity {
     pub password: String,
     pub bio: String,
     pub image: Option<String>,
+    pub email_verified_at: Option<PrimitiveDateTime>,
+    pub role: String,
+    // trueなら承認したフォロワーにだけ記事を公開する
+    pub is_private: bool,
+    pub display_name: Option<String>,
+    pub location: Option<String>,
+    pub pronouns: Option<String>,
+    pub links: Vec<String>,
+    // 匿名化して退会した日時 退会していなければNone
+    pub deleted_at: Option<PrimitiveDateTime>,
+}
+
+// パスワードのハッシュはログに出さない
+impl std::fmt::Debug for UserEntity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UserEntity")
+            .field("id", &self.id)
+            .field("created_at", &self.created_at)
+            .field("updated_at", &self.updated_at)
+            .field("username", &self.username)
+            .field("email", &self.email)
+            .field("password", &Redacted::new(()))
+            .field("bio", &self.bio)
+            .field("image", &self.image)
+            .field("email_verified_at", &self.email_verified_at)
+            .field("role", &self.role)
+            .field("is_private", &self.is_private)
+            .field("display_name", &self.display_name)
+            .field("location", &self.location)
+            .field("pronouns", &self.pronouns)
+            .field("links", &self.links)
+            .field("deleted_at", &self.deleted_at)
+            .finish()
+    }
 }
 
 impl UserEntity {
+    pub fn is_email_verified(&self) -> bool {
+        self.email_verified_at.is_some()
+    }
+
+    /// DBの値が不正な場合は，権限の弱い一般ユーザーとして扱う
+    pub fn role(&self) -> Role {
+        self.role.parse().unwrap_or_default()
+    }
+
     pub fn into_dto_with_generated_token(self, token: String) -> User {
         User {
             email: self.email,
@@ -24,16 +72,75 @@ impl UserEntity {
             bio: self.bio,
             image: self.image,
             token,
+            is_private: self.is_private,
+            extension: None,
         }
     }
     pub(crate) fn update_user_entity(self, update_user: UpdateUser) -> Self {
         UserEntity {
             email: update_user.email.unwrap_or(self.email),
-            password: update_user.password.unwrap_or(self.password),
+            password: update_user
+                .password
+                .map(Redacted::into_inner)
+                .unwrap_or(self.password),
             username: update_user.username.unwrap_or(self.username),
             bio: update_user.bio.unwrap_or(self.bio),
-            image: update_user.image.or(self.image),
+            image: update_optional(update_user.image, self.image),
+            is_private: update_user.is_private.unwrap_or(self.is_private),
+            display_name: update_optional(update_user.display_name, self.display_name),
+            location: update_optional(update_user.location, self.location),
+            pronouns: update_optional(update_user.pronouns, self.pronouns),
+            links: update_user.links.unwrap_or(self.links),
             ..self
         }
     }
 }
+
+// 空文字が送られてきた場合は，項目を消したいものとして扱う
+fn update_optional(new: Option<String>, old: Option<String>) -> Option<String> {
+    match new {
+        Some(value) if value.trim().is_empty() => None,
+        Some(value) => Some(value.trim().to_string()),
+        None => old,
+    }
+}
+
+/// ユーザーの権限
+/// モデレーターは他人の記事やコメントを編集・削除でき，管理者はさらにユーザーの権限を変更できる
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    User,
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Self::User, Self::Moderator, Self::Admin];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Moderator => "moderator",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|role| role.as_str() == s)
+            .ok_or_else(|| format!("unknown role: {}", s))
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -1,34 +1,68 @@
 use sqlx::PgPool;
 use users::UserDao;
 
+pub mod access_tokens;
+pub mod account_data;
 pub mod articles;
+pub mod email_verifications;
 pub mod favorites;
+pub mod login_attempts;
+pub mod login_audits;
+pub mod password_resets;
 pub mod profiles;
+pub mod reactions;
 pub mod tags;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod two_factor;
 pub mod users;
 
 #[derive(Clone)]
 pub struct Daos {
+    pub access_tokens: access_tokens::AccessTokensDao,
+    pub account_data: account_data::AccountDataDao,
     pub users: UserDao,
     pub profiles: profiles::ProfileDao,
     pub articles: articles::ArticlesDao,
     pub tags: tags::TagsDao,
     pub favorites: favorites::FavoriteDao,
+    pub reactions: reactions::ReactionsDao,
+    pub password_resets: password_resets::PasswordResetsDao,
+    pub email_verifications: email_verifications::EmailVerificationsDao,
+    pub login_attempts: login_attempts::LoginAttemptsDao,
+    pub login_audits: login_audits::LoginAuditsDao,
+    pub two_factor: two_factor::TwoFactorDao,
 }
 
 impl Daos {
     pub fn new(pool: PgPool) -> Self {
+        let access_tokens = access_tokens::AccessTokensDao::new(pool.clone());
+        let account_data = account_data::AccountDataDao::new(pool.clone());
         let users = users::UserDao::new(pool.clone());
         let profiles = profiles::ProfileDao::new(pool.clone());
         let articles = articles::ArticlesDao::new(pool.clone());
         let tags = tags::TagsDao::new(pool.clone());
         let favorites = favorites::FavoriteDao::new(pool.clone());
+        let reactions = reactions::ReactionsDao::new(pool.clone());
+        let password_resets = password_resets::PasswordResetsDao::new(pool.clone());
+        let email_verifications = email_verifications::EmailVerificationsDao::new(pool.clone());
+        let login_attempts = login_attempts::LoginAttemptsDao::new(pool.clone());
+        let login_audits = login_audits::LoginAuditsDao::new(pool.clone());
+        let two_factor = two_factor::TwoFactorDao::new(pool.clone());
         Self {
+            access_tokens,
+            account_data,
             users,
             profiles,
             articles,
             tags,
             favorites,
+            reactions,
+            password_resets,
+            email_verifications,
+            login_attempts,
+            login_audits,
+            two_factor,
         }
     }
 }
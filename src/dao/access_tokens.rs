@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use anyhow::Context as _;
+use axum::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    core::access_tokens::{dao_trait::AccessTokensDaoTrait, entity::AccessTokenEntity},
+    error::ConduitResult,
+};
+
+#[derive(Clone)]
+pub struct AccessTokensDao {
+    pool: sqlx::PgPool,
+}
+
+impl AccessTokensDao {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AccessTokensDaoTrait for AccessTokensDao {
+    async fn create_access_token(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        token_hash: &str,
+        scopes: Vec<String>,
+        ttl: Option<Duration>,
+    ) -> ConduitResult<AccessTokenEntity> {
+        let uuid = Uuid::now_v7();
+        let token = sqlx::query_as!(
+            AccessTokenEntity,
+            r#"
+            INSERT INTO access_tokens (id, user_id, name, token_hash, scopes, expires_at)
+            VALUES (
+                $1, $2, $3, $4, $5,
+                CURRENT_TIMESTAMP + make_interval(secs => $6::FLOAT8)
+            )
+            RETURNING *
+            "#,
+            uuid,
+            user_id,
+            name,
+            token_hash,
+            &scopes,
+            ttl.map(|ttl| ttl.as_secs_f64())
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("unexpected error: while inserting access_token")?;
+        Ok(token)
+    }
+
+    async fn get_access_tokens(&self, user_id: Uuid) -> ConduitResult<Vec<AccessTokenEntity>> {
+        let tokens = sqlx::query_as!(
+            AccessTokenEntity,
+            r#"
+            SELECT *
+            FROM access_tokens
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("unexpected error: while fetching access_tokens")?;
+        Ok(tokens)
+    }
+
+    async fn delete_access_token(&self, user_id: Uuid, token_id: Uuid) -> ConduitResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM access_tokens
+            WHERE id = $1 AND user_id = $2
+            "#,
+            token_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while deleting access_token")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn use_access_token(&self, token_hash: &str) -> ConduitResult<Option<AccessTokenEntity>> {
+        let token = sqlx::query_as!(
+            AccessTokenEntity,
+            r#"
+            UPDATE access_tokens
+            SET last_used_at = CURRENT_TIMESTAMP
+            WHERE token_hash = $1 AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP)
+            RETURNING *
+            "#,
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("unexpected error: while using access_token")?;
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao::test_support::setup_user;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_create_and_use_access_token(pool: PgPool) {
+        let user = setup_user(&pool, "token_user").await;
+        let dao = AccessTokensDao::new(pool);
+
+        let token = dao
+            .create_access_token(
+                user.id,
+                "ci",
+                "hash",
+                vec!["articles:write".to_string()],
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(token.expires_at, None);
+        assert_eq!(token.last_used_at, None);
+
+        let used = dao.use_access_token("hash").await.unwrap().unwrap();
+        assert_eq!(used.id, token.id);
+        assert!(used.last_used_at.is_some());
+        assert_eq!(dao.use_access_token("unknown").await.unwrap(), None);
+
+        // 期限切れのトークンは使えない
+        dao.create_access_token(
+            user.id,
+            "expired",
+            "expired_hash",
+            vec!["read".to_string()],
+            Some(Duration::ZERO),
+        )
+        .await
+        .unwrap();
+        assert_eq!(dao.use_access_token("expired_hash").await.unwrap(), None);
+
+        let tokens = dao.get_access_tokens(user.id).await.unwrap();
+        assert_eq!(tokens.len(), 2);
+    }
+
+    #[sqlx::test]
+    async fn test_delete_access_token(pool: PgPool) {
+        let user_a = setup_user(&pool, "token_user_a").await;
+        let user_b = setup_user(&pool, "token_user_b").await;
+        let dao = AccessTokensDao::new(pool);
+
+        let token = dao
+            .create_access_token(user_a.id, "ci", "hash", vec!["read".to_string()], None)
+            .await
+            .unwrap();
+
+        // 他のユーザーのトークンは削除できない
+        assert!(!dao.delete_access_token(user_b.id, token.id).await.unwrap());
+        assert!(dao.delete_access_token(user_a.id, token.id).await.unwrap());
+        assert_eq!(dao.use_access_token("hash").await.unwrap(), None);
+    }
+}
@@ -0,0 +1,523 @@
+use std::time::Duration;
+
+use anyhow::Context as _;
+use axum::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    core::account_data::{
+        dao_trait::AccountDataDaoTrait,
+        entity::{
+            AccountDeletionEntity, DeletionMode, ExportedArticleEntity, ExportedFavoriteEntity,
+            ExportedFollowEntity,
+        },
+    },
+    error::ConduitResult,
+};
+
+#[derive(Clone)]
+pub struct AccountDataDao {
+    pool: sqlx::PgPool,
+}
+
+impl AccountDataDao {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AccountDataDaoTrait for AccountDataDao {
+    async fn get_authored_articles(
+        &self,
+        user_id: Uuid,
+    ) -> ConduitResult<Vec<ExportedArticleEntity>> {
+        let articles = sqlx::query_as!(
+            ExportedArticleEntity,
+            r#"
+            SELECT a.slug, a.title, a.description, a.body,
+                COALESCE(
+                    array_agg(t.tag ORDER BY t.tag) FILTER (WHERE t.tag IS NOT NULL),
+                    '{}'
+                ) AS "tag_list!",
+                a.created_at, a.updated_at
+            FROM articles a
+            LEFT JOIN article_tags at ON at.article_id = a.id
+            LEFT JOIN tags t ON t.id = at.tag_id
+            WHERE a.author_id = $1
+            GROUP BY a.id
+            ORDER BY a.created_at
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("unexpected error: while exporting articles")?;
+        Ok(articles)
+    }
+
+    async fn get_favorited_articles(
+        &self,
+        user_id: Uuid,
+    ) -> ConduitResult<Vec<ExportedFavoriteEntity>> {
+        let favorites = sqlx::query_as!(
+            ExportedFavoriteEntity,
+            r#"
+            SELECT a.slug, a.title, f.created_at
+            FROM favorites f
+            JOIN articles a ON a.id = f.article_id
+            WHERE f.user_id = $1 AND f.is_deleted = false
+            ORDER BY f.created_at
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("unexpected error: while exporting favorites")?;
+        Ok(favorites)
+    }
+
+    async fn get_following(&self, user_id: Uuid) -> ConduitResult<Vec<ExportedFollowEntity>> {
+        let following = sqlx::query_as!(
+            ExportedFollowEntity,
+            r#"
+            SELECT u.username, f.created_at
+            FROM user_follows f
+            JOIN users u ON u.id = f.followee_id
+            WHERE f.follower_id = $1
+            ORDER BY f.created_at
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("unexpected error: while exporting following")?;
+        Ok(following)
+    }
+
+    async fn get_followers(&self, user_id: Uuid) -> ConduitResult<Vec<ExportedFollowEntity>> {
+        let followers = sqlx::query_as!(
+            ExportedFollowEntity,
+            r#"
+            SELECT u.username, f.created_at
+            FROM user_follows f
+            JOIN users u ON u.id = f.follower_id
+            WHERE f.followee_id = $1
+            ORDER BY f.created_at
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("unexpected error: while exporting followers")?;
+        Ok(followers)
+    }
+
+    async fn schedule_deletion(
+        &self,
+        user_id: Uuid,
+        mode: DeletionMode,
+        grace_period: Duration,
+    ) -> ConduitResult<AccountDeletionEntity> {
+        // 既存の予約があれば，何も変更せずにその行を返す
+        let deletion = sqlx::query_as!(
+            AccountDeletionEntity,
+            r#"
+            INSERT INTO account_deletions (user_id, mode, scheduled_at)
+            VALUES ($1, $2, CURRENT_TIMESTAMP + make_interval(secs => $3::FLOAT8))
+            ON CONFLICT (user_id) DO UPDATE SET user_id = account_deletions.user_id
+            RETURNING *
+            "#,
+            user_id,
+            mode.as_str(),
+            grace_period.as_secs_f64()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("unexpected error: while scheduling account deletion")?;
+        Ok(deletion)
+    }
+
+    async fn get_deletion(&self, user_id: Uuid) -> ConduitResult<Option<AccountDeletionEntity>> {
+        let deletion = sqlx::query_as!(
+            AccountDeletionEntity,
+            r#"
+            SELECT *
+            FROM account_deletions
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("unexpected error: while fetching account deletion")?;
+        Ok(deletion)
+    }
+
+    async fn cancel_deletion(&self, user_id: Uuid) -> ConduitResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM account_deletions
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while cancelling account deletion")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_due_deletions(&self, limit: i64) -> ConduitResult<Vec<AccountDeletionEntity>> {
+        let deletions = sqlx::query_as!(
+            AccountDeletionEntity,
+            r#"
+            SELECT *
+            FROM account_deletions
+            WHERE scheduled_at <= CURRENT_TIMESTAMP
+            ORDER BY scheduled_at
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("unexpected error: while fetching due account deletions")?;
+        Ok(deletions)
+    }
+
+    async fn anonymize_user(&self, user_id: Uuid) -> ConduitResult<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("unexpected error: while starting transaction")?;
+
+        // 記事以外のユーザーに紐づくデータは消す
+        sqlx::query!(
+            r#"
+            DELETE FROM user_follows
+            WHERE follower_id = $1 OR followee_id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting follows")?;
+        sqlx::query!(
+            r#"
+            DELETE FROM follow_requests
+            WHERE requester_id = $1 OR target_id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting follow requests")?;
+        sqlx::query!(
+            r#"
+            DELETE FROM user_blocks
+            WHERE blocker_id = $1 OR blocked_id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting blocks")?;
+        sqlx::query!(
+            r#"
+            DELETE FROM user_mutes
+            WHERE muter_id = $1 OR muted_id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting mutes")?;
+        sqlx::query!("DELETE FROM favorites WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .await
+            .context("unexpected error: while deleting favorites")?;
+        sqlx::query!("DELETE FROM article_reactions WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .await
+            .context("unexpected error: while deleting reactions")?;
+        // いいねの履歴は記事の集計に使うので，ユーザーとの紐づけだけを外す
+        sqlx::query!(
+            "UPDATE favorite_events SET user_id = NULL WHERE user_id = $1",
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while anonymizing favorite events")?;
+        sqlx::query!("DELETE FROM access_tokens WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .await
+            .context("unexpected error: while deleting access tokens")?;
+        sqlx::query!("DELETE FROM user_totp WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .await
+            .context("unexpected error: while deleting totp")?;
+        sqlx::query!(
+            "DELETE FROM totp_recovery_codes WHERE user_id = $1",
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting recovery codes")?;
+        sqlx::query!(
+            "DELETE FROM two_factor_challenges WHERE user_id = $1",
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting two factor challenges")?;
+        sqlx::query!(
+            "DELETE FROM password_reset_tokens WHERE user_id = $1",
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting password reset tokens")?;
+        sqlx::query!(
+            "DELETE FROM email_verification_tokens WHERE user_id = $1",
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting email verification tokens")?;
+        sqlx::query!("DELETE FROM login_audit_events WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .await
+            .context("unexpected error: while deleting login audits")?;
+        sqlx::query!("DELETE FROM account_deletions WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .await
+            .context("unexpected error: while deleting account deletion")?;
+
+        // ユーザー名とメールアドレスはIDから作り，一意制約を満たしつつログインできないようにする
+        // .invalidは予約済みのTLDなので，実在するアドレスにはならない
+        // deleted_atを入れることで，発行済みのJWTも使えなくなる
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET username = 'deleted-' || replace(id::text, '-', ''),
+                email = 'deleted-' || id::text || '@example.invalid',
+                password = '',
+                bio = '',
+                image = NULL,
+                email_verified_at = NULL,
+                role = 'user',
+                display_name = NULL,
+                location = NULL,
+                pronouns = NULL,
+                links = '{}',
+                is_private = FALSE,
+                deleted_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while anonymizing user")?;
+
+        tx.commit()
+            .await
+            .context("unexpected error: while committing transaction")?;
+        Ok(())
+    }
+
+    async fn delete_user(&self, user_id: Uuid) -> ConduitResult<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM users
+            WHERE id = $1
+            "#,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while deleting user")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::users::dao_trait::UsersDaoTrait,
+        dao::{
+            test_support::{setup_article, setup_user},
+            users::UserDao,
+        },
+    };
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_schedule_and_cancel_deletion(pool: PgPool) {
+        let user = setup_user(&pool, "deletion_user").await;
+        let dao = AccountDataDao::new(pool);
+
+        let deletion = dao
+            .schedule_deletion(user.id, DeletionMode::Cascade, Duration::from_secs(60 * 60))
+            .await
+            .unwrap();
+        assert_eq!(deletion.mode(), DeletionMode::Cascade);
+        // 猶予期間中は対象にならない
+        assert!(dao.get_due_deletions(10).await.unwrap().is_empty());
+
+        // 2回目の予約では既存の予約が変わらない
+        let again = dao
+            .schedule_deletion(user.id, DeletionMode::Anonymize, Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(again, deletion);
+
+        assert!(dao.cancel_deletion(user.id).await.unwrap());
+        assert!(!dao.cancel_deletion(user.id).await.unwrap());
+        assert_eq!(dao.get_deletion(user.id).await.unwrap(), None);
+
+        dao.schedule_deletion(user.id, DeletionMode::Anonymize, Duration::ZERO)
+            .await
+            .unwrap();
+        let due = dao.get_due_deletions(10).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].user_id, user.id);
+    }
+
+    #[sqlx::test]
+    async fn test_anonymize_user_keeps_articles(pool: PgPool) {
+        let user = setup_user(&pool, "anonymize_user").await;
+        let other = setup_user(&pool, "anonymize_other").await;
+        setup_article(&pool, user.id, "anonymize-article").await;
+        let other_article_id = setup_article(&pool, other.id, "other-article").await;
+        sqlx::query!(
+            "INSERT INTO favorites (user_id, article_id) VALUES ($1, $2)",
+            user.id,
+            other_article_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO user_follows (id, follower_id, followee_id) VALUES ($1, $2, $3)",
+            Uuid::now_v7(),
+            other.id,
+            user.id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO user_blocks (id, blocker_id, blocked_id) VALUES ($1, $2, $3)",
+            Uuid::now_v7(),
+            user.id,
+            other.id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO user_mutes (id, muter_id, muted_id) VALUES ($1, $2, $3)",
+            Uuid::now_v7(),
+            other.id,
+            user.id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO follow_requests (id, requester_id, target_id) VALUES ($1, $2, $3)",
+            Uuid::now_v7(),
+            user.id,
+            other.id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!("UPDATE users SET is_private = TRUE WHERE id = $1", user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let dao = AccountDataDao::new(pool.clone());
+
+        assert_eq!(dao.get_authored_articles(user.id).await.unwrap().len(), 1);
+        assert_eq!(dao.get_favorited_articles(user.id).await.unwrap().len(), 1);
+        assert_eq!(dao.get_followers(user.id).await.unwrap().len(), 1);
+
+        dao.anonymize_user(user.id).await.unwrap();
+
+        let anonymized = UserDao::new(pool.clone())
+            .get_user_by_id(user.id)
+            .await
+            .unwrap();
+        assert!(anonymized.username.starts_with("deleted-"));
+        assert_ne!(anonymized.email, user.email);
+        assert_eq!(anonymized.password, "");
+        assert!(!anonymized.is_private);
+        assert!(anonymized.deleted_at.is_some());
+        // 発行済みのトークンを拒否できる
+        assert!(!UserDao::new(pool.clone())
+            .is_active_user(user.id)
+            .await
+            .unwrap());
+        // ログインでは存在しないメールアドレスと同じ扱いになる
+        assert_eq!(
+            UserDao::new(pool.clone())
+                .get_user_by_email(&anonymized.email)
+                .await
+                .unwrap(),
+            None
+        );
+        let relations = sqlx::query_scalar!(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM user_blocks WHERE blocker_id = $1 OR blocked_id = $1)
+                + (SELECT COUNT(*) FROM user_mutes WHERE muter_id = $1 OR muted_id = $1)
+                + (SELECT COUNT(*) FROM follow_requests WHERE requester_id = $1 OR target_id = $1)
+                AS "count!"
+            "#,
+            user.id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(relations, 0);
+        assert!(dao
+            .get_favorited_articles(user.id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(dao.get_followers(user.id).await.unwrap().is_empty());
+        let events = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM favorite_events WHERE user_id = $1"#,
+            user.id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(events, 0);
+        let article = dao.get_authored_articles(user.id).await.unwrap();
+        assert_eq!(article.len(), 1);
+        assert_eq!(article[0].slug, "anonymize-article");
+    }
+
+    #[sqlx::test]
+    async fn test_delete_user_cascades(pool: PgPool) {
+        let user = setup_user(&pool, "cascade_user").await;
+        setup_article(&pool, user.id, "cascade-article").await;
+        let dao = AccountDataDao::new(pool.clone());
+
+        let user_dao = UserDao::new(pool.clone());
+        assert!(user_dao.is_active_user(user.id).await.unwrap());
+
+        dao.delete_user(user.id).await.unwrap();
+        assert!(!user_dao.is_active_user(user.id).await.unwrap());
+
+        let found = user_dao.get_user_by_email(&user.email).await.unwrap();
+        assert_eq!(found, None);
+        assert!(dao.get_authored_articles(user.id).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+use std::time::Duration;
+
+use anyhow::Context as _;
+use axum::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    core::email_verifications::{
+        dao_trait::EmailVerificationsDaoTrait, entity::EmailVerificationTokenEntity,
+    },
+    error::ConduitResult,
+};
+
+#[derive(Clone)]
+pub struct EmailVerificationsDao {
+    pool: sqlx::PgPool,
+}
+
+impl EmailVerificationsDao {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl EmailVerificationsDaoTrait for EmailVerificationsDao {
+    async fn create_verification_token(
+        &self,
+        user_id: Uuid,
+        email: &str,
+        token_hash: &str,
+        ttl: Duration,
+    ) -> ConduitResult<EmailVerificationTokenEntity> {
+        let mut tx = self.pool.begin().await.context("failed to begin")?;
+
+        // 古いメールアドレス宛のトークンが使われないよう，未使用のものは消しておく
+        sqlx::query!(
+            r#"
+            DELETE FROM email_verification_tokens
+            WHERE user_id = $1 AND used_at IS NULL
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting email_verification_tokens")?;
+
+        let uuid = Uuid::now_v7();
+        let token = sqlx::query_as!(
+            EmailVerificationTokenEntity,
+            r#"
+            INSERT INTO email_verification_tokens (id, user_id, email, token_hash, expires_at)
+            VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP + make_interval(secs => $5))
+            RETURNING *
+            "#,
+            uuid,
+            user_id,
+            email,
+            token_hash,
+            ttl.as_secs_f64()
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("unexpected error: while inserting email_verification_token")?;
+
+        tx.commit().await.context("failed to commit")?;
+        Ok(token)
+    }
+
+    async fn consume_verification_token(
+        &self,
+        token_hash: &str,
+    ) -> ConduitResult<Option<EmailVerificationTokenEntity>> {
+        let token = sqlx::query_as!(
+            EmailVerificationTokenEntity,
+            r#"
+            UPDATE email_verification_tokens
+            SET used_at = CURRENT_TIMESTAMP
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+            RETURNING *
+            "#,
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("unexpected error: while consuming email_verification_token")?;
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::users::{dao_trait::UsersDaoTrait as _, dto::PasswdHashedNewUser},
+        dao::users::UserDao,
+    };
+    use sqlx::PgPool;
+
+    async fn setup_user(pool: &PgPool) -> Uuid {
+        let user_dao = UserDao::new(pool.clone());
+        let new_user = PasswdHashedNewUser::new(
+            "verify".to_string(),
+            "verify@example.com".to_string(),
+            "password".to_string(),
+        );
+        user_dao.create_user(new_user).await.unwrap().id
+    }
+
+    #[sqlx::test]
+    async fn consume_verification_token_only_once(pool: PgPool) {
+        let user_id = setup_user(&pool).await;
+        let dao = EmailVerificationsDao::new(pool);
+        dao.create_verification_token(
+            user_id,
+            "verify@example.com",
+            "hash",
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+
+        let token = dao
+            .consume_verification_token("hash")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(token.email, "verify@example.com");
+        assert_eq!(dao.consume_verification_token("hash").await.unwrap(), None);
+    }
+
+    // 新しいトークンを発行すると古いトークンは使えなくなる
+    #[sqlx::test]
+    async fn new_token_invalidates_old_one(pool: PgPool) {
+        let user_id = setup_user(&pool).await;
+        let dao = EmailVerificationsDao::new(pool);
+        dao.create_verification_token(user_id, "old@example.com", "old", Duration::from_secs(60))
+            .await
+            .unwrap();
+        dao.create_verification_token(user_id, "new@example.com", "new", Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        assert_eq!(dao.consume_verification_token("old").await.unwrap(), None);
+        let token = dao
+            .consume_verification_token("new")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(token.email, "new@example.com");
+    }
+}
@@ -1,5 +1,13 @@
+use std::time::Duration;
+
 use crate::{
-    core::favorites::{dao_trait::FavoritesDaoTrait, entity::FavoritesEntity},
+    core::{
+        favorites::{
+            dao_trait::FavoritesDaoTrait,
+            entity::{FavoriteStatsEntity, FavoritedArticleEntity, FavoritesEntity},
+        },
+        users::entity::UserEntity,
+    },
     error::ConduitResult,
 };
 use anyhow::Context as _;
@@ -67,7 +75,7 @@ impl FavoritesDaoTrait for FavoriteDao {
         &self,
         user_id: Uuid,
         article_id: i32,
-    ) -> ConduitResult<FavoritesEntity> {
+    ) -> ConduitResult<Option<FavoritesEntity>> {
         // 論理削除フラグの更新
         let favorite = sqlx::query_as!(
             FavoritesEntity,
@@ -80,10 +88,600 @@ impl FavoritesDaoTrait for FavoriteDao {
             user_id,
             article_id
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
         .await
         .context("Failed to remove favorite")?;
 
         Ok(favorite)
     }
+
+    async fn get_favoriting_users(
+        &self,
+        article_id: i32,
+        limit: i64,
+        offset: i64,
+    ) -> ConduitResult<Vec<UserEntity>> {
+        let users = sqlx::query_as!(
+            UserEntity,
+            r#"
+            SELECT u.*
+            FROM favorites f
+            JOIN users u ON u.id = f.user_id
+            WHERE f.article_id = $1 AND f.is_deleted = false
+            ORDER BY f.created_at DESC, u.id
+            LIMIT $2 OFFSET $3
+            "#,
+            article_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get favoriting users")?;
+
+        Ok(users)
+    }
+
+    async fn count_favorites(&self, article_id: i32) -> ConduitResult<i32> {
+        // 記事が見つからない場合は0件として扱う
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT favorites_count FROM articles
+            WHERE id = $1
+            "#,
+            article_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to count favorites")?;
+
+        Ok(count.unwrap_or(0))
+    }
+
+    async fn repair_favorites_counts(&self) -> ConduitResult<u64> {
+        // 数がずれている記事だけを更新する
+        let result = sqlx::query!(
+            r#"
+            WITH counts AS (
+                SELECT a.id, COUNT(f.article_id)::INTEGER AS actual
+                FROM articles a
+                LEFT JOIN favorites f ON f.article_id = a.id AND f.is_deleted = false
+                GROUP BY a.id
+            )
+            UPDATE articles a
+            SET favorites_count = counts.actual
+            FROM counts
+            WHERE counts.id = a.id AND a.favorites_count <> counts.actual
+            "#
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to repair favorites counts")?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_favorited_articles(
+        &self,
+        user_id: Uuid,
+        viewer_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> ConduitResult<Vec<FavoritedArticleEntity>> {
+        // viewer_idがNULLの場合，フォローやミュートの条件はどれも成り立たない
+        let articles = sqlx::query_as!(
+            FavoritedArticleEntity,
+            r#"
+            SELECT
+                a.id, a.created_at, a.updated_at, a.title, a.slug, a.description, a.body,
+                a.author_id,
+                u.username AS author_username, u.bio AS author_bio, u.image AS author_image,
+                u.is_private AS author_is_private,
+                COALESCE(
+                    (
+                        SELECT array_agg(t.tag ORDER BY t.tag)
+                        FROM article_tags at
+                        JOIN tags t ON t.id = at.tag_id
+                        WHERE at.article_id = a.id
+                    ),
+                    '{}'
+                ) AS "tag_list!",
+                a.favorites_count,
+                EXISTS(
+                    SELECT 1 FROM favorites v
+                    WHERE v.article_id = a.id AND v.user_id = $2 AND v.is_deleted = false
+                ) AS "favorited!",
+                EXISTS(
+                    SELECT 1 FROM user_follows uf
+                    WHERE uf.follower_id = $2 AND uf.followee_id = a.author_id
+                ) AS "following!"
+            FROM favorites f
+            JOIN articles a ON a.id = f.article_id
+            JOIN users u ON u.id = a.author_id
+            WHERE f.user_id = $1 AND f.is_deleted = false
+                AND (
+                    NOT u.is_private
+                    OR a.author_id = $2
+                    OR EXISTS(
+                        SELECT 1 FROM user_follows uf
+                        WHERE uf.follower_id = $2 AND uf.followee_id = a.author_id
+                    )
+                )
+                AND NOT EXISTS(
+                    SELECT 1 FROM user_mutes m
+                    WHERE m.muter_id = $2 AND m.muted_id = a.author_id
+                )
+            ORDER BY f.created_at DESC, a.id DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            user_id,
+            viewer_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get favorited articles")?;
+
+        Ok(articles)
+    }
+
+    async fn count_favorited_articles(
+        &self,
+        user_id: Uuid,
+        viewer_id: Option<Uuid>,
+    ) -> ConduitResult<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM favorites f
+            JOIN articles a ON a.id = f.article_id
+            JOIN users u ON u.id = a.author_id
+            WHERE f.user_id = $1 AND f.is_deleted = false
+                AND (
+                    NOT u.is_private
+                    OR a.author_id = $2
+                    OR EXISTS(
+                        SELECT 1 FROM user_follows uf
+                        WHERE uf.follower_id = $2 AND uf.followee_id = a.author_id
+                    )
+                )
+                AND NOT EXISTS(
+                    SELECT 1 FROM user_mutes m
+                    WHERE m.muter_id = $2 AND m.muted_id = a.author_id
+                )
+            "#,
+            user_id,
+            viewer_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to count favorited articles")?;
+
+        Ok(count)
+    }
+
+    async fn purge_deleted_favorites(&self, older_than: Duration) -> ConduitResult<u64> {
+        // 最後のイベントが解除した時点のもの
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM favorites f
+            WHERE f.is_deleted = true
+                AND NOT EXISTS(
+                    SELECT 1 FROM favorite_events e
+                    WHERE e.user_id = f.user_id AND e.article_id = f.article_id
+                        AND e.created_at > CURRENT_TIMESTAMP - make_interval(secs => $1::FLOAT8)
+                )
+            "#,
+            older_than.as_secs_f64()
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to purge deleted favorites")?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_favorite_stats(
+        &self,
+        article_id: i32,
+        days: i32,
+    ) -> ConduitResult<Vec<FavoriteStatsEntity>> {
+        let stats = sqlx::query_as!(
+            FavoriteStatsEntity,
+            r#"
+            WITH days AS (
+                SELECT generate_series(
+                    CURRENT_DATE - ($2::INTEGER - 1),
+                    CURRENT_DATE,
+                    INTERVAL '1 day'
+                )::DATE AS day
+            ),
+            daily AS (
+                SELECT
+                    created_at::DATE AS day,
+                    COUNT(*) FILTER (WHERE kind = 'favorited') AS favorited,
+                    COUNT(*) FILTER (WHERE kind = 'unfavorited') AS unfavorited
+                FROM favorite_events
+                WHERE article_id = $1 AND created_at >= CURRENT_DATE - ($2::INTEGER - 1)
+                GROUP BY 1
+            )
+            SELECT
+                d.day AS "day!",
+                COALESCE(daily.favorited, 0) AS "favorited!",
+                COALESCE(daily.unfavorited, 0) AS "unfavorited!",
+                (
+                    SELECT COALESCE(SUM(CASE WHEN e.kind = 'favorited' THEN 1 ELSE -1 END), 0)
+                    FROM favorite_events e
+                    WHERE e.article_id = $1 AND e.created_at < d.day + 1
+                )::BIGINT AS "total!"
+            FROM days d
+            LEFT JOIN daily ON daily.day = d.day
+            ORDER BY d.day
+            "#,
+            article_id,
+            days
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get favorite stats")?;
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{profiles::dao_trait::ProfilesDaoTrait, users::dao_trait::UsersDaoTrait},
+        dao::{
+            profiles::ProfileDao,
+            test_support::{setup_article, setup_user},
+            users::UserDao,
+        },
+    };
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_get_favoriting_users(pool: PgPool) {
+        let dao = FavoriteDao::new(pool.clone());
+        let author = setup_user(&pool, "author").await;
+        let alice = setup_user(&pool, "alice").await;
+        let bob = setup_user(&pool, "bob").await;
+        let article_id = setup_article(&pool, author.id, "article").await;
+
+        dao.add_favorite(alice.id, article_id).await.unwrap();
+        dao.add_favorite(bob.id, article_id).await.unwrap();
+        dao.remove_favorite(alice.id, article_id).await.unwrap();
+
+        // 解除したいいねは含めない
+        let users = dao.get_favoriting_users(article_id, 20, 0).await.unwrap();
+        assert_eq!(
+            users.into_iter().map(|u| u.id).collect::<Vec<_>>(),
+            vec![bob.id]
+        );
+        assert_eq!(dao.count_favorites(article_id).await.unwrap(), 1);
+
+        let users = dao.get_favoriting_users(article_id, 20, 1).await.unwrap();
+        assert!(users.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_get_favorited_articles(pool: PgPool) {
+        let dao = FavoriteDao::new(pool.clone());
+        let profile_dao = ProfileDao::new(pool.clone());
+        let user_dao = UserDao::new(pool.clone());
+        let public = setup_user(&pool, "public").await;
+        let private = setup_user(&pool, "private").await;
+        let private = user_dao
+            .update_user(UserEntity {
+                is_private: true,
+                ..private
+            })
+            .await
+            .unwrap();
+        let fan = setup_user(&pool, "fan").await;
+        let viewer = setup_user(&pool, "viewer").await;
+
+        let public_article = setup_article(&pool, public.id, "public-article").await;
+        let private_article = setup_article(&pool, private.id, "private-article").await;
+        let removed_article = setup_article(&pool, public.id, "removed-article").await;
+        sqlx::query!(
+            r#"
+            INSERT INTO tags (tag) VALUES ('rust');
+            "#
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            r#"
+            INSERT INTO article_tags (article_id, tag_id)
+            SELECT $1, id FROM tags WHERE tag = 'rust'
+            "#,
+            public_article
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        for article_id in [public_article, private_article, removed_article] {
+            dao.add_favorite(fan.id, article_id).await.unwrap();
+        }
+        dao.remove_favorite(fan.id, removed_article).await.unwrap();
+        dao.add_favorite(viewer.id, public_article).await.unwrap();
+
+        // 非公開アカウントの記事は，フォローしていないと見えない
+        let articles = dao
+            .get_favorited_articles(fan.id, None, 20, 0)
+            .await
+            .unwrap();
+        assert_eq!(articles.len(), 1);
+        let article = &articles[0];
+        assert_eq!(article.id, public_article);
+        assert_eq!(article.author_username, public.username);
+        assert!(!article.author_is_private);
+        assert_eq!(article.tag_list, vec!["rust".to_string()]);
+        assert_eq!(article.favorites_count, 2);
+        assert!(!article.favorited);
+        assert_eq!(dao.count_favorited_articles(fan.id, None).await.unwrap(), 1);
+
+        profile_dao
+            .following_user(viewer.id, private.id)
+            .await
+            .unwrap();
+        let articles = dao
+            .get_favorited_articles(fan.id, Some(viewer.id), 20, 0)
+            .await
+            .unwrap();
+        let ids = articles.iter().map(|a| a.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![private_article, public_article]);
+        assert!(articles[0].following);
+        assert_eq!(articles[0].author_username, private.username);
+        assert!(articles[0].author_is_private);
+        assert!(articles[1].favorited);
+
+        // ミュートしている作者の記事は含めない
+        profile_dao.mute_user(viewer.id, public.id).await.unwrap();
+        let articles = dao
+            .get_favorited_articles(fan.id, Some(viewer.id), 20, 0)
+            .await
+            .unwrap();
+        assert_eq!(
+            articles.into_iter().map(|a| a.id).collect::<Vec<_>>(),
+            vec![private_article]
+        );
+        assert_eq!(
+            dao.count_favorited_articles(fan.id, Some(viewer.id))
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_favorites_count(pool: PgPool) {
+        let dao = FavoriteDao::new(pool.clone());
+        let author = setup_user(&pool, "author").await;
+        let alice = setup_user(&pool, "alice").await;
+        let bob = setup_user(&pool, "bob").await;
+        let article_id = setup_article(&pool, author.id, "article").await;
+        let updated_at = || async {
+            sqlx::query_scalar!("SELECT updated_at FROM articles WHERE id = $1", article_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        };
+        let before = updated_at().await;
+
+        // 同じユーザーが何度いいねしても1件として数える
+        dao.add_favorite(alice.id, article_id).await.unwrap();
+        dao.add_favorite(alice.id, article_id).await.unwrap();
+        dao.add_favorite(bob.id, article_id).await.unwrap();
+        assert_eq!(dao.count_favorites(article_id).await.unwrap(), 2);
+
+        dao.remove_favorite(alice.id, article_id).await.unwrap();
+        dao.remove_favorite(alice.id, article_id).await.unwrap();
+        assert_eq!(dao.count_favorites(article_id).await.unwrap(), 1);
+
+        dao.add_favorite(alice.id, article_id).await.unwrap();
+        assert_eq!(dao.count_favorites(article_id).await.unwrap(), 2);
+
+        // いいね数の更新では記事の更新日時は変わらない
+        assert_eq!(updated_at().await, before);
+
+        // ユーザーが削除されると，そのユーザーのいいねも数えなくなる
+        sqlx::query!("DELETE FROM users WHERE id = $1", bob.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(dao.count_favorites(article_id).await.unwrap(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_repair_favorites_counts(pool: PgPool) {
+        let dao = FavoriteDao::new(pool.clone());
+        let author = setup_user(&pool, "author").await;
+        let alice = setup_user(&pool, "alice").await;
+        let article_id = setup_article(&pool, author.id, "article").await;
+        let other_id = setup_article(&pool, author.id, "other").await;
+        dao.add_favorite(alice.id, article_id).await.unwrap();
+
+        sqlx::query!(
+            "UPDATE articles SET favorites_count = 10 WHERE id = $1",
+            article_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // ずれている記事だけを直す
+        assert_eq!(dao.repair_favorites_counts().await.unwrap(), 1);
+        assert_eq!(dao.count_favorites(article_id).await.unwrap(), 1);
+        assert_eq!(dao.count_favorites(other_id).await.unwrap(), 0);
+        assert_eq!(dao.repair_favorites_counts().await.unwrap(), 0);
+    }
+
+    #[sqlx::test]
+    async fn test_repeat_favorite_and_unfavorite(pool: PgPool) {
+        let dao = FavoriteDao::new(pool.clone());
+        let author = setup_user(&pool, "author").await;
+        let alice = setup_user(&pool, "alice").await;
+        let article_id = setup_article(&pool, author.id, "article").await;
+
+        // 一度もいいねしていない記事の解除はエラーにしない
+        let removed = dao.remove_favorite(alice.id, article_id).await.unwrap();
+        assert!(removed.is_none());
+        assert_eq!(dao.count_favorites(article_id).await.unwrap(), 0);
+
+        let first = dao.add_favorite(alice.id, article_id).await.unwrap();
+        let second = dao.add_favorite(alice.id, article_id).await.unwrap();
+        assert!(!second.is_deleted);
+        assert_eq!(first, second);
+        assert_eq!(dao.count_favorites(article_id).await.unwrap(), 1);
+
+        for _ in 0..2 {
+            let removed = dao.remove_favorite(alice.id, article_id).await.unwrap();
+            assert!(removed.unwrap().is_deleted);
+            assert_eq!(dao.count_favorites(article_id).await.unwrap(), 0);
+        }
+
+        let readded = dao.add_favorite(alice.id, article_id).await.unwrap();
+        assert!(!readded.is_deleted);
+        assert_eq!(dao.count_favorites(article_id).await.unwrap(), 1);
+        let favorites = dao.get_favorites_by_article_id(article_id).await.unwrap();
+        assert_eq!(favorites.len(), 1);
+    }
+
+    async fn events(pool: &PgPool, article_id: i32) -> Vec<(Option<Uuid>, String)> {
+        sqlx::query!(
+            "SELECT user_id, kind FROM favorite_events WHERE article_id = $1 ORDER BY id",
+            article_id
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|row| (row.user_id, row.kind))
+        .collect()
+    }
+
+    #[sqlx::test]
+    async fn test_favorite_events(pool: PgPool) {
+        let dao = FavoriteDao::new(pool.clone());
+        let author = setup_user(&pool, "author").await;
+        let alice = setup_user(&pool, "alice").await;
+        let bob = setup_user(&pool, "bob").await;
+        let article_id = setup_article(&pool, author.id, "article").await;
+
+        // 状態が変わったときだけ記録する
+        dao.add_favorite(alice.id, article_id).await.unwrap();
+        dao.add_favorite(alice.id, article_id).await.unwrap();
+        dao.remove_favorite(alice.id, article_id).await.unwrap();
+        dao.remove_favorite(alice.id, article_id).await.unwrap();
+        dao.remove_favorite(bob.id, article_id).await.unwrap();
+        dao.add_favorite(bob.id, article_id).await.unwrap();
+        let favorited = "favorited".to_string();
+        let unfavorited = "unfavorited".to_string();
+        assert_eq!(
+            events(&pool, article_id).await,
+            vec![
+                (Some(alice.id), favorited.clone()),
+                (Some(alice.id), unfavorited.clone()),
+                (Some(bob.id), favorited.clone()),
+            ]
+        );
+
+        // 解除済みのいいねを消しても記録しない 有効ないいねが消えた場合はユーザーを残さず記録する
+        sqlx::query!("DELETE FROM users WHERE id = ANY($1)", &[alice.id, bob.id])
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            events(&pool, article_id).await,
+            vec![
+                (None, favorited.clone()),
+                (None, unfavorited.clone()),
+                (None, favorited),
+                (None, unfavorited),
+            ]
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_purge_deleted_favorites(pool: PgPool) {
+        let dao = FavoriteDao::new(pool.clone());
+        let author = setup_user(&pool, "author").await;
+        let alice = setup_user(&pool, "alice").await;
+        let bob = setup_user(&pool, "bob").await;
+        let carol = setup_user(&pool, "carol").await;
+        let article_id = setup_article(&pool, author.id, "article").await;
+
+        for user in [&alice, &bob, &carol] {
+            dao.add_favorite(user.id, article_id).await.unwrap();
+        }
+        dao.remove_favorite(alice.id, article_id).await.unwrap();
+        dao.remove_favorite(bob.id, article_id).await.unwrap();
+        // aliceとcarolのイベントを40日前にずらす
+        sqlx::query!(
+            r#"
+            UPDATE favorite_events SET created_at = created_at - INTERVAL '40 days'
+            WHERE user_id = ANY($1)
+            "#,
+            &[alice.id, carol.id]
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // 解除してから30日経ったaliceのいいねだけを消す
+        let older_than = Duration::from_secs(60 * 60 * 24 * 30);
+        assert_eq!(dao.purge_deleted_favorites(older_than).await.unwrap(), 1);
+        assert_eq!(dao.purge_deleted_favorites(older_than).await.unwrap(), 0);
+        assert!(dao
+            .remove_favorite(alice.id, article_id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(dao
+            .remove_favorite(bob.id, article_id)
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(dao.count_favorites(article_id).await.unwrap(), 1);
+        // 履歴は残る
+        assert_eq!(events(&pool, article_id).await.len(), 5);
+    }
+
+    #[sqlx::test]
+    async fn test_get_favorite_stats(pool: PgPool) {
+        let dao = FavoriteDao::new(pool.clone());
+        let author = setup_user(&pool, "author").await;
+        let alice = setup_user(&pool, "alice").await;
+        let bob = setup_user(&pool, "bob").await;
+        let article_id = setup_article(&pool, author.id, "article").await;
+
+        dao.add_favorite(alice.id, article_id).await.unwrap();
+        dao.add_favorite(bob.id, article_id).await.unwrap();
+        // aliceのいいねは2日前のものにする
+        sqlx::query!(
+            r#"
+            UPDATE favorite_events SET created_at = created_at - INTERVAL '2 days'
+            WHERE user_id = $1
+            "#,
+            alice.id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        dao.remove_favorite(bob.id, article_id).await.unwrap();
+
+        let stats = dao.get_favorite_stats(article_id, 3).await.unwrap();
+        let stats = stats
+            .into_iter()
+            .map(|s| (s.favorited, s.unfavorited, s.total))
+            .collect::<Vec<_>>();
+        assert_eq!(stats, vec![(1, 0, 1), (0, 0, 1), (1, 1, 1)]);
+    }
 }
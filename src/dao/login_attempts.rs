@@ -0,0 +1,320 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context as _;
+use axum::async_trait;
+
+use crate::{core::login_attempts::dao_trait::LoginAttemptsDaoTrait, error::ConduitResult};
+
+/// 複数インスタンスで状態を共有するためのPostgres実装
+#[derive(Clone)]
+pub struct LoginAttemptsDao {
+    pool: sqlx::PgPool,
+}
+
+impl LoginAttemptsDao {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl LoginAttemptsDaoTrait for LoginAttemptsDao {
+    async fn try_begin_attempt(
+        &self,
+        key: &str,
+        window: Duration,
+        max_failures: i32,
+    ) -> ConduitResult<Option<i32>> {
+        // 行ロックを取ってから条件を確かめるので，同時に来た試行も1つずつ数えられる
+        // 失敗の結果でブロックが入ったまま期限が切れていれば，ロックが明けたとみなしてもう1回だけ試させる
+        // 試行を数えるたびにブロックを消すので，結果が出るまでの間に別の試行が通ることはない
+        let failed_count = sqlx::query_scalar!(
+            r#"
+            INSERT INTO login_attempts (key, failed_count, last_failed_at)
+            VALUES ($1, 1, CURRENT_TIMESTAMP)
+            ON CONFLICT (key) DO UPDATE
+            SET failed_count = CASE
+                    WHEN login_attempts.last_failed_at < CURRENT_TIMESTAMP - make_interval(secs => $2)
+                    THEN 1
+                    ELSE login_attempts.failed_count + 1
+                END,
+                last_failed_at = CURRENT_TIMESTAMP,
+                blocked_until = NULL
+            WHERE (login_attempts.blocked_until IS NULL
+                    OR login_attempts.blocked_until <= CURRENT_TIMESTAMP)
+                AND (login_attempts.failed_count < $3
+                    OR login_attempts.blocked_until IS NOT NULL
+                    OR login_attempts.last_failed_at < CURRENT_TIMESTAMP - make_interval(secs => $2))
+            RETURNING failed_count
+            "#,
+            key,
+            window.as_secs_f64(),
+            max_failures
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("unexpected error: while reserving login_attempts")?;
+        Ok(failed_count)
+    }
+
+    async fn release_attempt(&self, key: &str) -> ConduitResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE login_attempts
+            SET failed_count = GREATEST(failed_count - 1, 0)
+            WHERE key = $1
+            "#,
+            key
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while releasing login_attempts")?;
+        Ok(())
+    }
+
+    async fn block(&self, key: &str, duration: Duration) -> ConduitResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO login_attempts (key, blocked_until)
+            VALUES ($1, CURRENT_TIMESTAMP + make_interval(secs => $2))
+            ON CONFLICT (key) DO UPDATE
+            SET blocked_until = GREATEST(login_attempts.blocked_until, EXCLUDED.blocked_until)
+            "#,
+            key,
+            duration.as_secs_f64()
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while blocking login_attempts")?;
+        Ok(())
+    }
+
+    async fn blocked_for(&self, key: &str) -> ConduitResult<Option<Duration>> {
+        let secs = sqlx::query_scalar!(
+            r#"
+            SELECT EXTRACT(EPOCH FROM (blocked_until - CURRENT_TIMESTAMP))::float8 AS "secs!"
+            FROM login_attempts
+            WHERE key = $1 AND blocked_until > CURRENT_TIMESTAMP
+            "#,
+            key
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("unexpected error: while fetching login_attempts")?;
+        Ok(secs.map(Duration::from_secs_f64))
+    }
+
+    async fn reset(&self, key: &str) -> ConduitResult<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM login_attempts
+            WHERE key = $1
+            "#,
+            key
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while deleting login_attempts")?;
+        Ok(())
+    }
+}
+
+struct LoginAttempt {
+    failed_count: i32,
+    last_failed_at: Instant,
+    blocked_until: Option<Instant>,
+}
+
+/// 単一インスタンスで動かす場合やテスト用のインメモリ実装
+/// 再起動すると状態は消える
+#[derive(Default)]
+pub struct InMemoryLoginAttemptsDao {
+    attempts: Mutex<HashMap<String, LoginAttempt>>,
+}
+
+impl InMemoryLoginAttemptsDao {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl LoginAttemptsDaoTrait for InMemoryLoginAttemptsDao {
+    async fn try_begin_attempt(
+        &self,
+        key: &str,
+        window: Duration,
+        max_failures: i32,
+    ) -> ConduitResult<Option<i32>> {
+        let now = Instant::now();
+        let mut attempts = self.attempts.lock().unwrap();
+        // 期限切れのものを掃除しておかないと，際限なく増えていく
+        attempts.retain(|_, a| {
+            now.duration_since(a.last_failed_at) < window
+                || a.blocked_until.is_some_and(|until| until > now)
+        });
+
+        let attempt = attempts.entry(key.to_string()).or_insert(LoginAttempt {
+            failed_count: 0,
+            last_failed_at: now,
+            blocked_until: None,
+        });
+        if now.duration_since(attempt.last_failed_at) >= window {
+            attempt.failed_count = 0;
+        }
+        // Postgres実装と同じく，ブロックの期限が切れていればロックが明けたとみなす
+        let allowed = match attempt.blocked_until {
+            Some(until) => until <= now,
+            None => attempt.failed_count < max_failures,
+        };
+        if !allowed {
+            return Ok(None);
+        }
+        attempt.failed_count += 1;
+        attempt.last_failed_at = now;
+        attempt.blocked_until = None;
+        Ok(Some(attempt.failed_count))
+    }
+
+    async fn release_attempt(&self, key: &str) -> ConduitResult<()> {
+        if let Some(attempt) = self.attempts.lock().unwrap().get_mut(key) {
+            attempt.failed_count = (attempt.failed_count - 1).max(0);
+        }
+        Ok(())
+    }
+
+    async fn block(&self, key: &str, duration: Duration) -> ConduitResult<()> {
+        let now = Instant::now();
+        let mut attempts = self.attempts.lock().unwrap();
+        let attempt = attempts.entry(key.to_string()).or_insert(LoginAttempt {
+            failed_count: 0,
+            last_failed_at: now,
+            blocked_until: None,
+        });
+        attempt.blocked_until = attempt.blocked_until.max(Some(now + duration));
+        Ok(())
+    }
+
+    async fn blocked_for(&self, key: &str) -> ConduitResult<Option<Duration>> {
+        let now = Instant::now();
+        let attempts = self.attempts.lock().unwrap();
+        let blocked_for = attempts
+            .get(key)
+            .and_then(|a| a.blocked_until)
+            .filter(|until| *until > now)
+            .map(|until| until - now);
+        Ok(blocked_for)
+    }
+
+    async fn reset(&self, key: &str) -> ConduitResult<()> {
+        self.attempts.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    const WINDOW: Duration = Duration::from_secs(60 * 60);
+
+    const MAX: i32 = 3;
+
+    async fn begin(dao: &(dyn LoginAttemptsDaoTrait + Send + Sync), key: &str) -> Option<i32> {
+        dao.try_begin_attempt(key, WINDOW, MAX).await.unwrap()
+    }
+
+    async fn begin_block_reset(dao: &(dyn LoginAttemptsDaoTrait + Send + Sync)) {
+        assert_eq!(begin(dao, "email:a").await, Some(1));
+        assert_eq!(begin(dao, "email:a").await, Some(2));
+        assert_eq!(begin(dao, "email:b").await, Some(1));
+        assert_eq!(dao.blocked_for("email:a").await.unwrap(), None);
+
+        dao.block("email:a", Duration::from_secs(60)).await.unwrap();
+        let blocked_for = dao.blocked_for("email:a").await.unwrap().unwrap();
+        assert!(blocked_for <= Duration::from_secs(60));
+        assert!(blocked_for > Duration::from_secs(50));
+        assert_eq!(dao.blocked_for("email:b").await.unwrap(), None);
+        // 後から短いブロックが来ても縮めない
+        dao.block("email:a", Duration::ZERO).await.unwrap();
+        assert!(dao.blocked_for("email:a").await.unwrap().is_some());
+        // ブロック中は数えずに弾く
+        assert_eq!(begin(dao, "email:a").await, None);
+
+        dao.reset("email:a").await.unwrap();
+        assert_eq!(dao.blocked_for("email:a").await.unwrap(), None);
+        assert_eq!(begin(dao, "email:a").await, Some(1));
+    }
+
+    async fn begin_until_max_release(dao: &(dyn LoginAttemptsDaoTrait + Send + Sync)) {
+        for count in 1..=MAX {
+            assert_eq!(begin(dao, "ip:127.0.0.1").await, Some(count));
+        }
+        // 結果が出ていない試行だけで上限に達していれば，それ以上は通さない
+        assert_eq!(begin(dao, "ip:127.0.0.1").await, None);
+
+        dao.release_attempt("ip:127.0.0.1").await.unwrap();
+        assert_eq!(begin(dao, "ip:127.0.0.1").await, Some(MAX));
+
+        // ロックが明けたら，もう1回だけ試せる
+        dao.block("ip:127.0.0.1", Duration::ZERO).await.unwrap();
+        assert_eq!(begin(dao, "ip:127.0.0.1").await, Some(MAX + 1));
+        assert_eq!(begin(dao, "ip:127.0.0.1").await, None);
+    }
+
+    #[sqlx::test]
+    async fn pg_begin_block_reset(pool: PgPool) {
+        let dao = LoginAttemptsDao::new(pool);
+        begin_block_reset(&dao).await;
+    }
+
+    #[tokio::test]
+    async fn in_memory_begin_block_reset() {
+        let dao = InMemoryLoginAttemptsDao::new();
+        begin_block_reset(&dao).await;
+    }
+
+    #[sqlx::test]
+    async fn pg_begin_until_max_release(pool: PgPool) {
+        let dao = LoginAttemptsDao::new(pool);
+        begin_until_max_release(&dao).await;
+    }
+
+    #[tokio::test]
+    async fn in_memory_begin_until_max_release() {
+        let dao = InMemoryLoginAttemptsDao::new();
+        begin_until_max_release(&dao).await;
+    }
+
+    // windowを過ぎた失敗は数えない
+    #[sqlx::test]
+    async fn pg_failures_outside_window_are_forgotten(pool: PgPool) {
+        let dao = LoginAttemptsDao::new(pool);
+        dao.try_begin_attempt("ip:127.0.0.1", Duration::ZERO, MAX)
+            .await
+            .unwrap();
+        let count = dao
+            .try_begin_attempt("ip:127.0.0.1", Duration::ZERO, MAX)
+            .await
+            .unwrap();
+        assert_eq!(count, Some(1));
+    }
+
+    #[tokio::test]
+    async fn in_memory_failures_outside_window_are_forgotten() {
+        let dao = InMemoryLoginAttemptsDao::new();
+        dao.try_begin_attempt("ip:127.0.0.1", Duration::ZERO, MAX)
+            .await
+            .unwrap();
+        let count = dao
+            .try_begin_attempt("ip:127.0.0.1", Duration::ZERO, MAX)
+            .await
+            .unwrap();
+        assert_eq!(count, Some(1));
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::Context as _;
+use axum::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    core::login_audits::{
+        dao_trait::LoginAuditsDaoTrait,
+        entity::{LoginAuditEvent, LoginAuditEventEntity},
+    },
+    error::ConduitResult,
+};
+
+#[derive(Clone)]
+pub struct LoginAuditsDao {
+    pool: sqlx::PgPool,
+}
+
+impl LoginAuditsDao {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl LoginAuditsDaoTrait for LoginAuditsDao {
+    async fn record_login_event(
+        &self,
+        event: LoginAuditEvent,
+        email: &str,
+        user_id: Option<Uuid>,
+        ip_address: Option<String>,
+    ) -> ConduitResult<LoginAuditEventEntity> {
+        let uuid = Uuid::now_v7();
+        let event = sqlx::query_as!(
+            LoginAuditEventEntity,
+            r#"
+            INSERT INTO login_audit_events (id, user_id, email, ip_address, event)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+            uuid,
+            user_id,
+            email,
+            ip_address,
+            event.as_str()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("unexpected error: while inserting login_audit_event")?;
+        Ok(event)
+    }
+
+    async fn get_login_events_by_email(
+        &self,
+        email: &str,
+    ) -> ConduitResult<Vec<LoginAuditEventEntity>> {
+        let events = sqlx::query_as!(
+            LoginAuditEventEntity,
+            r#"
+            SELECT *
+            FROM login_audit_events
+            WHERE email = $1
+            ORDER BY created_at, id
+            "#,
+            email
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("unexpected error: while fetching login_audit_events")?;
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn record_login_event(pool: PgPool) {
+        let dao = LoginAuditsDao::new(pool);
+        dao.record_login_event(
+            LoginAuditEvent::Failed,
+            "audit@example.com",
+            None,
+            Some("127.0.0.1".to_string()),
+        )
+        .await
+        .unwrap();
+        dao.record_login_event(LoginAuditEvent::Locked, "audit@example.com", None, None)
+            .await
+            .unwrap();
+
+        let events = dao
+            .get_login_events_by_email("audit@example.com")
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event, "failed");
+        assert_eq!(events[0].ip_address.as_deref(), Some("127.0.0.1"));
+        assert_eq!(events[1].event, "locked");
+    }
+}
@@ -0,0 +1,185 @@
+use std::time::Duration;
+
+use anyhow::Context as _;
+use axum::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    core::password_resets::{dao_trait::PasswordResetsDaoTrait, entity::PasswordResetTokenEntity},
+    error::ConduitResult,
+};
+
+#[derive(Clone)]
+pub struct PasswordResetsDao {
+    pool: sqlx::PgPool,
+}
+
+impl PasswordResetsDao {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl PasswordResetsDaoTrait for PasswordResetsDao {
+    async fn create_reset_token(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        ttl: Duration,
+    ) -> ConduitResult<PasswordResetTokenEntity> {
+        let uuid = Uuid::now_v7();
+        let token = sqlx::query_as!(
+            PasswordResetTokenEntity,
+            r#"
+            INSERT INTO password_reset_tokens (id, user_id, token_hash, expires_at)
+            VALUES ($1, $2, $3, CURRENT_TIMESTAMP + make_interval(secs => $4))
+            RETURNING *
+            "#,
+            uuid,
+            user_id,
+            token_hash,
+            ttl.as_secs_f64()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("unexpected error: while inserting password_reset_token")?;
+        Ok(token)
+    }
+
+    async fn find_reset_token(
+        &self,
+        token_hash: &str,
+    ) -> ConduitResult<Option<PasswordResetTokenEntity>> {
+        let token = sqlx::query_as!(
+            PasswordResetTokenEntity,
+            r#"
+            SELECT *
+            FROM password_reset_tokens
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+            "#,
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("unexpected error: while fetching password_reset_token")?;
+        Ok(token)
+    }
+
+    async fn consume_reset_token(
+        &self,
+        token_hash: &str,
+    ) -> ConduitResult<Option<PasswordResetTokenEntity>> {
+        // 使用済みにするのと同時に取得することで，同じトークンが2回使われないようにする
+        let token = sqlx::query_as!(
+            PasswordResetTokenEntity,
+            r#"
+            UPDATE password_reset_tokens
+            SET used_at = CURRENT_TIMESTAMP
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+            RETURNING *
+            "#,
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("unexpected error: while consuming password_reset_token")?;
+        Ok(token)
+    }
+
+    async fn delete_unused_tokens(&self, user_id: Uuid) -> ConduitResult<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM password_reset_tokens
+            WHERE user_id = $1 AND used_at IS NULL
+            "#,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while deleting password_reset_tokens")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::users::{dao_trait::UsersDaoTrait as _, dto::PasswdHashedNewUser},
+        dao::users::UserDao,
+    };
+    use sqlx::PgPool;
+
+    async fn setup_user(pool: &PgPool) -> Uuid {
+        let user_dao = UserDao::new(pool.clone());
+        let new_user = PasswdHashedNewUser::new(
+            "reset".to_string(),
+            "reset@example.com".to_string(),
+            "password".to_string(),
+        );
+        user_dao.create_user(new_user).await.unwrap().id
+    }
+
+    #[sqlx::test]
+    async fn consume_reset_token_only_once(pool: PgPool) {
+        let user_id = setup_user(&pool).await;
+        let dao = PasswordResetsDao::new(pool);
+        dao.create_reset_token(user_id, "hash", Duration::from_secs(60 * 60))
+            .await
+            .unwrap();
+
+        let token = dao.consume_reset_token("hash").await.unwrap().unwrap();
+        assert_eq!(token.user_id, user_id);
+        assert!(token.used_at.is_some());
+
+        // 2回目は使えない
+        let token = dao.consume_reset_token("hash").await.unwrap();
+        assert_eq!(token, None);
+    }
+
+    #[sqlx::test]
+    async fn find_reset_token_does_not_consume(pool: PgPool) {
+        let user_id = setup_user(&pool).await;
+        let dao = PasswordResetsDao::new(pool);
+        dao.create_reset_token(user_id, "hash", Duration::from_secs(60 * 60))
+            .await
+            .unwrap();
+
+        let token = dao.find_reset_token("hash").await.unwrap().unwrap();
+        assert_eq!(token.user_id, user_id);
+        assert!(token.used_at.is_none());
+
+        // 確認した後でも消費できる
+        assert!(dao.consume_reset_token("hash").await.unwrap().is_some());
+        assert_eq!(dao.find_reset_token("hash").await.unwrap(), None);
+    }
+
+    #[sqlx::test]
+    async fn expired_reset_token_is_rejected(pool: PgPool) {
+        let user_id = setup_user(&pool).await;
+        let dao = PasswordResetsDao::new(pool);
+        dao.create_reset_token(user_id, "hash", Duration::ZERO)
+            .await
+            .unwrap();
+
+        let token = dao.consume_reset_token("hash").await.unwrap();
+        assert_eq!(token, None);
+    }
+
+    #[sqlx::test]
+    async fn delete_unused_tokens(pool: PgPool) {
+        let user_id = setup_user(&pool).await;
+        let dao = PasswordResetsDao::new(pool);
+        dao.create_reset_token(user_id, "hash1", Duration::from_secs(60))
+            .await
+            .unwrap();
+        dao.create_reset_token(user_id, "hash2", Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        dao.delete_unused_tokens(user_id).await.unwrap();
+        assert_eq!(dao.consume_reset_token("hash1").await.unwrap(), None);
+        assert_eq!(dao.consume_reset_token("hash2").await.unwrap(), None);
+    }
+}
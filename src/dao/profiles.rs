@@ -1,5 +1,14 @@
 use crate::{
-    core::profiles::{dao_trait::ProfilesDaoTrait, entity::UserFollowEntity},
+    core::{
+        profiles::{
+            dao_trait::ProfilesDaoTrait,
+            entity::{
+                FollowCountsEntity, FollowRequestEntity, UserBlockEntity, UserFollowEntity,
+                UserMuteEntity,
+            },
+        },
+        users::entity::UserEntity,
+    },
     error::ConduitResult,
 };
 use anyhow::Context as _;
@@ -50,6 +59,8 @@ impl ProfilesDaoTrait for ProfileDao {
             r#"
             INSERT INTO user_follows (id, follower_id, followee_id)
             VALUES ($1, $2, $3)
+            ON CONFLICT (follower_id, followee_id)
+                DO UPDATE SET follower_id = user_follows.follower_id
             RETURNING id, created_at, follower_id, followee_id
             "#,
             uuid,
@@ -66,7 +77,7 @@ impl ProfilesDaoTrait for ProfileDao {
         &self,
         follower_id: Uuid,
         followee_id: Uuid,
-    ) -> ConduitResult<UserFollowEntity> {
+    ) -> ConduitResult<Option<UserFollowEntity>> {
         let user_follow = sqlx::query_as!(
             UserFollowEntity,
             r#"
@@ -77,7 +88,7 @@ impl ProfilesDaoTrait for ProfileDao {
             follower_id,
             followee_id
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
         .await
         .context("unexpected error: while deleting user_follow")?;
         Ok(user_follow)
@@ -105,14 +116,481 @@ impl ProfilesDaoTrait for ProfileDao {
         .context("unexpected error: while fetching user_follow")?;
         Ok(user_follow)
     }
+
+    async fn get_followers(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> ConduitResult<Vec<UserEntity>> {
+        let followers = sqlx::query_as!(
+            UserEntity,
+            r#"
+            SELECT u.*
+            FROM user_follows f
+            JOIN users u ON u.id = f.follower_id
+            WHERE f.followee_id = $1
+            ORDER BY f.created_at DESC, f.id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            user_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("unexpected error: while fetching followers")?;
+        Ok(followers)
+    }
+
+    async fn get_following(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> ConduitResult<Vec<UserEntity>> {
+        let following = sqlx::query_as!(
+            UserEntity,
+            r#"
+            SELECT u.*
+            FROM user_follows f
+            JOIN users u ON u.id = f.followee_id
+            WHERE f.follower_id = $1
+            ORDER BY f.created_at DESC, f.id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            user_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("unexpected error: while fetching following")?;
+        Ok(following)
+    }
+
+    async fn get_follow_counts(&self, user_id: Uuid) -> ConduitResult<FollowCountsEntity> {
+        let counts = sqlx::query_as!(
+            FollowCountsEntity,
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM user_follows WHERE followee_id = $1) AS "followers_count!",
+                (SELECT COUNT(*) FROM user_follows WHERE follower_id = $1) AS "following_count!"
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("unexpected error: while counting user_follows")?;
+        Ok(counts)
+    }
+
+    async fn get_follow_suggestions(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> ConduitResult<Vec<UserEntity>> {
+        // 共通のフォロー1人につき3点，重なるタグ1つにつき2点，30日以内に投稿していれば1点
+        let suggestions = sqlx::query_as!(
+            UserEntity,
+            r#"
+            WITH followees AS (
+                SELECT followee_id FROM user_follows WHERE follower_id = $1
+            ),
+            favorite_tags AS (
+                SELECT DISTINCT at.tag_id
+                FROM favorites f
+                JOIN article_tags at ON at.article_id = f.article_id
+                WHERE f.user_id = $1 AND f.is_deleted = FALSE
+            ),
+            mutuals AS (
+                SELECT uf.followee_id AS user_id, COUNT(*) AS mutual_count
+                FROM user_follows uf
+                JOIN followees fe ON fe.followee_id = uf.follower_id
+                GROUP BY uf.followee_id
+            ),
+            candidates AS (
+                SELECT user_id FROM mutuals
+                UNION
+                SELECT user_id FROM user_activity_stats
+                WHERE tag_ids && ARRAY(SELECT tag_id FROM favorite_tags)
+                    OR last_published_at > CURRENT_TIMESTAMP - INTERVAL '30 days'
+            ),
+            scored AS (
+                SELECT
+                    c.user_id,
+                    COALESCE(m.mutual_count, 0) * 3
+                        + (
+                            SELECT COUNT(*)
+                            FROM unnest(s.tag_ids) AS t(tag_id)
+                            WHERE t.tag_id IN (SELECT tag_id FROM favorite_tags)
+                        ) * 2
+                        + CASE
+                            WHEN s.last_published_at > CURRENT_TIMESTAMP - INTERVAL '30 days' THEN 1
+                            ELSE 0
+                        END AS score,
+                    s.last_published_at
+                FROM candidates c
+                LEFT JOIN mutuals m ON m.user_id = c.user_id
+                LEFT JOIN user_activity_stats s ON s.user_id = c.user_id
+                WHERE c.user_id <> $1
+                    AND c.user_id NOT IN (SELECT followee_id FROM followees)
+                    AND NOT EXISTS (
+                        SELECT 1 FROM user_blocks b
+                        WHERE (b.blocker_id = $1 AND b.blocked_id = c.user_id)
+                            OR (b.blocker_id = c.user_id AND b.blocked_id = $1)
+                    )
+            )
+            SELECT u.*
+            FROM scored s
+            JOIN users u ON u.id = s.user_id AND u.deleted_at IS NULL
+            ORDER BY s.score DESC, s.last_published_at DESC NULLS LAST, u.id
+            LIMIT $2
+            "#,
+            user_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("unexpected error: while fetching follow suggestions")?;
+        Ok(suggestions)
+    }
+
+    async fn refresh_follow_suggestions(&self) -> ConduitResult<()> {
+        // CONCURRENTLYなら更新中もおすすめを読める
+        sqlx::query!("REFRESH MATERIALIZED VIEW CONCURRENTLY user_activity_stats")
+            .execute(&self.pool)
+            .await
+            .context("unexpected error: while refreshing user_activity_stats")?;
+        Ok(())
+    }
+
+    async fn request_follow(
+        &self,
+        requester_id: Uuid,
+        target_id: Uuid,
+    ) -> ConduitResult<FollowRequestEntity> {
+        let uuid = Uuid::now_v7();
+        let request = sqlx::query_as!(
+            FollowRequestEntity,
+            r#"
+            INSERT INTO follow_requests (id, requester_id, target_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (requester_id, target_id)
+                DO UPDATE SET requester_id = follow_requests.requester_id
+            RETURNING id, created_at, requester_id, target_id
+            "#,
+            uuid,
+            requester_id,
+            target_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("unexpected error: while inserting follow_request")?;
+        Ok(request)
+    }
+
+    async fn has_follow_request(&self, requester_id: Uuid, target_id: Uuid) -> ConduitResult<bool> {
+        let exists = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM follow_requests
+                WHERE requester_id = $1 AND target_id = $2
+            ) AS "exists!"
+            "#,
+            requester_id,
+            target_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("unexpected error: while fetching follow_request")?;
+        Ok(exists)
+    }
+
+    async fn get_follow_requests(
+        &self,
+        target_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> ConduitResult<Vec<UserEntity>> {
+        let requesters = sqlx::query_as!(
+            UserEntity,
+            r#"
+            SELECT u.*
+            FROM follow_requests r
+            JOIN users u ON u.id = r.requester_id
+            WHERE r.target_id = $1
+            ORDER BY r.created_at, r.id
+            LIMIT $2 OFFSET $3
+            "#,
+            target_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("unexpected error: while fetching follow_requests")?;
+        Ok(requesters)
+    }
+
+    async fn count_follow_requests(&self, target_id: Uuid) -> ConduitResult<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM follow_requests
+            WHERE target_id = $1
+            "#,
+            target_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("unexpected error: while counting follow_requests")?;
+        Ok(count)
+    }
+
+    async fn approve_follow_request(
+        &self,
+        requester_id: Uuid,
+        target_id: Uuid,
+    ) -> ConduitResult<Option<UserFollowEntity>> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("unexpected error: while starting transaction")?;
+
+        let deleted = sqlx::query!(
+            r#"
+            DELETE FROM follow_requests
+            WHERE requester_id = $1 AND target_id = $2
+            "#,
+            requester_id,
+            target_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting follow_request")?;
+        if deleted.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let uuid = Uuid::now_v7();
+        let user_follow = sqlx::query_as!(
+            UserFollowEntity,
+            r#"
+            INSERT INTO user_follows (id, follower_id, followee_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (follower_id, followee_id)
+                DO UPDATE SET follower_id = user_follows.follower_id
+            RETURNING id, created_at, follower_id, followee_id
+            "#,
+            uuid,
+            requester_id,
+            target_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("unexpected error: while inserting user_follow")?;
+
+        tx.commit()
+            .await
+            .context("unexpected error: while committing transaction")?;
+        Ok(Some(user_follow))
+    }
+
+    async fn delete_follow_request(
+        &self,
+        requester_id: Uuid,
+        target_id: Uuid,
+    ) -> ConduitResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM follow_requests
+            WHERE requester_id = $1 AND target_id = $2
+            "#,
+            requester_id,
+            target_id
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while deleting follow_request")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn block_user(
+        &self,
+        blocker_id: Uuid,
+        blocked_id: Uuid,
+    ) -> ConduitResult<UserBlockEntity> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("unexpected error: while starting transaction")?;
+
+        let uuid = Uuid::now_v7();
+        let user_block = sqlx::query_as!(
+            UserBlockEntity,
+            r#"
+            INSERT INTO user_blocks (id, blocker_id, blocked_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (blocker_id, blocked_id) DO UPDATE SET blocker_id = user_blocks.blocker_id
+            RETURNING id, created_at, blocker_id, blocked_id
+            "#,
+            uuid,
+            blocker_id,
+            blocked_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .context("unexpected error: while inserting user_block")?;
+
+        // ブロックした側・された側どちらのフォローも解除する
+        sqlx::query!(
+            r#"
+            DELETE FROM user_follows
+            WHERE (follower_id = $1 AND followee_id = $2)
+                OR (follower_id = $2 AND followee_id = $1)
+            "#,
+            blocker_id,
+            blocked_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting user_follows")?;
+        sqlx::query!(
+            r#"
+            DELETE FROM follow_requests
+            WHERE (requester_id = $1 AND target_id = $2)
+                OR (requester_id = $2 AND target_id = $1)
+            "#,
+            blocker_id,
+            blocked_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting follow_requests")?;
+
+        tx.commit()
+            .await
+            .context("unexpected error: while committing transaction")?;
+        Ok(user_block)
+    }
+
+    async fn unblock_user(&self, blocker_id: Uuid, blocked_id: Uuid) -> ConduitResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_blocks
+            WHERE blocker_id = $1 AND blocked_id = $2
+            "#,
+            blocker_id,
+            blocked_id
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while deleting user_block")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn is_blocking(&self, blocker_id: Uuid, blocked_id: Uuid) -> ConduitResult<bool> {
+        let blocking = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM user_blocks
+                WHERE blocker_id = $1 AND blocked_id = $2
+            ) AS "exists!"
+            "#,
+            blocker_id,
+            blocked_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("unexpected error: while fetching user_block")?;
+        Ok(blocking)
+    }
+
+    async fn is_blocked_between(&self, user_a: Uuid, user_b: Uuid) -> ConduitResult<bool> {
+        let blocked = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM user_blocks
+                WHERE (blocker_id = $1 AND blocked_id = $2)
+                    OR (blocker_id = $2 AND blocked_id = $1)
+            ) AS "exists!"
+            "#,
+            user_a,
+            user_b
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("unexpected error: while fetching user_blocks")?;
+        Ok(blocked)
+    }
+
+    async fn mute_user(&self, muter_id: Uuid, muted_id: Uuid) -> ConduitResult<UserMuteEntity> {
+        let uuid = Uuid::now_v7();
+        let user_mute = sqlx::query_as!(
+            UserMuteEntity,
+            r#"
+            INSERT INTO user_mutes (id, muter_id, muted_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (muter_id, muted_id) DO UPDATE SET muter_id = user_mutes.muter_id
+            RETURNING id, created_at, muter_id, muted_id
+            "#,
+            uuid,
+            muter_id,
+            muted_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("unexpected error: while inserting user_mute")?;
+        Ok(user_mute)
+    }
+
+    async fn unmute_user(&self, muter_id: Uuid, muted_id: Uuid) -> ConduitResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_mutes
+            WHERE muter_id = $1 AND muted_id = $2
+            "#,
+            muter_id,
+            muted_id
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while deleting user_mute")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_muted_user_ids(&self, muter_id: Uuid) -> ConduitResult<Vec<Uuid>> {
+        let muted_ids = sqlx::query_scalar!(
+            r#"
+            SELECT muted_id
+            FROM user_mutes
+            WHERE muter_id = $1
+            "#,
+            muter_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("unexpected error: while fetching user_mutes")?;
+        Ok(muted_ids)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        core::users::{dao_trait::UsersDaoTrait, dto::NewUser, entity::UserEntity},
-        dao::users::UserDao,
+        core::{
+            account_data::dao_trait::AccountDataDaoTrait,
+            users::{dao_trait::UsersDaoTrait, dto::NewUser, entity::UserEntity},
+        },
+        dao::{
+            account_data::AccountDataDao,
+            test_support::{setup_tagged_article, setup_user},
+            users::UserDao,
+        },
         services::hash::PasswordHashService,
     };
     use sqlx::PgPool;
@@ -123,17 +601,21 @@ mod tests {
         let new_a_user = NewUser {
             username: Some("test_user_a".to_string()),
             email: Some("test@example.com".to_string()),
-            password: Some("password".to_string()),
+            password: Some("password".to_string().into()),
         };
-        let new_a_user = PasswordHashService::hash_password_newuser(new_a_user).unwrap();
+        let new_a_user = PasswordHashService::default()
+            .hash_password_newuser(new_a_user)
+            .unwrap();
         let user_a = user_dao.create_user(new_a_user).await.unwrap();
 
         let new_b_user = NewUser {
             username: Some("test_user_b".to_string()),
             email: Some("testb@eexample.com".to_string()),
-            password: Some("password".to_string()),
+            password: Some("password".to_string().into()),
         };
-        let new_b_user = PasswordHashService::hash_password_newuser(new_b_user).unwrap();
+        let new_b_user = PasswordHashService::default()
+            .hash_password_newuser(new_b_user)
+            .unwrap();
         let user_b = user_dao.create_user(new_b_user).await.unwrap();
         (user_a, user_b)
     }
@@ -171,6 +653,7 @@ mod tests {
         let user_follow = profile_dao
             .unfollow_user(user_a.id, user_b.id)
             .await
+            .unwrap()
             .unwrap();
         assert_eq!(user_follow.follower_id, user_a.id);
         assert_eq!(user_follow.followee_id, user_b.id);
@@ -232,4 +715,323 @@ mod tests {
         let user_follow = profile_dao.is_follow(user_a.id, user_b.id).await.unwrap();
         assert_eq!(user_follow, None);
     }
+
+    // ブロックするとお互いのフォローが解除される
+    #[sqlx::test]
+    async fn test_block_user(pool: PgPool) {
+        let (user_a, user_b) = setup_user_ab(&pool).await;
+        let profile_dao = ProfileDao::new(pool.clone());
+        profile_dao
+            .following_user(user_a.id, user_b.id)
+            .await
+            .unwrap();
+        profile_dao
+            .following_user(user_b.id, user_a.id)
+            .await
+            .unwrap();
+
+        let block = profile_dao.block_user(user_a.id, user_b.id).await.unwrap();
+        assert_eq!(block.blocker_id, user_a.id);
+        // 2回ブロックしても同じものが返る
+        let again = profile_dao.block_user(user_a.id, user_b.id).await.unwrap();
+        assert_eq!(again, block);
+
+        assert!(profile_dao.is_blocking(user_a.id, user_b.id).await.unwrap());
+        assert!(!profile_dao.is_blocking(user_b.id, user_a.id).await.unwrap());
+        assert!(profile_dao
+            .is_blocked_between(user_b.id, user_a.id)
+            .await
+            .unwrap());
+        assert_eq!(
+            profile_dao.is_follow(user_a.id, user_b.id).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            profile_dao.is_follow(user_b.id, user_a.id).await.unwrap(),
+            None
+        );
+
+        assert!(profile_dao
+            .unblock_user(user_a.id, user_b.id)
+            .await
+            .unwrap());
+        assert!(!profile_dao
+            .unblock_user(user_a.id, user_b.id)
+            .await
+            .unwrap());
+        assert!(!profile_dao
+            .is_blocked_between(user_a.id, user_b.id)
+            .await
+            .unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_mute_user(pool: PgPool) {
+        let (user_a, user_b) = setup_user_ab(&pool).await;
+        let profile_dao = ProfileDao::new(pool.clone());
+
+        let mute = profile_dao.mute_user(user_a.id, user_b.id).await.unwrap();
+        assert_eq!(mute.muted_id, user_b.id);
+        assert_eq!(
+            profile_dao.get_muted_user_ids(user_a.id).await.unwrap(),
+            vec![user_b.id]
+        );
+        // ミュートは一方向
+        assert!(profile_dao
+            .get_muted_user_ids(user_b.id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(profile_dao.unmute_user(user_a.id, user_b.id).await.unwrap());
+        assert!(profile_dao
+            .get_muted_user_ids(user_a.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_followers_and_following(pool: PgPool) {
+        let (user_a, user_b) = setup_user_ab(&pool).await;
+        let user_c = UserDao::new(pool.clone())
+            .create_user(
+                PasswordHashService::default()
+                    .hash_password_newuser(NewUser {
+                        username: Some("test_user_c".to_string()),
+                        email: Some("testc@example.com".to_string()),
+                        password: Some("password".to_string().into()),
+                    })
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // BとCがAをフォローし，AはBをフォローする
+        let profile_dao = ProfileDao::new(pool.clone());
+        profile_dao
+            .following_user(user_b.id, user_a.id)
+            .await
+            .unwrap();
+        profile_dao
+            .following_user(user_c.id, user_a.id)
+            .await
+            .unwrap();
+        profile_dao
+            .following_user(user_a.id, user_b.id)
+            .await
+            .unwrap();
+
+        // 新しい順に返る
+        let followers = profile_dao.get_followers(user_a.id, 10, 0).await.unwrap();
+        let usernames = followers.iter().map(|u| &u.username).collect::<Vec<_>>();
+        assert_eq!(usernames, vec!["test_user_c", "test_user_b"]);
+        let followers = profile_dao.get_followers(user_a.id, 1, 1).await.unwrap();
+        assert_eq!(followers[0].id, user_b.id);
+
+        let following = profile_dao.get_following(user_a.id, 10, 0).await.unwrap();
+        assert_eq!(following.len(), 1);
+        assert_eq!(following[0].id, user_b.id);
+
+        let counts = profile_dao.get_follow_counts(user_a.id).await.unwrap();
+        assert_eq!(
+            counts,
+            FollowCountsEntity {
+                followers_count: 2,
+                following_count: 1
+            }
+        );
+    }
+
+    // 2回フォローしても1件だけになり，フォロー解除も繰り返せる
+    #[sqlx::test]
+    async fn test_follow_is_idempotent(pool: PgPool) {
+        let (user_a, user_b) = setup_user_ab(&pool).await;
+        let profile_dao = ProfileDao::new(pool.clone());
+
+        let first = profile_dao
+            .following_user(user_a.id, user_b.id)
+            .await
+            .unwrap();
+        let second = profile_dao
+            .following_user(user_a.id, user_b.id)
+            .await
+            .unwrap();
+        assert_eq!(first, second);
+        let counts = profile_dao.get_follow_counts(user_b.id).await.unwrap();
+        assert_eq!(counts.followers_count, 1);
+
+        let removed = profile_dao
+            .unfollow_user(user_a.id, user_b.id)
+            .await
+            .unwrap();
+        assert_eq!(removed, Some(first));
+        let removed = profile_dao
+            .unfollow_user(user_a.id, user_b.id)
+            .await
+            .unwrap();
+        assert_eq!(removed, None);
+    }
+
+    #[sqlx::test]
+    async fn test_cannot_follow_self(pool: PgPool) {
+        let (user_a, _) = setup_user_ab(&pool).await;
+        let profile_dao = ProfileDao::new(pool.clone());
+        let result = profile_dao.following_user(user_a.id, user_a.id).await;
+        assert!(result.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_follow_request(pool: PgPool) {
+        let (user_a, user_b) = setup_user_ab(&pool).await;
+        let profile_dao = ProfileDao::new(pool.clone());
+
+        // AがBにフォローリクエストを送る
+        let request = profile_dao
+            .request_follow(user_a.id, user_b.id)
+            .await
+            .unwrap();
+        let again = profile_dao
+            .request_follow(user_a.id, user_b.id)
+            .await
+            .unwrap();
+        assert_eq!(request, again);
+        assert!(profile_dao
+            .has_follow_request(user_a.id, user_b.id)
+            .await
+            .unwrap());
+        assert_eq!(
+            profile_dao.count_follow_requests(user_b.id).await.unwrap(),
+            1
+        );
+        let requesters = profile_dao
+            .get_follow_requests(user_b.id, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(requesters[0].id, user_a.id);
+        // リクエストの段階ではフォローしていない
+        assert_eq!(
+            profile_dao.is_follow(user_a.id, user_b.id).await.unwrap(),
+            None
+        );
+
+        let follow = profile_dao
+            .approve_follow_request(user_a.id, user_b.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(follow.follower_id, user_a.id);
+        assert!(!profile_dao
+            .has_follow_request(user_a.id, user_b.id)
+            .await
+            .unwrap());
+        // リクエストがなければ承認できない
+        assert_eq!(
+            profile_dao
+                .approve_follow_request(user_b.id, user_a.id)
+                .await
+                .unwrap(),
+            None
+        );
+
+        profile_dao
+            .request_follow(user_b.id, user_a.id)
+            .await
+            .unwrap();
+        assert!(profile_dao
+            .delete_follow_request(user_b.id, user_a.id)
+            .await
+            .unwrap());
+        assert!(!profile_dao
+            .delete_follow_request(user_b.id, user_a.id)
+            .await
+            .unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_follow_suggestions(pool: PgPool) {
+        let user = setup_user(&pool, "suggest_user").await;
+        let followee = setup_user(&pool, "suggest_followee").await;
+        let friend_of_friend = setup_user(&pool, "suggest_fof").await;
+        let same_tags = setup_user(&pool, "suggest_same_tags").await;
+        let blocked = setup_user(&pool, "suggest_blocked").await;
+        let profile_dao = ProfileDao::new(pool.clone());
+
+        // フォローしている人がフォローしている
+        profile_dao
+            .following_user(user.id, followee.id)
+            .await
+            .unwrap();
+        profile_dao
+            .following_user(followee.id, friend_of_friend.id)
+            .await
+            .unwrap();
+        // いいねした記事と同じタグで最近投稿している
+        let article_id = setup_tagged_article(&pool, same_tags.id, "rust-article", "rust").await;
+        sqlx::query!(
+            "INSERT INTO favorites (user_id, article_id) VALUES ($1, $2)",
+            user.id,
+            article_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        // 自分自身やブロックした相手は，最近投稿していてもおすすめしない
+        setup_tagged_article(&pool, user.id, "own-article", "rust").await;
+        setup_tagged_article(&pool, blocked.id, "blocked-article", "rust").await;
+        profile_dao.block_user(user.id, blocked.id).await.unwrap();
+
+        // 集計を更新するまでは記事の情報が反映されない
+        let suggestions = profile_dao
+            .get_follow_suggestions(user.id, 10)
+            .await
+            .unwrap();
+        let ids = suggestions.iter().map(|u| u.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![friend_of_friend.id]);
+
+        profile_dao.refresh_follow_suggestions().await.unwrap();
+        let suggestions = profile_dao
+            .get_follow_suggestions(user.id, 10)
+            .await
+            .unwrap();
+        let ids = suggestions.iter().map(|u| u.id).collect::<Vec<_>>();
+        // スコアが同じ場合は最近投稿している方が先
+        assert_eq!(ids, vec![same_tags.id, friend_of_friend.id]);
+
+        let suggestions = profile_dao
+            .get_follow_suggestions(user.id, 1)
+            .await
+            .unwrap();
+        assert_eq!(suggestions.len(), 1);
+    }
+
+    // 退会して匿名化されたユーザーは，記事が残っていてもおすすめしない
+    #[sqlx::test]
+    async fn test_follow_suggestions_exclude_deleted_users(pool: PgPool) {
+        let user = setup_user(&pool, "suggest_viewer").await;
+        let deleted = setup_user(&pool, "suggest_deleted").await;
+        setup_tagged_article(&pool, deleted.id, "deleted-article", "rust").await;
+        let profile_dao = ProfileDao::new(pool.clone());
+
+        profile_dao.refresh_follow_suggestions().await.unwrap();
+        let suggestions = profile_dao
+            .get_follow_suggestions(user.id, 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            suggestions.iter().map(|u| u.id).collect::<Vec<_>>(),
+            vec![deleted.id]
+        );
+
+        AccountDataDao::new(pool.clone())
+            .anonymize_user(deleted.id)
+            .await
+            .unwrap();
+        profile_dao.refresh_follow_suggestions().await.unwrap();
+        let suggestions = profile_dao
+            .get_follow_suggestions(user.id, 10)
+            .await
+            .unwrap();
+        assert!(suggestions.is_empty());
+    }
 }
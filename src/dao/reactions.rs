@@ -0,0 +1,186 @@
+use crate::{
+    core::reactions::{
+        dao_trait::ReactionsDaoTrait,
+        entity::{ReactionCountEntity, ReactionEntity},
+    },
+    error::ConduitResult,
+};
+use anyhow::Context as _;
+use axum::async_trait;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct ReactionsDao {
+    pool: sqlx::PgPool,
+}
+
+impl ReactionsDao {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ReactionsDaoTrait for ReactionsDao {
+    async fn add_reaction(
+        &self,
+        user_id: Uuid,
+        article_id: i32,
+        kind: &str,
+    ) -> ConduitResult<ReactionEntity> {
+        // 何も変えないUPDATEで，既存の行もRETURNINGで返す
+        let reaction = sqlx::query_as!(
+            ReactionEntity,
+            r#"
+            INSERT INTO article_reactions (user_id, article_id, kind)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, article_id, kind) DO UPDATE
+            SET kind = article_reactions.kind
+            RETURNING *
+            "#,
+            user_id,
+            article_id,
+            kind
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to add reaction")?;
+
+        Ok(reaction)
+    }
+
+    async fn remove_reaction(
+        &self,
+        user_id: Uuid,
+        article_id: i32,
+        kind: &str,
+    ) -> ConduitResult<Option<ReactionEntity>> {
+        let reaction = sqlx::query_as!(
+            ReactionEntity,
+            r#"
+            DELETE FROM article_reactions
+            WHERE user_id = $1 AND article_id = $2 AND kind = $3
+            RETURNING *
+            "#,
+            user_id,
+            article_id,
+            kind
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to remove reaction")?;
+
+        Ok(reaction)
+    }
+
+    async fn get_reaction_counts(
+        &self,
+        article_id: i32,
+    ) -> ConduitResult<Vec<ReactionCountEntity>> {
+        let counts = sqlx::query_as!(
+            ReactionCountEntity,
+            r#"
+            SELECT kind, COUNT(*) AS "count!"
+            FROM article_reactions
+            WHERE article_id = $1
+            GROUP BY kind
+            ORDER BY kind
+            "#,
+            article_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get reaction counts")?;
+
+        Ok(counts)
+    }
+
+    async fn get_user_reactions(
+        &self,
+        user_id: Uuid,
+        article_id: i32,
+    ) -> ConduitResult<Vec<String>> {
+        let kinds = sqlx::query_scalar!(
+            r#"
+            SELECT kind FROM article_reactions
+            WHERE user_id = $1 AND article_id = $2
+            ORDER BY kind
+            "#,
+            user_id,
+            article_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get user reactions")?;
+
+        Ok(kinds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao::test_support::{setup_article, setup_user};
+    use sqlx::PgPool;
+
+    fn count(kind: &str, count: i64) -> ReactionCountEntity {
+        ReactionCountEntity {
+            kind: kind.to_string(),
+            count,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_add_and_remove_reactions(pool: PgPool) {
+        let dao = ReactionsDao::new(pool.clone());
+        let author = setup_user(&pool, "author").await;
+        let alice = setup_user(&pool, "alice").await;
+        let bob = setup_user(&pool, "bob").await;
+        let article_id = setup_article(&pool, author.id, "article").await;
+
+        // 同じ種類は1つだけ 違う種類は並べて付けられる
+        let first = dao
+            .add_reaction(alice.id, article_id, "heart")
+            .await
+            .unwrap();
+        let second = dao
+            .add_reaction(alice.id, article_id, "heart")
+            .await
+            .unwrap();
+        assert_eq!(first, second);
+        dao.add_reaction(alice.id, article_id, "rocket")
+            .await
+            .unwrap();
+        dao.add_reaction(bob.id, article_id, "heart").await.unwrap();
+
+        assert_eq!(
+            dao.get_reaction_counts(article_id).await.unwrap(),
+            vec![count("heart", 2), count("rocket", 1)]
+        );
+        assert_eq!(
+            dao.get_user_reactions(alice.id, article_id).await.unwrap(),
+            vec!["heart".to_string(), "rocket".to_string()]
+        );
+
+        let removed = dao
+            .remove_reaction(alice.id, article_id, "heart")
+            .await
+            .unwrap();
+        assert_eq!(removed, Some(first));
+        // リアクションしていない場合もエラーにしない
+        let removed = dao
+            .remove_reaction(alice.id, article_id, "heart")
+            .await
+            .unwrap();
+        assert!(removed.is_none());
+
+        assert_eq!(
+            dao.get_reaction_counts(article_id).await.unwrap(),
+            vec![count("heart", 1), count("rocket", 1)]
+        );
+        assert_eq!(
+            dao.get_user_reactions(alice.id, article_id).await.unwrap(),
+            vec!["rocket".to_string()]
+        );
+    }
+}
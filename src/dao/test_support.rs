@@ -0,0 +1,65 @@
+//! DAOのテストで使うデータの準備
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    core::users::{dao_trait::UsersDaoTrait, dto::PasswdHashedNewUser, entity::UserEntity},
+    dao::users::UserDao,
+};
+
+/// `<username>@example.com`のユーザーを作る
+pub(crate) async fn setup_user(pool: &PgPool, username: &str) -> UserEntity {
+    let user_dao = UserDao::new(pool.clone());
+    user_dao
+        .create_user(PasswdHashedNewUser::new(
+            username.to_string(),
+            format!("{}@example.com", username),
+            "password".to_string(),
+        ))
+        .await
+        .unwrap()
+}
+
+/// slugをタイトルにした記事を作り，そのIDを返す
+pub(crate) async fn setup_article(pool: &PgPool, author_id: Uuid, slug: &str) -> i32 {
+    sqlx::query_scalar!(
+        r#"
+        INSERT INTO articles (title, description, body, slug, author_id)
+        VALUES ($1, 'description', 'body', $1, $2)
+        RETURNING id
+        "#,
+        slug,
+        author_id
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+/// タグ付きの記事を作る
+pub(crate) async fn setup_tagged_article(
+    pool: &PgPool,
+    author_id: Uuid,
+    slug: &str,
+    tag: &str,
+) -> i32 {
+    let article_id = setup_article(pool, author_id, slug).await;
+    sqlx::query!(
+        r#"
+        WITH tag AS (
+            INSERT INTO tags (tag) VALUES ($1)
+            ON CONFLICT (tag) DO UPDATE SET tag = tags.tag
+            RETURNING id
+        )
+        INSERT INTO article_tags (article_id, tag_id)
+        SELECT $2, id FROM tag
+        "#,
+        tag,
+        article_id
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+    article_id
+}
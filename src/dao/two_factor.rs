@@ -0,0 +1,357 @@
+use std::time::Duration;
+
+use anyhow::Context as _;
+use axum::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    core::two_factor::{
+        dao_trait::TwoFactorDaoTrait,
+        entity::{TwoFactorChallengeEntity, UserTotpEntity},
+    },
+    error::ConduitResult,
+};
+
+#[derive(Clone)]
+pub struct TwoFactorDao {
+    pool: sqlx::PgPool,
+}
+
+impl TwoFactorDao {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl TwoFactorDaoTrait for TwoFactorDao {
+    async fn create_pending_totp(
+        &self,
+        user_id: Uuid,
+        secret_ciphertext: Vec<u8>,
+        recovery_code_hashes: Vec<String>,
+    ) -> ConduitResult<bool> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("unexpected error: while beginning transaction")?;
+
+        // 有効になっている設定は置き換えない
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO user_totp (user_id, secret_ciphertext)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE
+            SET secret_ciphertext = EXCLUDED.secret_ciphertext,
+                last_used_step = NULL,
+                created_at = CURRENT_TIMESTAMP
+            WHERE user_totp.enabled_at IS NULL
+            "#,
+            user_id,
+            secret_ciphertext
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while inserting user_totp")?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            r#"
+            DELETE FROM totp_recovery_codes
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting totp_recovery_codes")?;
+
+        let ids = recovery_code_hashes
+            .iter()
+            .map(|_| Uuid::now_v7())
+            .collect::<Vec<_>>();
+        sqlx::query!(
+            r#"
+            INSERT INTO totp_recovery_codes (id, user_id, code_hash)
+            SELECT id, $1, code_hash
+            FROM UNNEST($2::UUID[], $3::VARCHAR[]) AS t(id, code_hash)
+            "#,
+            user_id,
+            &ids,
+            &recovery_code_hashes
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while inserting totp_recovery_codes")?;
+
+        tx.commit()
+            .await
+            .context("unexpected error: while committing transaction")?;
+        Ok(true)
+    }
+
+    async fn get_totp(&self, user_id: Uuid) -> ConduitResult<Option<UserTotpEntity>> {
+        let totp = sqlx::query_as!(
+            UserTotpEntity,
+            r#"
+            SELECT *
+            FROM user_totp
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("unexpected error: while fetching user_totp")?;
+        Ok(totp)
+    }
+
+    async fn enable_totp(&self, user_id: Uuid, step: i64) -> ConduitResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE user_totp
+            SET enabled_at = CURRENT_TIMESTAMP, last_used_step = $2
+            WHERE user_id = $1
+            "#,
+            user_id,
+            step
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while enabling user_totp")?;
+        Ok(())
+    }
+
+    async fn use_totp_step(&self, user_id: Uuid, step: i64) -> ConduitResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE user_totp
+            SET last_used_step = $2
+            WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+            "#,
+            user_id,
+            step
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while updating user_totp")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_totp(&self, user_id: Uuid) -> ConduitResult<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("unexpected error: while beginning transaction")?;
+        sqlx::query!(
+            r#"
+            DELETE FROM totp_recovery_codes
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting totp_recovery_codes")?;
+        sqlx::query!(
+            r#"
+            DELETE FROM user_totp
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .context("unexpected error: while deleting user_totp")?;
+        tx.commit()
+            .await
+            .context("unexpected error: while committing transaction")?;
+        Ok(())
+    }
+
+    async fn consume_recovery_code(&self, user_id: Uuid, code_hash: &str) -> ConduitResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE totp_recovery_codes
+            SET used_at = CURRENT_TIMESTAMP
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            "#,
+            user_id,
+            code_hash
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while consuming totp_recovery_code")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn create_challenge(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        ttl: Duration,
+    ) -> ConduitResult<TwoFactorChallengeEntity> {
+        let challenge = sqlx::query_as!(
+            TwoFactorChallengeEntity,
+            r#"
+            INSERT INTO two_factor_challenges (token_hash, user_id, expires_at)
+            VALUES ($1, $2, CURRENT_TIMESTAMP + make_interval(secs => $3))
+            RETURNING *
+            "#,
+            token_hash,
+            user_id,
+            ttl.as_secs_f64()
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("unexpected error: while inserting two_factor_challenge")?;
+        Ok(challenge)
+    }
+
+    async fn get_challenge(
+        &self,
+        token_hash: &str,
+    ) -> ConduitResult<Option<TwoFactorChallengeEntity>> {
+        let challenge = sqlx::query_as!(
+            TwoFactorChallengeEntity,
+            r#"
+            SELECT *
+            FROM two_factor_challenges
+            WHERE token_hash = $1 AND expires_at > CURRENT_TIMESTAMP
+            "#,
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("unexpected error: while fetching two_factor_challenge")?;
+        Ok(challenge)
+    }
+
+    async fn record_challenge_failure(&self, token_hash: &str) -> ConduitResult<i32> {
+        let failed_count = sqlx::query_scalar!(
+            r#"
+            UPDATE two_factor_challenges
+            SET failed_count = failed_count + 1
+            WHERE token_hash = $1
+            RETURNING failed_count
+            "#,
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("unexpected error: while updating two_factor_challenge")?;
+        Ok(failed_count.unwrap_or(i32::MAX))
+    }
+
+    async fn delete_challenge(&self, token_hash: &str) -> ConduitResult<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM two_factor_challenges
+            WHERE token_hash = $1
+            "#,
+            token_hash
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while deleting two_factor_challenge")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::users::{dao_trait::UsersDaoTrait, dto::PasswdHashedNewUser, entity::UserEntity},
+        dao::users::UserDao,
+    };
+    use sqlx::PgPool;
+
+    async fn setup_user(pool: &PgPool) -> UserEntity {
+        let user_dao = UserDao::new(pool.clone());
+        user_dao
+            .create_user(PasswdHashedNewUser::new(
+                "two_factor_user".to_string(),
+                "two_factor@example.com".to_string(),
+                "password".to_string(),
+            ))
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_enroll_and_disable_totp(pool: PgPool) {
+        let user = setup_user(&pool).await;
+        let dao = TwoFactorDao::new(pool);
+        let codes = vec!["hash_a".to_string(), "hash_b".to_string()];
+
+        let created = dao
+            .create_pending_totp(user.id, vec![1, 2, 3], codes.clone())
+            .await
+            .unwrap();
+        assert!(created);
+        let totp = dao.get_totp(user.id).await.unwrap().unwrap();
+        assert!(!totp.is_enabled());
+
+        // 確認待ちの間は作り直せる
+        let created = dao
+            .create_pending_totp(user.id, vec![4, 5, 6], codes.clone())
+            .await
+            .unwrap();
+        assert!(created);
+
+        dao.enable_totp(user.id, 100).await.unwrap();
+        let totp = dao.get_totp(user.id).await.unwrap().unwrap();
+        assert!(totp.is_enabled());
+        assert_eq!(totp.secret_ciphertext, vec![4, 5, 6]);
+
+        // 有効になった後は置き換えられない
+        let created = dao
+            .create_pending_totp(user.id, vec![7, 8, 9], codes)
+            .await
+            .unwrap();
+        assert!(!created);
+
+        // 同じ時間ステップや古い時間ステップは使えない
+        assert!(!dao.use_totp_step(user.id, 100).await.unwrap());
+        assert!(dao.use_totp_step(user.id, 101).await.unwrap());
+
+        // リカバリーコードは1度しか使えない
+        assert!(dao.consume_recovery_code(user.id, "hash_a").await.unwrap());
+        assert!(!dao.consume_recovery_code(user.id, "hash_a").await.unwrap());
+        assert!(!dao.consume_recovery_code(user.id, "unknown").await.unwrap());
+
+        dao.delete_totp(user.id).await.unwrap();
+        assert_eq!(dao.get_totp(user.id).await.unwrap(), None);
+        assert!(!dao.consume_recovery_code(user.id, "hash_b").await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_challenge(pool: PgPool) {
+        let user = setup_user(&pool).await;
+        let dao = TwoFactorDao::new(pool);
+
+        dao.create_challenge(user.id, "challenge", Duration::from_secs(300))
+            .await
+            .unwrap();
+        let challenge = dao.get_challenge("challenge").await.unwrap().unwrap();
+        assert_eq!(challenge.user_id, user.id);
+        assert_eq!(challenge.failed_count, 0);
+
+        assert_eq!(dao.record_challenge_failure("challenge").await.unwrap(), 1);
+        assert_eq!(dao.record_challenge_failure("challenge").await.unwrap(), 2);
+
+        dao.delete_challenge("challenge").await.unwrap();
+        assert_eq!(dao.get_challenge("challenge").await.unwrap(), None);
+
+        // 期限切れのチャレンジは取得できない
+        dao.create_challenge(user.id, "expired", Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(dao.get_challenge("expired").await.unwrap(), None);
+    }
+}
@@ -1,10 +1,15 @@
 use crate::{
-    core::users::{dao_trait::UsersDaoTrait, dto::PasswdHashedNewUser, entity::UserEntity},
-    error::ConduitResult,
+    core::users::{
+        dao_trait::UsersDaoTrait,
+        dto::PasswdHashedNewUser,
+        entity::{Role, UserEntity},
+    },
+    error::{ConduitError, ConduitResult},
 };
 use anyhow::Context as _;
 use axum::async_trait;
 use uuid::Uuid;
+use validator::{ValidationError, ValidationErrors};
 
 #[derive(Clone)]
 pub struct UserDao {
@@ -17,6 +22,35 @@ impl UserDao {
     }
 }
 
+// ユーザー名やメールアドレスの一意制約に違反した場合は，他の入力チェックと同じく
+// userの下のフィールドのエラーとして，どのフィールドが重複したかを返す
+fn map_unique_violation(e: sqlx::Error, context: &'static str) -> ConduitError {
+    if let sqlx::Error::Database(db_error) = &e {
+        let taken = match db_error.constraint() {
+            Some("users_username_lower_key") => Some((
+                "username",
+                "username_taken",
+                "username has already been taken",
+            )),
+            Some("users_email_key") => {
+                Some(("email", "email_taken", "email has already been taken"))
+            }
+            _ => None,
+        };
+        if let Some((field, code, message)) = taken {
+            let mut errors = ValidationErrors::new();
+            errors.add(
+                field,
+                ValidationError::new(code).with_message(message.into()),
+            );
+            if let Err(errors) = ValidationErrors::merge(Ok(()), "user", Err(errors)) {
+                return errors.into();
+            }
+        }
+    }
+    anyhow::Error::new(e).context(context).into()
+}
+
 #[async_trait]
 impl UsersDaoTrait for UserDao {
     /// パスワードをハッシュ化しないまま値を渡さないでください
@@ -32,7 +66,9 @@ impl UsersDaoTrait for UserDao {
             r#"
             INSERT INTO users (id, username, email, password)
             VALUES ($1, $2, $3, $4)
-            RETURNING username, email, bio, image, id, created_at, updated_at, password
+            RETURNING username, email, bio, image, id, created_at, updated_at, password,
+                email_verified_at, role, is_private, display_name, location, pronouns, links,
+                deleted_at
             "#,
             uuid,
             user_hashed_password.username,
@@ -41,7 +77,7 @@ impl UsersDaoTrait for UserDao {
         )
         .fetch_one(&self.pool)
         .await
-        .context("unexpected error: while inserting user")?;
+        .map_err(|e| map_unique_violation(e, "unexpected error: while inserting user"))?;
         Ok(user)
     }
 
@@ -61,13 +97,30 @@ impl UsersDaoTrait for UserDao {
         Ok(user)
     }
 
+    async fn is_active_user(&self, user_id: Uuid) -> ConduitResult<bool> {
+        let active = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM users
+                WHERE id = $1 AND deleted_at IS NULL
+            ) AS "active!"
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("unexpected error: while checking user")?;
+        Ok(active)
+    }
+
     async fn get_user_by_email(&self, email: &str) -> ConduitResult<Option<UserEntity>> {
         let user = sqlx::query_as!(
             UserEntity,
             r#"
             SELECT *
             FROM users
-            WHERE email = $1
+            WHERE email = $1 AND deleted_at IS NULL
             "#,
             email
         )
@@ -83,7 +136,7 @@ impl UsersDaoTrait for UserDao {
             r#"
             SELECT *
             FROM users
-            WHERE username = $1
+            WHERE lower(username) = lower($1)
             "#,
             username
         )
@@ -98,7 +151,9 @@ impl UsersDaoTrait for UserDao {
             UserEntity,
             r#"
             UPDATE users
-            SET username = $1, email = $2, bio = $3, image = $4, password = $5
+            SET username = $1, email = $2, bio = $3, image = $4, password = $5,
+                email_verified_at = CASE WHEN email = $2::VARCHAR THEN email_verified_at ELSE NULL END,
+                is_private = $7, display_name = $8, location = $9, pronouns = $10, links = $11
             WHERE id = $6
             RETURNING *
             "#,
@@ -107,11 +162,72 @@ impl UsersDaoTrait for UserDao {
             user.bio,
             user.image,
             user.password,
-            user.id
+            user.id,
+            user.is_private,
+            user.display_name,
+            user.location,
+            user.pronouns,
+            &user.links
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| map_unique_violation(e, "failed: user update"))?;
+        Ok(user)
+    }
+
+    async fn mark_email_verified(
+        &self,
+        user_id: Uuid,
+        email: &str,
+    ) -> ConduitResult<Option<UserEntity>> {
+        let user = sqlx::query_as!(
+            UserEntity,
+            r#"
+            UPDATE users
+            SET email_verified_at = COALESCE(email_verified_at, CURRENT_TIMESTAMP)
+            WHERE id = $1 AND email = $2
+            RETURNING *
+            "#,
+            user_id,
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("unexpected error: while verifying email")?;
+        Ok(user)
+    }
+
+    async fn update_password(&self, user_id: Uuid, password: &str) -> ConduitResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET password = $1
+            WHERE id = $2
+            "#,
+            password,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .context("failed: password update")?;
+        Ok(())
+    }
+
+    async fn update_role(&self, user_id: Uuid, role: Role) -> ConduitResult<UserEntity> {
+        let user = sqlx::query_as!(
+            UserEntity,
+            r#"
+            UPDATE users
+            SET role = $1
+            WHERE id = $2
+            RETURNING *
+            "#,
+            role.as_str(),
+            user_id
         )
         .fetch_one(&self.pool)
         .await
-        .context("failed: user update")?;
+        .context("failed: role update")?;
         Ok(user)
     }
 }
@@ -121,6 +237,18 @@ mod tests {
     use super::*;
     use crate::core::users::entity::UserEntity;
     use sqlx::PgPool;
+    use validator::ValidationErrorsKind;
+
+    // 重複したフィールドの名前を返す
+    fn taken_fields(result: ConduitResult<UserEntity>) -> Vec<&'static str> {
+        let Err(ConduitError::ValidationErrpr(errors)) = result else {
+            panic!("expected validation errors: {:?}", result);
+        };
+        let Some(ValidationErrorsKind::Struct(user)) = errors.errors().get("user") else {
+            panic!("expected errors on user: {:?}", errors);
+        };
+        user.field_errors().keys().copied().collect()
+    }
 
     #[sqlx::test()]
     async fn test_create_user(pool: PgPool) {
@@ -140,6 +268,14 @@ mod tests {
             password: new_user.password,
             bio: "".to_string(),
             image: None,
+            email_verified_at: None,
+            role: "user".to_string(),
+            is_private: false,
+            display_name: None,
+            location: None,
+            pronouns: None,
+            links: vec![],
+            deleted_at: None,
         };
         assert_eq!(user, test_user);
     }
@@ -170,4 +306,147 @@ mod tests {
         let get_user = dao.get_user_by_email(&new_user.email).await.unwrap();
         assert_eq!(user, get_user.unwrap());
     }
+
+    #[sqlx::test()]
+    async fn test_mark_email_verified(pool: PgPool) {
+        let new_user = PasswdHashedNewUser {
+            email: "verify_test@gmail.com".to_string(),
+            password: "password".to_string(),
+            username: "verify_test".to_string(),
+        };
+        let dao = UserDao::new(pool);
+        let user = dao.create_user(new_user.clone()).await.unwrap();
+        assert!(!user.is_email_verified());
+
+        let user = dao
+            .mark_email_verified(user.id, &new_user.email)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(user.is_email_verified());
+
+        // メールアドレスを変更すると未確認に戻る
+        let user = dao
+            .update_user(UserEntity {
+                email: "verify_test_new@gmail.com".to_string(),
+                ..user
+            })
+            .await
+            .unwrap();
+        assert!(!user.is_email_verified());
+
+        // 古いメールアドレス宛のトークンでは確認済みにならない
+        let verified = dao
+            .mark_email_verified(user.id, &new_user.email)
+            .await
+            .unwrap();
+        assert_eq!(verified, None);
+    }
+
+    #[sqlx::test()]
+    async fn test_update_password(pool: PgPool) {
+        let new_user = PasswdHashedNewUser {
+            email: "password_update_test@gmail.com".to_string(),
+            password: "password".to_string(),
+            username: "password_update_test".to_string(),
+        };
+        let dao = UserDao::new(pool);
+        let user = dao.create_user(new_user).await.unwrap();
+
+        dao.update_password(user.id, "rehashed").await.unwrap();
+        let updated = dao.get_user_by_id(user.id).await.unwrap();
+        assert_eq!(updated.password, "rehashed");
+        assert_eq!(updated.email, user.email);
+    }
+
+    #[sqlx::test()]
+    async fn test_username_is_case_insensitive(pool: PgPool) {
+        let dao = UserDao::new(pool);
+        let user = dao
+            .create_user(PasswdHashedNewUser {
+                email: "case_test@gmail.com".to_string(),
+                password: "password".to_string(),
+                username: "CaseTest".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let found = dao.get_user_by_username("casetest").await.unwrap();
+        assert_eq!(found.map(|u| u.id), Some(user.id));
+
+        // 大文字小文字だけが異なるユーザー名は登録できない
+        let duplicated = dao
+            .create_user(PasswdHashedNewUser {
+                email: "case_test2@gmail.com".to_string(),
+                password: "password".to_string(),
+                username: "casetest".to_string(),
+            })
+            .await;
+        assert_eq!(taken_fields(duplicated), vec!["username"]);
+
+        let duplicated = dao
+            .create_user(PasswdHashedNewUser {
+                email: "case_test@gmail.com".to_string(),
+                password: "password".to_string(),
+                username: "other".to_string(),
+            })
+            .await;
+        assert_eq!(taken_fields(duplicated), vec!["email"]);
+
+        // 自分のユーザー名の大文字小文字を変えるのは問題ない
+        let updated = dao
+            .update_user(UserEntity {
+                username: "casetest".to_string(),
+                ..user
+            })
+            .await
+            .unwrap();
+        assert_eq!(updated.username, "casetest");
+    }
+
+    #[sqlx::test()]
+    async fn test_update_role(pool: PgPool) {
+        let dao = UserDao::new(pool);
+        let user = dao
+            .create_user(PasswdHashedNewUser {
+                email: "role_test@gmail.com".to_string(),
+                password: "password".to_string(),
+                username: "role_test".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(user.role(), Role::User);
+
+        let updated = dao.update_role(user.id, Role::Moderator).await.unwrap();
+        assert_eq!(updated.role(), Role::Moderator);
+        let fetched = dao.get_user_by_id(user.id).await.unwrap();
+        assert_eq!(fetched.role(), Role::Moderator);
+    }
+
+    #[sqlx::test()]
+    async fn test_update_profile_extension(pool: PgPool) {
+        let new_user = PasswdHashedNewUser {
+            email: "extension_test@gmail.com".to_string(),
+            password: "password".to_string(),
+            username: "extension_test".to_string(),
+        };
+        let dao = UserDao::new(pool);
+        let user = dao.create_user(new_user).await.unwrap();
+
+        let user = dao
+            .update_user(UserEntity {
+                display_name: Some("Extension Test".to_string()),
+                location: Some("Tokyo".to_string()),
+                pronouns: Some("they/them".to_string()),
+                links: vec!["https://example.com".to_string()],
+                ..user
+            })
+            .await
+            .unwrap();
+        let get_user = dao.get_user_by_id(user.id).await.unwrap();
+        assert_eq!(get_user.display_name.as_deref(), Some("Extension Test"));
+        assert_eq!(get_user.location.as_deref(), Some("Tokyo"));
+        assert_eq!(get_user.pronouns.as_deref(), Some("they/them"));
+        assert_eq!(get_user.links, vec!["https://example.com".to_string()]);
+    }
 }
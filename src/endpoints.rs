@@ -1,4 +1,14 @@
+pub mod access_tokens;
+pub mod account_data;
+pub mod admin;
 pub mod articles;
+pub mod email_verifications;
 pub mod favorites;
+pub mod follow_requests;
+pub mod password_resets;
 pub mod profiles;
+pub mod reactions;
+pub mod two_factor;
+pub mod uploads;
 pub mod users;
+pub mod well_known;
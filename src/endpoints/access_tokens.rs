@@ -0,0 +1,109 @@
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    routing::{delete, get},
+    Extension, Json, Router,
+};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    core::access_tokens::{
+        dao_trait::DynAccessTokensDao,
+        dto::{AccessTokensRes, CreateAccessTokenReq, CreateAccessTokenRes, CreatedAccessToken},
+        entity::ACCESS_TOKEN_PREFIX,
+    },
+    error::{ConduitError, ConduitResult},
+    extractor::{RequiredAuth, ValidationExtractor},
+    services::opaque_token::OpaqueTokenService,
+};
+
+const SECS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// 個人用アクセストークンの管理
+/// アクセストークンでトークンを発行できないよう，これらのルートにはスコープを付けない
+pub struct AccessTokenRouter {
+    dyn_access_tokens_dao: DynAccessTokensDao,
+}
+
+impl AccessTokenRouter {
+    pub fn new(dyn_access_tokens_dao: DynAccessTokensDao) -> Self {
+        Self {
+            dyn_access_tokens_dao,
+        }
+    }
+
+    pub fn to_router(&self) -> Router {
+        Router::new()
+            .route(
+                "/user/tokens",
+                get(Self::get_access_tokens).post(Self::create_access_token),
+            )
+            .route("/user/tokens/:id", delete(Self::delete_access_token))
+            .layer(Extension(self.dyn_access_tokens_dao.clone()))
+    }
+
+    #[tracing::instrument(skip(access_tokens_dao))]
+    pub async fn get_access_tokens(
+        RequiredAuth(user_id): RequiredAuth,
+        Extension(access_tokens_dao): Extension<DynAccessTokensDao>,
+    ) -> ConduitResult<(StatusCode, Json<AccessTokensRes>)> {
+        let tokens = access_tokens_dao.get_access_tokens(user_id).await?;
+        let res = AccessTokensRes {
+            access_tokens: tokens.into_iter().map(Into::into).collect(),
+        };
+        Ok((StatusCode::OK, Json(res)))
+    }
+
+    /// トークンそのものはこのレスポンスでしか返さない
+    #[tracing::instrument(skip(access_tokens_dao))]
+    pub async fn create_access_token(
+        RequiredAuth(user_id): RequiredAuth,
+        Extension(access_tokens_dao): Extension<DynAccessTokensDao>,
+        ValidationExtractor(req): ValidationExtractor<CreateAccessTokenReq>,
+    ) -> ConduitResult<(StatusCode, Json<CreateAccessTokenRes>)> {
+        let req = req.access_token;
+        let token = format!("{}{}", ACCESS_TOKEN_PREFIX, OpaqueTokenService::generate());
+        let ttl = req
+            .expires_in_days
+            .map(|days| std::time::Duration::from_secs(u64::from(days) * SECS_PER_DAY));
+
+        let mut scopes = req.scopes.unwrap();
+        scopes.sort();
+        scopes.dedup();
+        let access_token = access_tokens_dao
+            .create_access_token(
+                user_id,
+                &req.name.unwrap(),
+                &OpaqueTokenService::hash(&token),
+                scopes,
+                ttl,
+            )
+            .await?;
+
+        info!("access token created, id: {}", access_token.id);
+        let res = CreateAccessTokenRes {
+            access_token: CreatedAccessToken {
+                access_token: access_token.into(),
+                token,
+            },
+        };
+        Ok((StatusCode::CREATED, Json(res)))
+    }
+
+    #[tracing::instrument(skip(access_tokens_dao))]
+    pub async fn delete_access_token(
+        RequiredAuth(user_id): RequiredAuth,
+        Path(token_id): Path<Uuid>,
+        Extension(access_tokens_dao): Extension<DynAccessTokensDao>,
+    ) -> ConduitResult<StatusCode> {
+        let deleted = access_tokens_dao
+            .delete_access_token(user_id, token_id)
+            .await?;
+        if !deleted {
+            return Err(ConduitError::NotFound("access token not found".to_string()));
+        }
+        info!("access token deleted, id: {}", token_id);
+        Ok(StatusCode::OK)
+    }
+}
@@ -0,0 +1,152 @@
+use axum::{
+    http::{header::CONTENT_DISPOSITION, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Extension, Json, Router,
+};
+use tracing::info;
+
+use crate::{
+    core::{
+        account_data::{
+            dao_trait::DynAccountDataDao,
+            dto::{
+                AccountDeletionRes, AccountExport, AccountExportRes, DeleteAccountReq,
+                ExportedProfile,
+            },
+        },
+        profiles::dto::ProfileExtension,
+        users::dao_trait::DynUsersDao,
+    },
+    error::{ConduitError, ConduitResult},
+    extractor::{RequiredAuth, ValidationExtractor},
+    services::hash::PasswordHashService,
+    ArcState,
+};
+
+/// データのエクスポートと退会
+/// 個人情報をまとめて扱うため，アクセストークンでは呼び出せないようスコープは付けない
+pub struct AccountDataRouter {
+    dyn_users_dao: DynUsersDao,
+    dyn_account_data_dao: DynAccountDataDao,
+}
+
+impl AccountDataRouter {
+    pub fn new(dyn_users_dao: DynUsersDao, dyn_account_data_dao: DynAccountDataDao) -> Self {
+        Self {
+            dyn_users_dao,
+            dyn_account_data_dao,
+        }
+    }
+
+    pub fn to_router(&self) -> Router {
+        Router::new()
+            .route("/user", delete(Self::delete_account))
+            .route("/user/export", get(Self::export_account))
+            .route("/user/deletion", get(Self::get_account_deletion))
+            .route("/user/deletion/cancel", post(Self::cancel_account_deletion))
+            .layer(Extension(self.dyn_users_dao.clone()))
+            .layer(Extension(self.dyn_account_data_dao.clone()))
+    }
+
+    /// プロフィール，記事，いいね，フォローをまとめたJSONを添付ファイルとして返す
+    #[tracing::instrument(skip(users, account_data))]
+    pub async fn export_account(
+        RequiredAuth(user_id): RequiredAuth,
+        Extension(users): Extension<DynUsersDao>,
+        Extension(account_data): Extension<DynAccountDataDao>,
+    ) -> ConduitResult<Response> {
+        info!("exporting account data");
+        let user = users.get_user_by_id(user_id).await?;
+        let articles = account_data.get_authored_articles(user_id).await?;
+        let favorites = account_data.get_favorited_articles(user_id).await?;
+        let following = account_data.get_following(user_id).await?;
+        let followers = account_data.get_followers(user_id).await?;
+
+        let export = AccountExport {
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            profile: ExportedProfile {
+                email_verified: user.is_email_verified(),
+                extension: ProfileExtension::from(&user),
+                username: user.username,
+                email: user.email,
+                bio: user.bio,
+                image: user.image,
+                created_at: user.created_at.to_string(),
+                updated_at: user.updated_at.to_string(),
+            },
+            articles: articles.into_iter().map(Into::into).collect(),
+            favorites: favorites.into_iter().map(Into::into).collect(),
+            following: following.into_iter().map(Into::into).collect(),
+            followers: followers.into_iter().map(Into::into).collect(),
+        };
+        Ok((
+            StatusCode::OK,
+            [(
+                CONTENT_DISPOSITION,
+                "attachment; filename=\"conduit-export.json\"",
+            )],
+            Json(AccountExportRes { export }),
+        )
+            .into_response())
+    }
+
+    /// 退会を予約する 実際の削除は猶予期間が過ぎてからジョブで行う
+    #[tracing::instrument(skip_all)]
+    pub async fn delete_account(
+        RequiredAuth(user_id): RequiredAuth,
+        Extension(state): Extension<ArcState>,
+        Extension(users): Extension<DynUsersDao>,
+        Extension(account_data): Extension<DynAccountDataDao>,
+        ValidationExtractor(req): ValidationExtractor<DeleteAccountReq>,
+    ) -> ConduitResult<(StatusCode, Json<AccountDeletionRes>)> {
+        let user = users.get_user_by_id(user_id).await?;
+        let hasher = PasswordHashService::new(state.password_hash_params.clone());
+        // パスワードが違う場合はInvalidLoginになる
+        hasher.verify_password(&user.password, req.user.password.unwrap().expose())?;
+
+        let policy = &state.account_deletion;
+        let deletion = account_data
+            .schedule_deletion(user_id, policy.mode, policy.grace_period)
+            .await?;
+        info!(
+            "account deletion scheduled: {} at {}",
+            user_id, deletion.scheduled_at
+        );
+
+        let res = AccountDeletionRes {
+            account_deletion: deletion.into(),
+        };
+        Ok((StatusCode::ACCEPTED, Json(res)))
+    }
+
+    #[tracing::instrument(skip(account_data))]
+    pub async fn get_account_deletion(
+        RequiredAuth(user_id): RequiredAuth,
+        Extension(account_data): Extension<DynAccountDataDao>,
+    ) -> ConduitResult<(StatusCode, Json<AccountDeletionRes>)> {
+        let Some(deletion) = account_data.get_deletion(user_id).await? else {
+            return Err(ConduitError::NotFound(
+                "account deletion is not scheduled".to_string(),
+            ));
+        };
+        let res = AccountDeletionRes {
+            account_deletion: deletion.into(),
+        };
+        Ok((StatusCode::OK, Json(res)))
+    }
+
+    #[tracing::instrument(skip(account_data))]
+    pub async fn cancel_account_deletion(
+        RequiredAuth(user_id): RequiredAuth,
+        Extension(account_data): Extension<DynAccountDataDao>,
+    ) -> ConduitResult<StatusCode> {
+        if !account_data.cancel_deletion(user_id).await? {
+            return Err(ConduitError::NotFound(
+                "account deletion is not scheduled".to_string(),
+            ));
+        }
+        info!("account deletion cancelled: {}", user_id);
+        Ok(StatusCode::OK)
+    }
+}
@@ -0,0 +1,93 @@
+use axum::{extract::Path, http::StatusCode, routing::put, Extension, Json, Router};
+use tracing::info;
+
+use crate::{
+    core::users::{
+        dao_trait::DynUsersDao,
+        dto::{UpdateRoleReq, UserRole, UserRoleRes},
+    },
+    error::{ConduitError, ConduitResult},
+    extractor::{RequiredActor, ValidationExtractor},
+    services::{authorization::AuthorizationService, username::UsernameService},
+};
+
+/// 管理者向けのエンドポイント
+/// アクセストークンでは呼び出せないよう，スコープは付けない
+pub struct AdminRouter {
+    dyn_users_dao: DynUsersDao,
+}
+
+impl AdminRouter {
+    pub fn new(dyn_users_dao: DynUsersDao) -> Self {
+        Self { dyn_users_dao }
+    }
+
+    pub fn to_router(&self) -> Router {
+        Router::new()
+            .route("/admin/users/:username/role", put(Self::update_role))
+            .layer(Extension(self.dyn_users_dao.clone()))
+    }
+
+    #[tracing::instrument(skip(users, req))]
+    pub async fn update_role(
+        Path(username): Path<String>,
+        RequiredActor(actor): RequiredActor,
+        Extension(users): Extension<DynUsersDao>,
+        ValidationExtractor(req): ValidationExtractor<UpdateRoleReq>,
+    ) -> ConduitResult<(StatusCode, Json<UserRoleRes>)> {
+        // 権限がなければ，対象のユーザーがいるかどうかに関わらず同じエラーを返す
+        AuthorizationService::ensure_admin(&actor)?;
+        let username = UsernameService::normalize(&username);
+        let Some(target) = users.get_user_by_username(&username).await? else {
+            return Err(ConduitError::NotFound("user not found".to_string()));
+        };
+        AuthorizationService::ensure_can_change_role(&actor, target.id)?;
+
+        let role = req.user.role;
+        let updated = users.update_role(target.id, role).await?;
+        info!(
+            "role changed: {} {} -> {} by {}",
+            updated.username, target.role, updated.role, actor.user_id
+        );
+
+        let res = UserRoleRes {
+            user: UserRole {
+                username: updated.username.clone(),
+                role: updated.role(),
+            },
+        };
+        Ok((StatusCode::OK, Json(res)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        core::users::{dao_trait::MockUsersDaoTrait, dto::UpdateRole, entity::Role},
+        services::authorization::Actor,
+    };
+
+    // 管理者以外には，存在しないユーザーでも404ではなく403を返す
+    #[tokio::test]
+    async fn non_admin_is_forbidden_before_user_lookup() {
+        // 期待する呼び出しを設定しないので，ユーザーを探せばパニックになる
+        let users: DynUsersDao = Arc::new(MockUsersDaoTrait::new());
+        let req = UpdateRoleReq {
+            user: UpdateRole { role: Role::Admin },
+        };
+
+        let res = AdminRouter::update_role(
+            Path("nobody".to_string()),
+            RequiredActor(Actor::new(Uuid::now_v7(), Role::Moderator)),
+            Extension(users),
+            ValidationExtractor(req),
+        )
+        .await;
+        assert!(matches!(res, Err(ConduitError::Forbidden(_))));
+    }
+}
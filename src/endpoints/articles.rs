@@ -1,5 +1,6 @@
 use axum::{
     extract::Path,
+    handler::Handler,
     http::StatusCode,
     routing::{get, post},
     Extension, Json, Router,
@@ -9,6 +10,7 @@ use tracing::info;
 
 use crate::{
     core::{
+        access_tokens::entity::TokenScope,
         articles::{
             dao_trait::{CreatArticle, DynArticlesDao},
             dto::{
@@ -16,20 +18,24 @@ use crate::{
                 UpdateArticleRes,
             },
         },
-        favorites::dao_trait::DynFavoritesDao,
-        profiles::dto::Profile,
+        profiles::{dao_trait::DynProfilesDao, dto::Profile},
+        reactions::dao_trait::DynReactionsDao,
         tags::dao_trait::DynTagsDao,
         users::dao_trait::DynUsersDao,
     },
+    endpoints::reactions::ReactionSummarizer,
     error::{ConduitError, ConduitResult},
-    extractor::{RequiredAuth, ValidationExtractor},
+    extractor::{scope, OptionalAuth, RequiredActor, RequiredAuth, ValidationExtractor},
+    services::authorization::AuthorizationService,
+    ArcState,
 };
 
 pub struct ArticleRouter {
     article_dao: DynArticlesDao,
     user_dao: DynUsersDao,
     tag_dao: DynTagsDao,
-    favorite_dao: DynFavoritesDao,
+    profile_dao: DynProfilesDao,
+    reaction_dao: DynReactionsDao,
 }
 
 impl ArticleRouter {
@@ -37,41 +43,58 @@ impl ArticleRouter {
         article_dao: DynArticlesDao,
         user_dao: DynUsersDao,
         tag_dao: DynTagsDao,
-        favorite_dao: DynFavoritesDao,
+        profile_dao: DynProfilesDao,
+        reaction_dao: DynReactionsDao,
     ) -> Self {
         Self {
             article_dao,
             user_dao,
             tag_dao,
-            favorite_dao,
+            profile_dao,
+            reaction_dao,
         }
     }
 
     pub fn to_router(&self) -> Router {
         Router::new()
-            .route("/articles", post(Self::create_article))
+            .route(
+                "/articles",
+                post(Self::create_article.layer(scope(TokenScope::ArticlesWrite))),
+            )
             .route(
                 "/articles/:slug",
-                get(Self::get_article)
-                    .put(Self::update_article)
-                    .delete(Self::delete_article),
+                get(Self::get_article.layer(scope(TokenScope::Read)))
+                    .put(Self::update_article.layer(scope(TokenScope::ArticlesWrite)))
+                    .delete(Self::delete_article.layer(scope(TokenScope::ArticlesWrite))),
             )
             .layer(Extension(self.article_dao.clone()))
             .layer(Extension(self.user_dao.clone()))
             .layer(Extension(self.tag_dao.clone()))
-            .layer(Extension(self.favorite_dao.clone()))
+            .layer(Extension(self.profile_dao.clone()))
+            .layer(Extension(self.reaction_dao.clone()))
     }
 
     #[tracing::instrument(skip_all)]
     // #[debug_handler]
     pub async fn create_article(
         RequiredAuth(user_id): RequiredAuth,
+        Extension(state): Extension<ArcState>,
         Extension(user_dao): Extension<DynUsersDao>,
         Extension(article_dao): Extension<DynArticlesDao>,
         Extension(tag_dao): Extension<DynTagsDao>,
         ValidationExtractor(req): ValidationExtractor<CreateArticleReq>,
     ) -> ConduitResult<(StatusCode, Json<CreateArticleRes>)> {
         info!("create_article");
+        // 記事の作者(自分)を取得
+        let user_entity = user_dao.get_user_by_id(user_id).await?;
+        // 設定によっては，メールアドレスの確認が済むまで投稿させない
+        if state.require_verified_email && !user_entity.is_email_verified() {
+            info!("email is not verified");
+            return Err(ConduitError::Forbidden(
+                "email address is not verified".to_string(),
+            ));
+        }
+
         // バリデーション済みなのでそのことを示す
         let new_article = req.article.into_validated();
 
@@ -104,14 +127,7 @@ impl ArticleRouter {
 
         info!("new article created id: {}", article.id);
 
-        // 記事の作者(自分)を取得
-        let user_entity = user_dao.get_user_by_id(user_id).await?;
-        let author = Profile {
-            username: user_entity.username,
-            bio: user_entity.bio,
-            image: user_entity.image,
-            following: false,
-        };
+        let author = Profile::from_user_entity(user_entity, false);
 
         let article = Article {
             id: article.id,
@@ -125,18 +141,22 @@ impl ArticleRouter {
             favorited: false,
             favorites_count: 0,
             author,
+            // 作ったばかりの記事にはリアクションがない
+            reactions: Some(state.reactions.summarize(&[], &[])),
         };
 
         Ok((StatusCode::CREATED, Json(CreateArticleRes { article })))
     }
 
-    #[tracing::instrument(skip(user_dao, article_dao, tag_dao, favorite_dao))]
+    #[tracing::instrument(skip(user_dao, article_dao, tag_dao, profile_dao, reactions))]
     pub async fn get_article(
         Path(slug): Path<String>,
         Extension(user_dao): Extension<DynUsersDao>,
         Extension(article_dao): Extension<DynArticlesDao>,
         Extension(tag_dao): Extension<DynTagsDao>,
-        Extension(favorite_dao): Extension<DynFavoritesDao>,
+        Extension(profile_dao): Extension<DynProfilesDao>,
+        reactions: ReactionSummarizer,
+        OptionalAuth(current_user_id): OptionalAuth,
     ) -> ConduitResult<(StatusCode, Json<GetArticleRes>)> {
         info!("retrieving article");
         let article = article_dao.get_article_by_slug(&slug).await?;
@@ -146,26 +166,29 @@ impl ArticleRouter {
             return Err(ConduitError::NotFound("article not found".to_string()));
         };
         info!("article found");
+
+        // 非公開アカウントの記事は，承認されたフォロワーでなければ存在しないものとして扱う
+        let (user_entity, is_follower) = AuthorizationService::ensure_article_visible(
+            &user_dao,
+            &profile_dao,
+            current_user_id,
+            exists_article.author_id,
+        )
+        .await?;
+
         // タグを取得
         let tags = tag_dao.get_article_tags(exists_article.id).await?;
         let tag_list = tags.iter().map(|tag| tag.tag.clone()).collect::<Vec<_>>();
 
-        // いいね数を取得
-        let favorites = favorite_dao
-            .get_favorites_by_article_id(exists_article.id)
-            .await?;
-        let favorites_count = favorites.len() as i32;
+        // いいね数はarticlesに保存している
+        let favorites_count = exists_article.favorites_count;
         // いいねしているかどうかは未実装
         // OptionalAuthが必要なのでは? 仕様にないから実装してないが．
 
-        // 記事の作者を取得
-        let user_entity = user_dao.get_user_by_id(exists_article.author_id).await?;
-        let author = Profile {
-            username: user_entity.username,
-            bio: user_entity.bio,
-            image: user_entity.image,
-            following: false,
-        };
+        let author = Profile::from_user_entity(user_entity, is_follower);
+        let reactions = reactions
+            .summarize(exists_article.id, current_user_id)
+            .await?;
 
         let article = Article {
             id: exists_article.id,
@@ -179,36 +202,38 @@ impl ArticleRouter {
             favorited: false,
             favorites_count,
             author,
+            reactions: Some(reactions),
         };
 
         Ok((StatusCode::OK, Json(GetArticleRes { article })))
     }
 
-    #[tracing::instrument(skip(user_dao, article_dao, tag_dao, req, favorite_dao))]
+    #[tracing::instrument(skip(user_dao, article_dao, tag_dao, reactions, req))]
     async fn update_article(
         Path(slug): Path<String>,
-        RequiredAuth(user_id): RequiredAuth,
+        RequiredActor(actor): RequiredActor,
         Extension(user_dao): Extension<DynUsersDao>,
         Extension(article_dao): Extension<DynArticlesDao>,
         Extension(tag_dao): Extension<DynTagsDao>,
-        Extension(favorite_dao): Extension<DynFavoritesDao>,
+        reactions: ReactionSummarizer,
         ValidationExtractor(req): ValidationExtractor<UpdateArticleReq>,
     ) -> ConduitResult<(StatusCode, Json<UpdateArticleRes>)> {
         info!("retrieving article to update");
         let update_article = req.article;
         // 記事の作者であるかどうかの確認
-        // 作者でない場合はエラー
+        // 作者でもモデレーターでもない場合はエラー
         let article = article_dao.get_article_by_slug(&slug).await?;
         let Some(article) = article else {
             info!("article not found");
             return Err(ConduitError::NotFound("article not found".to_string()));
         };
 
-        if article.author_id != user_id {
+        if let Err(e) = AuthorizationService::ensure_can_modify_content(&actor, article.author_id) {
             info!("invalid user");
-            return Err(ConduitError::Forbidden(
-                "you are not the author".to_string(),
-            ));
+            return Err(e);
+        }
+        if article.author_id != actor.user_id {
+            info!("moderating article as {}", actor.role);
         }
         // 記事の更新
         // titleからslugを生成
@@ -230,20 +255,15 @@ impl ArticleRouter {
         let tag_list = tags.iter().map(|tag| tag.tag.clone()).collect::<Vec<_>>();
 
         // 記事のいいね数を取得
-        let favorites = favorite_dao
-            .get_favorites_by_article_id(updated_article.id)
-            .await?;
-        let favorites_count = favorites.len() as i32;
+        let favorites_count = updated_article.favorites_count;
 
         // 記事の作者を取得
         let user_entity = user_dao.get_user_by_id(updated_article.author_id).await?;
-        let author = Profile {
-            username: user_entity.username,
-            bio: user_entity.bio,
-            image: user_entity.image,
-            following: false,
-        };
-        // faviritedとfavorites_countは未実装
+        let author = Profile::from_user_entity(user_entity, false);
+        let reactions = reactions
+            .summarize(updated_article.id, Some(actor.user_id))
+            .await?;
+        // faviritedは未実装
         let article = Article {
             id: updated_article.id,
             slug: updated_article.slug,
@@ -256,6 +276,7 @@ impl ArticleRouter {
             favorited: false,
             favorites_count,
             author,
+            reactions: Some(reactions),
         };
 
         Ok((StatusCode::OK, Json(UpdateArticleRes { article })))
@@ -269,23 +290,24 @@ impl ArticleRouter {
     #[tracing::instrument(skip(article_dao))]
     pub async fn delete_article(
         Path(slug): Path<String>,
-        RequiredAuth(user_id): RequiredAuth,
+        RequiredActor(actor): RequiredActor,
         Extension(article_dao): Extension<DynArticlesDao>,
     ) -> ConduitResult<StatusCode> {
         info!("deleting article");
         // 記事の作者であるかどうかの確認
-        // 作者でない場合はエラー
+        // 作者でもモデレーターでもない場合はエラー
         let article = article_dao.get_article_by_slug(&slug).await?;
         let Some(article) = article else {
             info!("article not found");
             return Err(ConduitError::NotFound("article not found".to_string()));
         };
 
-        if article.author_id != user_id {
+        if let Err(e) = AuthorizationService::ensure_can_modify_content(&actor, article.author_id) {
             info!("invalid user");
-            return Err(ConduitError::Forbidden(
-                "you are not the author".to_string(),
-            ));
+            return Err(e);
+        }
+        if article.author_id != actor.user_id {
+            info!("moderating article as {}", actor.role);
         }
 
         // 記事の削除
@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use axum::{http::StatusCode, routing::post, Extension, Json, Router};
+use tracing::{error, info};
+
+use crate::{
+    core::{
+        email_verifications::{
+            dao_trait::DynEmailVerificationsDao,
+            dto::{VerifyEmailReq, VerifyEmailRes},
+        },
+        users::{dao_trait::DynUsersDao, entity::UserEntity},
+    },
+    error::{ConduitError, ConduitResult},
+    extractor::{RequiredAuth, ValidationExtractor},
+    services::{
+        mailer::{DynMailer, Mail},
+        opaque_token::OpaqueTokenService,
+    },
+    ArcState,
+};
+
+const EMAIL_VERIFICATION_TOKEN_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// 確認用のトークンを発行し，ユーザーの現在のメールアドレス宛に送る
+/// 登録時とメールアドレス変更時にも使う
+pub(crate) async fn send_verification_mail(
+    state: &ArcState,
+    verification_dao: &DynEmailVerificationsDao,
+    mailer: &DynMailer,
+    user_entity: &UserEntity,
+) -> ConduitResult<()> {
+    let token = OpaqueTokenService::generate();
+    verification_dao
+        .create_verification_token(
+            user_entity.id,
+            &user_entity.email,
+            &OpaqueTokenService::hash(&token),
+            EMAIL_VERIFICATION_TOKEN_TTL,
+        )
+        .await?;
+
+    let link = format!("{}/verify-email?token={}", state.frontend_url, token);
+    let mail = Mail::new(
+        user_entity.email.clone(),
+        "Verify your email address".to_string(),
+        format!(
+            "Open the link below to verify your email address. The link expires in 24 hours.\n\n{}",
+            link
+        ),
+    );
+    // メール送信の失敗で登録や更新自体が失敗しないよう，送信は待たない
+    // 届かなかった場合は再送してもらう
+    let mailer = mailer.clone();
+    tokio::spawn(async move {
+        if let Err(e) = mailer.send(mail).await {
+            error!("failed to send verification mail: {}", e);
+        }
+    });
+    Ok(())
+}
+
+pub struct EmailVerificationRouter {
+    dyn_users_dao: DynUsersDao,
+    dyn_email_verifications_dao: DynEmailVerificationsDao,
+    dyn_mailer: DynMailer,
+}
+
+impl EmailVerificationRouter {
+    pub fn new(
+        dyn_users_dao: DynUsersDao,
+        dyn_email_verifications_dao: DynEmailVerificationsDao,
+        dyn_mailer: DynMailer,
+    ) -> Self {
+        Self {
+            dyn_users_dao,
+            dyn_email_verifications_dao,
+            dyn_mailer,
+        }
+    }
+
+    pub fn to_router(&self) -> Router {
+        Router::new()
+            .route("/users/verify-email", post(Self::verify_email))
+            .route("/user/verify-email", post(Self::resend_verification_mail))
+            .layer(Extension(self.dyn_users_dao.clone()))
+            .layer(Extension(self.dyn_email_verifications_dao.clone()))
+            .layer(Extension(self.dyn_mailer.clone()))
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn verify_email(
+        Extension(user_dao): Extension<DynUsersDao>,
+        Extension(verification_dao): Extension<DynEmailVerificationsDao>,
+        ValidationExtractor(req): ValidationExtractor<VerifyEmailReq>,
+    ) -> ConduitResult<(StatusCode, Json<VerifyEmailRes>)> {
+        let token_hash = OpaqueTokenService::hash(&req.user.token.unwrap());
+        let Some(verification) = verification_dao
+            .consume_verification_token(&token_hash)
+            .await?
+        else {
+            info!("invalid or expired verification token");
+            return Err(ConduitError::BadRequest(
+                "invalid or expired token".to_string(),
+            ));
+        };
+
+        // トークン発行後にメールアドレスが変わっていたら確認済みにしない
+        let verified = user_dao
+            .mark_email_verified(verification.user_id, &verification.email)
+            .await?;
+        if verified.is_none() {
+            info!("email has changed since the token was issued");
+            return Err(ConduitError::BadRequest(
+                "invalid or expired token".to_string(),
+            ));
+        }
+
+        info!("email verified, user_id: {}", verification.user_id);
+        let res = VerifyEmailRes {
+            message: "email has been verified".to_string(),
+        };
+        Ok((StatusCode::OK, Json(res)))
+    }
+
+    // 確認メールの再送
+    #[tracing::instrument(skip(state, user_dao, verification_dao, mailer))]
+    pub async fn resend_verification_mail(
+        RequiredAuth(user_id): RequiredAuth,
+        Extension(state): Extension<ArcState>,
+        Extension(user_dao): Extension<DynUsersDao>,
+        Extension(verification_dao): Extension<DynEmailVerificationsDao>,
+        Extension(mailer): Extension<DynMailer>,
+    ) -> ConduitResult<(StatusCode, Json<VerifyEmailRes>)> {
+        let user_entity = user_dao.get_user_by_id(user_id).await?;
+        if user_entity.is_email_verified() {
+            return Err(ConduitError::Conflict(
+                "email is already verified".to_string(),
+            ));
+        }
+
+        info!("resending verification mail");
+        send_verification_mail(&state, &verification_dao, &mailer, &user_entity).await?;
+
+        let res = VerifyEmailRes {
+            message: "verification mail has been sent".to_string(),
+        };
+        Ok((StatusCode::ACCEPTED, Json(res)))
+    }
+}
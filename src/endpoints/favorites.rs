@@ -1,15 +1,40 @@
-use axum::{extract::Path, http::StatusCode, routing::post, Extension, Json, Router};
+use axum::{
+    extract::{Path, Query},
+    handler::Handler,
+    http::StatusCode,
+    routing::{get, post},
+    Extension, Json, Router,
+};
 use tracing::info;
 
 use crate::{
     core::{
-        articles::{dao_trait::DynArticlesDao, dto::Article},
-        favorites::{dao_trait::DynFavoritesDao, dto::AddFavoriteRes},
-        profiles::{dao_trait::DynProfilesDao, dto::Profile},
+        access_tokens::entity::TokenScope,
+        articles::{
+            dao_trait::DynArticlesDao,
+            dto::{Article, ArticlesQuery, ArticlesRes},
+            entity::ArticleEntity,
+        },
+        favorites::{
+            dao_trait::DynFavoritesDao,
+            dto::{
+                AddFavoriteRes, FavoriteDayStats, FavoriteStats, FavoriteStatsQuery,
+                FavoriteStatsRes,
+            },
+            entity::FavoritedArticleEntity,
+        },
+        profiles::{
+            dao_trait::DynProfilesDao,
+            dto::{Profile, ProfilesQuery, ProfilesRes},
+        },
+        reactions::dao_trait::DynReactionsDao,
         users::dao_trait::DynUsersDao,
     },
+    endpoints::{profiles::ProfileRouter, reactions::ReactionSummarizer},
     error::{ConduitError, ConduitResult},
-    extractor::RequiredAuth,
+    extractor::{scope, OptionalAuth, RequiredAuth},
+    services::{authorization::AuthorizationService, username::UsernameService},
+    ArcState,
 };
 
 pub struct FavoritesRouter {
@@ -17,6 +42,7 @@ pub struct FavoritesRouter {
     dyn_users_dao: DynUsersDao,
     dyn_articles_dao: DynArticlesDao,
     dyn_favorite_dao: DynFavoritesDao,
+    dyn_reactions_dao: DynReactionsDao,
 }
 
 impl FavoritesRouter {
@@ -25,12 +51,14 @@ impl FavoritesRouter {
         dyn_users_dao: DynUsersDao,
         dyn_articles_dao: DynArticlesDao,
         dyn_favorite_dao: DynFavoritesDao,
+        dyn_reactions_dao: DynReactionsDao,
     ) -> Self {
         Self {
             dyn_profiles_dao,
             dyn_users_dao,
             dyn_articles_dao,
             dyn_favorite_dao,
+            dyn_reactions_dao,
         }
     }
 
@@ -38,23 +66,50 @@ impl FavoritesRouter {
         Router::new()
             .route(
                 "/articles/:slug/favorite",
-                post(Self::add_favorite_article).delete(Self::delete_favorite_article),
+                post(Self::add_favorite_article.layer(scope(TokenScope::FavoritesWrite)))
+                    .delete(Self::delete_favorite_article.layer(scope(TokenScope::FavoritesWrite))),
+            )
+            .route(
+                "/articles/:slug/favorites",
+                get(Self::get_article_favorites.layer(scope(TokenScope::Read))),
+            )
+            .route(
+                "/articles/:slug/favorites/stats",
+                get(Self::get_favorite_stats.layer(scope(TokenScope::Read))),
+            )
+            .route(
+                "/profiles/:username/favorites",
+                get(Self::get_user_favorites.layer(scope(TokenScope::Read))),
             )
             .layer(Extension(self.dyn_profiles_dao.clone()))
             .layer(Extension(self.dyn_users_dao.clone()))
             .layer(Extension(self.dyn_articles_dao.clone()))
             .layer(Extension(self.dyn_favorite_dao.clone()))
+            // いいねの結果として返す記事にリアクションを含めるために使う
+            .layer(Extension(self.dyn_reactions_dao.clone()))
     }
 
     // いいねエンドポイント
-    #[tracing::instrument(skip(article_dao, favorite_dao, user_dao, profile_dao))]
+    // すでにいいねしている場合も，そのままいいねした状態を返す
+    // 記事の取得，可視性の確認，いいねの設定，リアクションの集計にそれぞれ依存がある
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(skip(
+        state,
+        article_dao,
+        favorite_dao,
+        user_dao,
+        profile_dao,
+        reactions
+    ))]
     pub async fn add_favorite_article(
         Path(slug): Path<String>,
         RequiredAuth(current_user_id): RequiredAuth,
+        Extension(state): Extension<ArcState>,
         Extension(profile_dao): Extension<DynProfilesDao>,
         Extension(user_dao): Extension<DynUsersDao>,
         Extension(article_dao): Extension<DynArticlesDao>,
         Extension(favorite_dao): Extension<DynFavoritesDao>,
+        reactions: ReactionSummarizer,
     ) -> ConduitResult<(StatusCode, Json<AddFavoriteRes>)> {
         info!("add favorite article");
         // いいねする記事を取得
@@ -64,44 +119,63 @@ impl FavoritesRouter {
             return Err(ConduitError::NotFound("article not found".to_string()));
         };
 
+        // 設定によっては，作者は自分の記事にいいねできない
+        if let Err(e) = state
+            .favorites
+            .ensure_can_favorite(current_user_id, article.author_id)
+        {
+            info!("self favorite is not allowed");
+            return Err(e);
+        }
+
+        // 作者にブロックされている場合はいいねできない
+        if profile_dao
+            .is_blocking(article.author_id, current_user_id)
+            .await?
+        {
+            info!("blocked by author");
+            return Err(ConduitError::Forbidden(
+                "you cannot favorite this article".to_string(),
+            ));
+        }
+
+        // 記事を書いたユーザーと，フォローしているかどうかを取得
+        // 非公開アカウントの記事は，承認されたフォロワーでなければ存在しないものとして扱う
+        let (author, following) = AuthorizationService::ensure_article_visible(
+            &user_dao,
+            &profile_dao,
+            Some(current_user_id),
+            article.author_id,
+        )
+        .await?;
+
         // いいねを追加
-        favorite_dao
+        let favorite = favorite_dao
             .add_favorite(current_user_id, article.id)
             .await?;
 
         // 返すデータを作成
-        // 記事のいいね数を取得
-        let favorites = favorite_dao.get_favorites_by_article_id(article.id).await?;
-        // current_userが記事をいいねしているかどうかを探す
-        let is_favorite_current_user = favorites
-            .iter()
-            .any(|favorite| favorite.user_id == current_user_id);
+        // 記事のいいね数はいいねの追加に合わせて更新されている
+        let favorites_count = favorite_dao.count_favorites(article.id).await?;
 
         info!("favorite added");
-        // 記事を書いたユーザーを取得
-        let author = user_dao.get_user_by_id(article.author_id).await?;
-        // フォローしているかどうかを取得
-        let following = profile_dao.is_follow(current_user_id, author.id).await?;
-        let following = if let Some(_u) = following {
-            true
-        } else {
-            false
-        };
         // Profileを作成
         let profile = Profile::from_user_entity(author, following);
+        let reactions = reactions
+            .summarize(article.id, Some(current_user_id))
+            .await?;
         // Articleを作成
-        let article = Article::from_entity(
-            article,
-            profile,
-            is_favorite_current_user,
-            favorites.len() as i32,
-        );
+        let article = Article {
+            reactions: Some(reactions),
+            ..Article::from_entity(article, profile, !favorite.is_deleted, favorites_count)
+        };
 
         Ok((StatusCode::OK, Json(AddFavoriteRes { article })))
     }
 
     // いいね解除エンドポイント
-    #[tracing::instrument(skip(article_dao, favorite_dao, user_dao, profile_dao))]
+    // いいねしていなかった場合も，いいねしていない状態を返す
+    #[tracing::instrument(skip(article_dao, favorite_dao, user_dao, profile_dao, reactions))]
     pub async fn delete_favorite_article(
         Path(slug): Path<String>,
         RequiredAuth(current_user_id): RequiredAuth,
@@ -109,6 +183,7 @@ impl FavoritesRouter {
         Extension(user_dao): Extension<DynUsersDao>,
         Extension(article_dao): Extension<DynArticlesDao>,
         Extension(favorite_dao): Extension<DynFavoritesDao>,
+        reactions: ReactionSummarizer,
     ) -> ConduitResult<(StatusCode, Json<AddFavoriteRes>)> {
         info!("delete favorite article");
         // いいね削除する記事を取得
@@ -118,39 +193,171 @@ impl FavoritesRouter {
             return Err(ConduitError::NotFound("article not found".to_string()));
         };
 
+        // 記事を書いたユーザーと，フォローしているかどうかを取得
+        // 非公開アカウントの記事は，承認されたフォロワーでなければ存在しないものとして扱う
+        let (author, following) = AuthorizationService::ensure_article_visible(
+            &user_dao,
+            &profile_dao,
+            Some(current_user_id),
+            article.author_id,
+        )
+        .await?;
+
         // いいねを削除
-        favorite_dao
+        let favorite = favorite_dao
             .remove_favorite(current_user_id, article.id)
             .await?;
+        let favorited = favorite.is_some_and(|favorite| !favorite.is_deleted);
 
         // 返すデータを作成
-        // 記事のいいね数を取得
-        let favorites = favorite_dao.get_favorites_by_article_id(article.id).await?;
-        // current_userが記事をいいねしているかどうかを探す
-        let is_favorite_current_user = favorites
-            .iter()
-            .any(|favorite| favorite.user_id == current_user_id);
+        // 記事のいいね数はいいねの削除に合わせて更新されている
+        let favorites_count = favorite_dao.count_favorites(article.id).await?;
 
         info!("favorite deleted");
-        // 記事を書いたユーザーを取得
-        let author = user_dao.get_user_by_id(article.author_id).await?;
-        // フォローしているかどうかを取得
-        let following = profile_dao.is_follow(current_user_id, author.id).await?;
-        let following = if let Some(_u) = following {
-            true
-        } else {
-            false
-        };
         // Profileを作成
         let profile = Profile::from_user_entity(author, following);
+        let reactions = reactions
+            .summarize(article.id, Some(current_user_id))
+            .await?;
         // Articleを作成
-        let article = Article::from_entity(
-            article,
-            profile,
-            is_favorite_current_user,
-            favorites.len() as i32,
-        );
+        let article = Article {
+            reactions: Some(reactions),
+            ..Article::from_entity(article, profile, favorited, favorites_count)
+        };
 
         Ok((StatusCode::OK, Json(AddFavoriteRes { article })))
     }
+
+    // 記事をいいねしたユーザーの一覧
+    #[tracing::instrument(skip(article_dao, favorite_dao, user_dao, profile_dao))]
+    pub async fn get_article_favorites(
+        Path(slug): Path<String>,
+        Query(query): Query<ProfilesQuery>,
+        OptionalAuth(current_user_id): OptionalAuth,
+        Extension(profile_dao): Extension<DynProfilesDao>,
+        Extension(user_dao): Extension<DynUsersDao>,
+        Extension(article_dao): Extension<DynArticlesDao>,
+        Extension(favorite_dao): Extension<DynFavoritesDao>,
+    ) -> ConduitResult<(StatusCode, Json<ProfilesRes>)> {
+        info!("received req: get article favorites: {}", slug);
+        let Some(article) = article_dao.get_article_by_slug(&slug).await? else {
+            info!("article not found");
+            return Err(ConduitError::NotFound("article not found".to_string()));
+        };
+
+        // 記事を見られないユーザーには，いいねしたユーザーも見せない
+        AuthorizationService::ensure_article_visible(
+            &user_dao,
+            &profile_dao,
+            current_user_id,
+            article.author_id,
+        )
+        .await?;
+
+        let users = favorite_dao
+            .get_favoriting_users(article.id, query.limit(), query.offset())
+            .await?;
+        let count = favorite_dao.count_favorites(article.id).await?;
+        let res = ProfilesRes {
+            profiles: ProfileRouter::to_profiles(&profile_dao, users, current_user_id).await?,
+            profiles_count: count as i64,
+        };
+        Ok((StatusCode::OK, Json(res)))
+    }
+
+    // ユーザーがいいねした記事の一覧
+    // 呼び出したユーザーが見られない記事は含めない
+    // 記事の一覧と同じく，リアクションは集計せずに省く
+    #[tracing::instrument(skip(favorite_dao, user_dao))]
+    pub async fn get_user_favorites(
+        Path(username): Path<String>,
+        Query(query): Query<ArticlesQuery>,
+        OptionalAuth(current_user_id): OptionalAuth,
+        Extension(user_dao): Extension<DynUsersDao>,
+        Extension(favorite_dao): Extension<DynFavoritesDao>,
+    ) -> ConduitResult<(StatusCode, Json<ArticlesRes>)> {
+        info!("received req: get user favorites: {}", username);
+        let username = UsernameService::normalize(&username);
+        let Some(user) = user_dao.get_user_by_username(&username).await? else {
+            return Err(ConduitError::NotFound("user not found".to_string()));
+        };
+
+        let favorited = favorite_dao
+            .get_favorited_articles(user.id, current_user_id, query.limit(), query.offset())
+            .await?;
+        let count = favorite_dao
+            .count_favorited_articles(user.id, current_user_id)
+            .await?;
+
+        let articles = favorited.into_iter().map(Self::to_article).collect();
+
+        let res = ArticlesRes {
+            articles,
+            articles_count: count,
+        };
+        Ok((StatusCode::OK, Json(res)))
+    }
+
+    // 記事のいいねの推移 作者だけが見られる
+    #[tracing::instrument(skip(article_dao, favorite_dao))]
+    pub async fn get_favorite_stats(
+        Path(slug): Path<String>,
+        Query(query): Query<FavoriteStatsQuery>,
+        RequiredAuth(current_user_id): RequiredAuth,
+        Extension(article_dao): Extension<DynArticlesDao>,
+        Extension(favorite_dao): Extension<DynFavoritesDao>,
+    ) -> ConduitResult<(StatusCode, Json<FavoriteStatsRes>)> {
+        info!("received req: get favorite stats: {}", slug);
+        let Some(article) = article_dao.get_article_by_slug(&slug).await? else {
+            info!("article not found");
+            return Err(ConduitError::NotFound("article not found".to_string()));
+        };
+        if article.author_id != current_user_id {
+            info!("not the author");
+            return Err(ConduitError::Forbidden(
+                "only the author can view favorite stats".to_string(),
+            ));
+        }
+
+        let days = favorite_dao
+            .get_favorite_stats(article.id, query.days())
+            .await?
+            .into_iter()
+            .map(FavoriteDayStats::from_entity)
+            .collect();
+        let stats = FavoriteStats {
+            favorites_count: article.favorites_count,
+            days,
+        };
+        Ok((StatusCode::OK, Json(FavoriteStatsRes { stats })))
+    }
+
+    fn to_article(entity: FavoritedArticleEntity) -> Article {
+        let author = Profile {
+            username: entity.author_username,
+            bio: entity.author_bio,
+            image: entity.author_image,
+            following: entity.following,
+            is_private: entity.author_is_private,
+            follow_requested: false,
+            followers_count: None,
+            following_count: None,
+            extension: None,
+        };
+        let article = ArticleEntity {
+            id: entity.id,
+            created_at: entity.created_at,
+            updated_at: entity.updated_at,
+            title: entity.title,
+            slug: entity.slug,
+            description: entity.description,
+            body: entity.body,
+            author_id: entity.author_id,
+            favorites_count: entity.favorites_count,
+        };
+        Article {
+            tag_list: entity.tag_list,
+            ..Article::from_entity(article, author, entity.favorited, entity.favorites_count)
+        }
+    }
 }
@@ -0,0 +1,148 @@
+use axum::{
+    extract::{Path, Query},
+    handler::Handler,
+    http::StatusCode,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use tracing::info;
+
+use crate::{
+    core::{
+        access_tokens::entity::TokenScope,
+        profiles::{
+            dao_trait::DynProfilesDao,
+            dto::{Profile, ProfileRes, ProfilesQuery, ProfilesRes},
+        },
+        users::dao_trait::DynUsersDao,
+    },
+    error::{ConduitError, ConduitResult},
+    extractor::{scope, RequiredAuth},
+    services::username::UsernameService,
+};
+
+/// 非公開アカウントに届いたフォローリクエスト
+pub struct FollowRequestRouter {
+    dyn_users_dao: DynUsersDao,
+    dyn_profiles_dao: DynProfilesDao,
+}
+
+impl FollowRequestRouter {
+    pub fn new(dyn_users_dao: DynUsersDao, dyn_profiles_dao: DynProfilesDao) -> Self {
+        Self {
+            dyn_users_dao,
+            dyn_profiles_dao,
+        }
+    }
+
+    pub fn to_router(&self) -> Router {
+        Router::new()
+            .route(
+                "/user/follow-requests",
+                get(Self::get_follow_requests.layer(scope(TokenScope::Read))),
+            )
+            .route(
+                "/user/follow-requests/:username/approve",
+                post(Self::approve_follow_request.layer(scope(TokenScope::ProfilesWrite))),
+            )
+            .route(
+                "/user/follow-requests/:username/reject",
+                post(Self::reject_follow_request.layer(scope(TokenScope::ProfilesWrite))),
+            )
+            .layer(Extension(self.dyn_users_dao.clone()))
+            .layer(Extension(self.dyn_profiles_dao.clone()))
+    }
+
+    #[tracing::instrument(skip(profiles))]
+    pub async fn get_follow_requests(
+        Query(query): Query<ProfilesQuery>,
+        Extension(profiles): Extension<DynProfilesDao>,
+        RequiredAuth(current_user_id): RequiredAuth,
+    ) -> ConduitResult<(StatusCode, Json<ProfilesRes>)> {
+        info!("received req: get follow requests");
+        let requesters = profiles
+            .get_follow_requests(current_user_id, query.limit(), query.offset())
+            .await?;
+        let count = profiles.count_follow_requests(current_user_id).await?;
+        // リクエストを送ってきたユーザーはまだフォロワーではない
+        let res = ProfilesRes {
+            profiles: requesters
+                .into_iter()
+                .map(|user| Profile::from_user_entity(user, false))
+                .collect(),
+            profiles_count: count,
+        };
+        Ok((StatusCode::OK, Json(res)))
+    }
+
+    #[tracing::instrument(skip(users, profiles))]
+    pub async fn approve_follow_request(
+        Path(username): Path<String>,
+        Extension(users): Extension<DynUsersDao>,
+        Extension(profiles): Extension<DynProfilesDao>,
+        RequiredAuth(current_user_id): RequiredAuth,
+    ) -> ConduitResult<(StatusCode, Json<ProfileRes>)> {
+        info!("received req: approve follow request: {}", username);
+        let username = UsernameService::normalize(&username);
+        let Some(requester) = users.get_user_by_username(&username).await? else {
+            return Err(ConduitError::NotFound("user not found".to_string()));
+        };
+
+        if profiles
+            .approve_follow_request(requester.id, current_user_id)
+            .await?
+            .is_none()
+        {
+            return Err(ConduitError::NotFound(
+                "follow request not found".to_string(),
+            ));
+        }
+
+        info!(
+            "follow request approved: from user_id: {}, to user_id: {}",
+            requester.id, current_user_id
+        );
+        let following = profiles
+            .is_follow(current_user_id, requester.id)
+            .await?
+            .is_some();
+        let counts = profiles.get_follow_counts(requester.id).await?;
+        let profile = Profile::from_user_entity(requester, following).with_counts(counts);
+        Ok((StatusCode::OK, Json(ProfileRes { profile })))
+    }
+
+    #[tracing::instrument(skip(users, profiles))]
+    pub async fn reject_follow_request(
+        Path(username): Path<String>,
+        Extension(users): Extension<DynUsersDao>,
+        Extension(profiles): Extension<DynProfilesDao>,
+        RequiredAuth(current_user_id): RequiredAuth,
+    ) -> ConduitResult<(StatusCode, Json<ProfileRes>)> {
+        info!("received req: reject follow request: {}", username);
+        let username = UsernameService::normalize(&username);
+        let Some(requester) = users.get_user_by_username(&username).await? else {
+            return Err(ConduitError::NotFound("user not found".to_string()));
+        };
+
+        if !profiles
+            .delete_follow_request(requester.id, current_user_id)
+            .await?
+        {
+            return Err(ConduitError::NotFound(
+                "follow request not found".to_string(),
+            ));
+        }
+
+        info!(
+            "follow request rejected: from user_id: {}, to user_id: {}",
+            requester.id, current_user_id
+        );
+        let following = profiles
+            .is_follow(current_user_id, requester.id)
+            .await?
+            .is_some();
+        let counts = profiles.get_follow_counts(requester.id).await?;
+        let profile = Profile::from_user_entity(requester, following).with_counts(counts);
+        Ok((StatusCode::OK, Json(ProfileRes { profile })))
+    }
+}
@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use axum::{http::StatusCode, routing::post, Extension, Json, Router};
+use tracing::{error, info};
+use validator::ValidationErrors;
+
+use crate::{
+    core::{
+        password_resets::{
+            dao_trait::DynPasswordResetsDao,
+            dto::{PasswordResetConfirmReq, PasswordResetReq, PasswordResetRes},
+        },
+        users::{dao_trait::DynUsersDao, entity::UserEntity},
+    },
+    error::{ConduitError, ConduitResult},
+    extractor::ValidationExtractor,
+    services::{
+        hash::PasswordHashService,
+        mailer::{DynMailer, Mail},
+        opaque_token::OpaqueTokenService,
+    },
+    ArcState,
+};
+
+const PASSWORD_RESET_TOKEN_TTL: Duration = Duration::from_secs(60 * 60);
+
+pub struct PasswordResetRouter {
+    dyn_users_dao: DynUsersDao,
+    dyn_password_resets_dao: DynPasswordResetsDao,
+    dyn_mailer: DynMailer,
+}
+
+impl PasswordResetRouter {
+    pub fn new(
+        dyn_users_dao: DynUsersDao,
+        dyn_password_resets_dao: DynPasswordResetsDao,
+        dyn_mailer: DynMailer,
+    ) -> Self {
+        Self {
+            dyn_users_dao,
+            dyn_password_resets_dao,
+            dyn_mailer,
+        }
+    }
+
+    pub fn to_router(&self) -> Router {
+        Router::new()
+            .route("/users/password-reset", post(Self::request_password_reset))
+            .route(
+                "/users/password-reset/confirm",
+                post(Self::confirm_password_reset),
+            )
+            .layer(Extension(self.dyn_users_dao.clone()))
+            .layer(Extension(self.dyn_password_resets_dao.clone()))
+            .layer(Extension(self.dyn_mailer.clone()))
+    }
+
+    // アカウントの有無がわからないよう，メールアドレスが存在しなくても同じレスポンスを返す
+    #[tracing::instrument(skip_all)]
+    pub async fn request_password_reset(
+        Extension(state): Extension<ArcState>,
+        Extension(user_dao): Extension<DynUsersDao>,
+        Extension(reset_dao): Extension<DynPasswordResetsDao>,
+        Extension(mailer): Extension<DynMailer>,
+        ValidationExtractor(req): ValidationExtractor<PasswordResetReq>,
+    ) -> ConduitResult<(StatusCode, Json<PasswordResetRes>)> {
+        let email = req.user.email.unwrap();
+
+        let user_entity = user_dao.get_user_by_email(&email).await?;
+        if let Some(user_entity) = user_entity {
+            info!("creating password reset token");
+            let token = OpaqueTokenService::generate();
+            reset_dao
+                .create_reset_token(
+                    user_entity.id,
+                    &OpaqueTokenService::hash(&token),
+                    PASSWORD_RESET_TOKEN_TTL,
+                )
+                .await?;
+
+            let link = format!("{}/reset-password?token={}", state.frontend_url, token);
+            let mail = Mail::new(
+                user_entity.email,
+                "Reset your Conduit password".to_string(),
+                format!(
+                    "Open the link below to reset your password. The link expires in 1 hour.\n\n{}",
+                    link
+                ),
+            );
+            // メール送信にかかる時間からアカウントの有無がわからないよう，送信は待たない
+            tokio::spawn(async move {
+                if let Err(e) = mailer.send(mail).await {
+                    error!("failed to send password reset mail: {}", e);
+                }
+            });
+        } else {
+            info!("password reset requested for unknown email");
+        }
+
+        let res = PasswordResetRes {
+            message: "if the email is registered, a password reset link has been sent".to_string(),
+        };
+        Ok((StatusCode::ACCEPTED, Json(res)))
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn confirm_password_reset(
+        Extension(state): Extension<ArcState>,
+        Extension(user_dao): Extension<DynUsersDao>,
+        Extension(reset_dao): Extension<DynPasswordResetsDao>,
+        ValidationExtractor(req): ValidationExtractor<PasswordResetConfirmReq>,
+    ) -> ConduitResult<(StatusCode, Json<PasswordResetRes>)> {
+        let req = req.user;
+        let password = req.password.unwrap().into_inner();
+
+        // パスワードが基準を満たさなくてもリンクを使い直せるよう，トークンを消費する前に確認する
+        let token_hash = OpaqueTokenService::hash(&req.token.unwrap());
+        let Some(reset_token) = reset_dao.find_reset_token(&token_hash).await? else {
+            info!("invalid or expired password reset token");
+            return Err(ConduitError::BadRequest(
+                "invalid or expired token".to_string(),
+            ));
+        };
+        let user_entity = user_dao.get_user_by_id(reset_token.user_id).await?;
+        let password_check = state
+            .password_policy
+            .check(&password, &[&user_entity.username, &user_entity.email]);
+        ValidationErrors::merge(Ok(()), "user", password_check)?;
+
+        // トークンを使用済みにする．同時に使われて先に消費されていればNone
+        let Some(reset_token) = reset_dao.consume_reset_token(&token_hash).await? else {
+            info!("password reset token was already used");
+            return Err(ConduitError::BadRequest(
+                "invalid or expired token".to_string(),
+            ));
+        };
+
+        info!("resetting password, user_id: {}", reset_token.user_id);
+        let hasher = PasswordHashService::new(state.password_hash_params.clone());
+        let user_entity = hasher.hash_password_user(UserEntity {
+            password,
+            ..user_entity
+        })?;
+        user_dao.update_user(user_entity).await?;
+
+        // 他に発行済みのトークンも使えないようにする
+        reset_dao.delete_unused_tokens(reset_token.user_id).await?;
+
+        info!("password reset successfully");
+        let res = PasswordResetRes {
+            message: "password has been reset".to_string(),
+        };
+        Ok((StatusCode::OK, Json(res)))
+    }
+}
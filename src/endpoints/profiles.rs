@@ -1,23 +1,30 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use axum::{
-    extract::Path,
+    extract::{Path, Query},
+    handler::Handler,
     http::StatusCode,
     routing::{get, post},
     Extension, Json, Router,
 };
 use tracing::info;
+use uuid::Uuid;
 
 use crate::{
     core::{
+        access_tokens::entity::TokenScope,
         profiles::{
             dao_trait::DynProfilesDao,
-            dto::{Profile, ProfileRes},
+            dto::{
+                FollowSuggestionsRes, Profile, ProfileFieldsQuery, ProfileRes, ProfilesQuery,
+                ProfilesRes,
+            },
         },
-        users::dao_trait::DynUsersDao,
+        users::{dao_trait::DynUsersDao, entity::UserEntity},
     },
     error::{ConduitError, ConduitResult},
-    extractor::{OptionalAuth, RequiredAuth},
+    extractor::{scope, OptionalAuth, RequiredAuth},
+    services::username::UsernameService,
 };
 
 pub struct ProfileRouter {
@@ -36,9 +43,35 @@ impl ProfileRouter {
         Router::new()
             .route(
                 "/profiles/:username/follow",
-                post(Self::follow_user).delete(Self::unfollow_user),
+                post(Self::follow_user.layer(scope(TokenScope::ProfilesWrite)))
+                    .delete(Self::unfollow_user.layer(scope(TokenScope::ProfilesWrite))),
+            )
+            .route(
+                "/profiles/:username/block",
+                post(Self::block_user.layer(scope(TokenScope::ProfilesWrite)))
+                    .delete(Self::unblock_user.layer(scope(TokenScope::ProfilesWrite))),
+            )
+            .route(
+                "/profiles/:username/mute",
+                post(Self::mute_user.layer(scope(TokenScope::ProfilesWrite)))
+                    .delete(Self::unmute_user.layer(scope(TokenScope::ProfilesWrite))),
+            )
+            .route(
+                "/profiles/suggestions",
+                get(Self::get_follow_suggestions.layer(scope(TokenScope::Read))),
+            )
+            .route(
+                "/profiles/:username/followers",
+                get(Self::get_followers.layer(scope(TokenScope::Read))),
+            )
+            .route(
+                "/profiles/:username/following",
+                get(Self::get_following.layer(scope(TokenScope::Read))),
+            )
+            .route(
+                "/profiles/:username",
+                get(Self::get_profile_by_username.layer(scope(TokenScope::Read))),
             )
-            .route("/profiles/:username", get(Self::get_profile_by_username))
             .layer(Extension(self.dyn_users_dao.clone()))
             .layer(Extension(self.dyn_profiles_dao.clone()))
     }
@@ -52,28 +85,61 @@ impl ProfileRouter {
         RequiredAuth(current_user_id): RequiredAuth,
     ) -> ConduitResult<(StatusCode, Json<ProfileRes>)> {
         info!("received req: follow profile: {}", username);
+        let username = UsernameService::normalize(&username);
         let followed_user = users.get_user_by_username(&username).await?;
-        let Some(followed_user) = followed_user else {
+        // 退会したユーザーはフォローできない
+        let Some(followed_user) = followed_user.filter(|user| user.deleted_at.is_none()) else {
             return Err(ConduitError::NotFound("user not found".to_string()));
         };
+        if followed_user.id == current_user_id {
+            return Err(ConduitError::BadRequest(
+                "you cannot follow yourself".to_string(),
+            ));
+        }
 
-        let is_following = profiles
-            .get_user_followees(followed_user.id)
+        // どちらかがブロックしている場合はフォローできない
+        if profiles
+            .is_blocked_between(current_user_id, followed_user.id)
             .await?
-            .iter()
-            .any(|f| f.follower_id == current_user_id);
+        {
+            info!("blocked");
+            return Err(ConduitError::Forbidden(
+                "you cannot follow this user".to_string(),
+            ));
+        }
 
-        if !is_following {
-            let _ = profiles
-                .following_user(current_user_id, followed_user.id)
+        // 非公開アカウントの場合は，フォローせずにリクエストを送る
+        if followed_user.is_private
+            && profiles
+                .is_follow(current_user_id, followed_user.id)
+                .await?
+                .is_none()
+        {
+            profiles
+                .request_follow(current_user_id, followed_user.id)
                 .await?;
+            info!(
+                "follow requested: from user_id: {}, to user_id: {}",
+                current_user_id, followed_user.id
+            );
+            let counts = profiles.get_follow_counts(followed_user.id).await?;
+            let profile = Profile::from_user_entity(followed_user, false)
+                .with_counts(counts)
+                .with_follow_requested(true);
+            return Ok((StatusCode::OK, Json(ProfileRes { profile })));
         }
 
+        // すでにフォローしていても成功として扱う
+        profiles
+            .following_user(current_user_id, followed_user.id)
+            .await?;
+
         info!(
             "following: from user_id: {}, to user_id: {}",
             current_user_id, followed_user.id
         );
-        let profile = Profile::from_user_entity(followed_user, true);
+        let counts = profiles.get_follow_counts(followed_user.id).await?;
+        let profile = Profile::from_user_entity(followed_user, true).with_counts(counts);
         let profile_res = ProfileRes { profile };
 
         Ok((StatusCode::OK, Json(profile_res)))
@@ -82,6 +148,7 @@ impl ProfileRouter {
     #[tracing::instrument(skip(users, profiles))]
     pub async fn get_profile_by_username(
         Path(params): Path<HashMap<String, String>>,
+        Query(fields): Query<ProfileFieldsQuery>,
         Extension(users): Extension<DynUsersDao>,
         Extension(profiles): Extension<DynProfilesDao>,
         OptionalAuth(current_user_id): OptionalAuth,
@@ -92,24 +159,31 @@ impl ProfileRouter {
         info!("received req: get profile by username: {}", user_name);
 
         // usernameからユーザー情報を取得
-        let user_entity = users.get_user_by_username(user_name).await?;
+        let user_name = UsernameService::normalize(user_name);
+        let user_entity = users.get_user_by_username(&user_name).await?;
         let Some(user_entity) = user_entity else {
             return Err(ConduitError::NotFound("profile not found".to_string()));
         };
 
-        // current_user_idがある場合，フォローしているかどうかを取得
-        let is_following = if let Some(user_id) = current_user_id {
-            let is_following = profiles
-                .get_user_followees(user_id)
-                .await?
-                .iter()
-                .any(|f| f.follower_id == user_id);
-            is_following
-        } else {
-            false
+        // current_user_idがある場合，このユーザーをフォローしているかどうかを取得
+        let is_following = match current_user_id {
+            Some(user_id) => profiles.is_follow(user_id, user_entity.id).await?.is_some(),
+            None => false,
+        };
+        // フォローリクエストを送っているかどうか
+        let follow_requested = match current_user_id {
+            Some(user_id) if user_entity.is_private => {
+                profiles.has_follow_request(user_id, user_entity.id).await?
+            }
+            _ => false,
         };
 
-        let profile = Profile::from_user_entity(user_entity, is_following);
+        let counts = profiles.get_follow_counts(user_entity.id).await?;
+        let extension = fields.extension(&user_entity);
+        let profile = Profile::from_user_entity(user_entity, is_following)
+            .with_counts(counts)
+            .with_follow_requested(follow_requested)
+            .with_extension(extension);
         let profile_res = ProfileRes { profile };
 
         Ok((StatusCode::OK, Json(profile_res)))
@@ -127,32 +201,526 @@ impl ProfileRouter {
             .ok_or(ConduitError::NotFound(String::from("invalid param")))?;
 
         info!("received req: unfollow profile: {}", username);
-        let followed_user = users.get_user_by_username(username).await?;
-        let Some(followed_user) = followed_user else {
+        let username = UsernameService::normalize(username);
+        let followed_user = users.get_user_by_username(&username).await?;
+        // 退会したユーザーはフォローできない
+        let Some(followed_user) = followed_user.filter(|user| user.deleted_at.is_none()) else {
             return Err(ConduitError::NotFound("user not found".to_string()));
         };
 
-        let is_following = profiles
-            .get_user_followees(current_user_id)
-            .await?
-            .iter()
-            .any(|f| f.follower_id == current_user_id);
-
-        if is_following {
-            let _ = profiles
-                .unfollow_user(current_user_id, followed_user.id)
-                .await?;
-        } else {
-            return Err(ConduitError::BadRequest("not following".to_string()));
-        }
+        // フォローしていなくても成功として扱う 送ったリクエストも取り下げる
+        profiles
+            .unfollow_user(current_user_id, followed_user.id)
+            .await?;
+        profiles
+            .delete_follow_request(current_user_id, followed_user.id)
+            .await?;
 
         info!(
             "unfollowing: from user_id: {}, to user_id: {}",
             current_user_id, followed_user.id
         );
-        let profile = Profile::from_user_entity(followed_user, false);
+        let counts = profiles.get_follow_counts(followed_user.id).await?;
+        let profile = Profile::from_user_entity(followed_user, false).with_counts(counts);
         let profile_res = ProfileRes { profile };
 
         Ok((StatusCode::OK, Json(profile_res)))
     }
+
+    #[tracing::instrument(skip(users, profiles))]
+    pub async fn block_user(
+        Path(username): Path<String>,
+        Extension(users): Extension<DynUsersDao>,
+        Extension(profiles): Extension<DynProfilesDao>,
+        RequiredAuth(current_user_id): RequiredAuth,
+    ) -> ConduitResult<(StatusCode, Json<ProfileRes>)> {
+        info!("received req: block profile: {}", username);
+        let blocked_user = Self::find_other_user(&users, &username, current_user_id).await?;
+
+        profiles
+            .block_user(current_user_id, blocked_user.id)
+            .await?;
+
+        info!(
+            "blocking: from user_id: {}, to user_id: {}",
+            current_user_id, blocked_user.id
+        );
+        // ブロックするとフォローも解除される
+        let counts = profiles.get_follow_counts(blocked_user.id).await?;
+        let profile = Profile::from_user_entity(blocked_user, false).with_counts(counts);
+        Ok((StatusCode::OK, Json(ProfileRes { profile })))
+    }
+
+    #[tracing::instrument(skip(users, profiles))]
+    pub async fn unblock_user(
+        Path(username): Path<String>,
+        Extension(users): Extension<DynUsersDao>,
+        Extension(profiles): Extension<DynProfilesDao>,
+        RequiredAuth(current_user_id): RequiredAuth,
+    ) -> ConduitResult<(StatusCode, Json<ProfileRes>)> {
+        info!("received req: unblock profile: {}", username);
+        let blocked_user = Self::find_other_user(&users, &username, current_user_id).await?;
+
+        // ブロックしていなくても，解除済みの状態を返す
+        if !profiles
+            .unblock_user(current_user_id, blocked_user.id)
+            .await?
+        {
+            info!("not blocking: {}", blocked_user.id);
+        }
+
+        info!(
+            "unblocking: from user_id: {}, to user_id: {}",
+            current_user_id, blocked_user.id
+        );
+        let counts = profiles.get_follow_counts(blocked_user.id).await?;
+        let profile = Profile::from_user_entity(blocked_user, false).with_counts(counts);
+        Ok((StatusCode::OK, Json(ProfileRes { profile })))
+    }
+
+    #[tracing::instrument(skip(users, profiles))]
+    pub async fn mute_user(
+        Path(username): Path<String>,
+        Extension(users): Extension<DynUsersDao>,
+        Extension(profiles): Extension<DynProfilesDao>,
+        RequiredAuth(current_user_id): RequiredAuth,
+    ) -> ConduitResult<(StatusCode, Json<ProfileRes>)> {
+        info!("received req: mute profile: {}", username);
+        let muted_user = Self::find_other_user(&users, &username, current_user_id).await?;
+
+        profiles.mute_user(current_user_id, muted_user.id).await?;
+
+        info!(
+            "muting: from user_id: {}, to user_id: {}",
+            current_user_id, muted_user.id
+        );
+        // ミュートしてもフォローはそのまま
+        let following = profiles
+            .is_follow(current_user_id, muted_user.id)
+            .await?
+            .is_some();
+        let counts = profiles.get_follow_counts(muted_user.id).await?;
+        let profile = Profile::from_user_entity(muted_user, following).with_counts(counts);
+        Ok((StatusCode::OK, Json(ProfileRes { profile })))
+    }
+
+    #[tracing::instrument(skip(users, profiles))]
+    pub async fn unmute_user(
+        Path(username): Path<String>,
+        Extension(users): Extension<DynUsersDao>,
+        Extension(profiles): Extension<DynProfilesDao>,
+        RequiredAuth(current_user_id): RequiredAuth,
+    ) -> ConduitResult<(StatusCode, Json<ProfileRes>)> {
+        info!("received req: unmute profile: {}", username);
+        let muted_user = Self::find_other_user(&users, &username, current_user_id).await?;
+
+        // ミュートしていなくても，解除済みの状態を返す
+        if !profiles.unmute_user(current_user_id, muted_user.id).await? {
+            info!("not muting: {}", muted_user.id);
+        }
+
+        info!(
+            "unmuting: from user_id: {}, to user_id: {}",
+            current_user_id, muted_user.id
+        );
+        let following = profiles
+            .is_follow(current_user_id, muted_user.id)
+            .await?
+            .is_some();
+        let counts = profiles.get_follow_counts(muted_user.id).await?;
+        let profile = Profile::from_user_entity(muted_user, following).with_counts(counts);
+        Ok((StatusCode::OK, Json(ProfileRes { profile })))
+    }
+
+    #[tracing::instrument(skip(users, profiles))]
+    pub async fn get_followers(
+        Path(username): Path<String>,
+        Query(query): Query<ProfilesQuery>,
+        Extension(users): Extension<DynUsersDao>,
+        Extension(profiles): Extension<DynProfilesDao>,
+        OptionalAuth(current_user_id): OptionalAuth,
+    ) -> ConduitResult<(StatusCode, Json<ProfilesRes>)> {
+        info!("received req: get followers: {}", username);
+        let username = UsernameService::normalize(&username);
+        let Some(user) = users.get_user_by_username(&username).await? else {
+            return Err(ConduitError::NotFound("profile not found".to_string()));
+        };
+
+        let followers = profiles
+            .get_followers(user.id, query.limit(), query.offset())
+            .await?;
+        let counts = profiles.get_follow_counts(user.id).await?;
+        let res = ProfilesRes {
+            profiles: Self::to_profiles(&profiles, followers, current_user_id).await?,
+            profiles_count: counts.followers_count,
+        };
+        Ok((StatusCode::OK, Json(res)))
+    }
+
+    #[tracing::instrument(skip(users, profiles))]
+    pub async fn get_following(
+        Path(username): Path<String>,
+        Query(query): Query<ProfilesQuery>,
+        Extension(users): Extension<DynUsersDao>,
+        Extension(profiles): Extension<DynProfilesDao>,
+        OptionalAuth(current_user_id): OptionalAuth,
+    ) -> ConduitResult<(StatusCode, Json<ProfilesRes>)> {
+        info!("received req: get following: {}", username);
+        let username = UsernameService::normalize(&username);
+        let Some(user) = users.get_user_by_username(&username).await? else {
+            return Err(ConduitError::NotFound("profile not found".to_string()));
+        };
+
+        let following = profiles
+            .get_following(user.id, query.limit(), query.offset())
+            .await?;
+        let counts = profiles.get_follow_counts(user.id).await?;
+        let res = ProfilesRes {
+            profiles: Self::to_profiles(&profiles, following, current_user_id).await?,
+            profiles_count: counts.following_count,
+        };
+        Ok((StatusCode::OK, Json(res)))
+    }
+
+    /// おすすめのユーザー フォロー済みのユーザーは含まれない
+    #[tracing::instrument(skip(profiles))]
+    pub async fn get_follow_suggestions(
+        Query(query): Query<ProfilesQuery>,
+        Extension(profiles): Extension<DynProfilesDao>,
+        RequiredAuth(current_user_id): RequiredAuth,
+    ) -> ConduitResult<(StatusCode, Json<FollowSuggestionsRes>)> {
+        info!("received req: get follow suggestions");
+        let suggestions = profiles
+            .get_follow_suggestions(current_user_id, query.limit())
+            .await?;
+        let res = FollowSuggestionsRes {
+            profiles: suggestions
+                .into_iter()
+                .map(|user| Profile::from_user_entity(user, false))
+                .collect(),
+        };
+        Ok((StatusCode::OK, Json(res)))
+    }
+
+    // 一覧の各ユーザーについて，呼び出したユーザーがフォローしているかを付ける
+    pub(crate) async fn to_profiles(
+        profiles: &DynProfilesDao,
+        users: Vec<UserEntity>,
+        current_user_id: Option<Uuid>,
+    ) -> ConduitResult<Vec<Profile>> {
+        let followees = match current_user_id {
+            Some(user_id) => profiles
+                .get_user_followees(user_id)
+                .await?
+                .into_iter()
+                .map(|f| f.followee_id)
+                .collect::<HashSet<_>>(),
+            None => HashSet::new(),
+        };
+        let profiles = users
+            .into_iter()
+            .map(|user| {
+                let following = followees.contains(&user.id);
+                Profile::from_user_entity(user, following)
+            })
+            .collect();
+        Ok(profiles)
+    }
+
+    // ブロックやミュートの相手を探す 自分自身は対象にできない
+    async fn find_other_user(
+        users: &DynUsersDao,
+        username: &str,
+        current_user_id: Uuid,
+    ) -> ConduitResult<UserEntity> {
+        let username = UsernameService::normalize(username);
+        let Some(user) = users.get_user_by_username(&username).await? else {
+            return Err(ConduitError::NotFound("user not found".to_string()));
+        };
+        if user.id == current_user_id {
+            return Err(ConduitError::BadRequest(
+                "you cannot do this to yourself".to_string(),
+            ));
+        }
+        Ok(user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use mockall::predicate::eq;
+    use sqlx::types::time::PrimitiveDateTime;
+
+    use super::*;
+    use crate::core::{
+        profiles::{
+            dao_trait::MockProfilesDaoTrait,
+            entity::{FollowCountsEntity, FollowRequestEntity, UserFollowEntity},
+        },
+        users::{dao_trait::MockUsersDaoTrait, entity::Role},
+    };
+
+    fn user(username: &str, is_private: bool) -> UserEntity {
+        let now = PrimitiveDateTime::MIN;
+        UserEntity {
+            id: Uuid::now_v7(),
+            created_at: now,
+            updated_at: now,
+            username: username.to_string(),
+            email: format!("{}@example.com", username),
+            password: String::new(),
+            bio: String::new(),
+            image: None,
+            email_verified_at: None,
+            role: Role::User.to_string(),
+            is_private,
+            display_name: None,
+            location: None,
+            pronouns: None,
+            links: vec![],
+            deleted_at: None,
+        }
+    }
+
+    fn follow(follower_id: Uuid, followee_id: Uuid) -> UserFollowEntity {
+        UserFollowEntity {
+            id: Uuid::now_v7(),
+            created_at: PrimitiveDateTime::MIN,
+            follower_id,
+            followee_id,
+        }
+    }
+
+    fn users_dao(target: &UserEntity) -> DynUsersDao {
+        let target = target.clone();
+        let mut users = MockUsersDaoTrait::new();
+        users
+            .expect_get_user_by_username()
+            .returning(move |_| Ok(Some(target.clone())));
+        Arc::new(users)
+    }
+
+    fn profiles_dao() -> MockProfilesDaoTrait {
+        let mut profiles = MockProfilesDaoTrait::new();
+        profiles.expect_get_follow_counts().returning(|_| {
+            Ok(FollowCountsEntity {
+                followers_count: 0,
+                following_count: 0,
+            })
+        });
+        profiles
+    }
+
+    async fn get_profile(
+        target: &UserEntity,
+        profiles: MockProfilesDaoTrait,
+        current_user_id: Option<Uuid>,
+    ) -> Profile {
+        let params = HashMap::from([("username".to_string(), target.username.clone())]);
+        let (_, Json(res)) = ProfileRouter::get_profile_by_username(
+            Path(params),
+            Query(ProfileFieldsQuery::default()),
+            Extension(users_dao(target)),
+            Extension(Arc::new(profiles)),
+            OptionalAuth(current_user_id),
+        )
+        .await
+        .unwrap();
+        res.profile
+    }
+
+    #[tokio::test]
+    async fn get_profile_without_auth_is_not_following() {
+        let target = user("target", false);
+        // 未ログインならフォローを調べない
+        let profile = get_profile(&target, profiles_dao(), None).await;
+        assert!(!profile.following);
+    }
+
+    #[tokio::test]
+    async fn get_profile_is_not_following_when_following_someone_else() {
+        let current_user_id = Uuid::now_v7();
+        let target = user("target", false);
+        let mut profiles = profiles_dao();
+        // 他のユーザーをフォローしていても，このユーザーをフォローしていなければfalse
+        profiles
+            .expect_get_user_followees()
+            .returning(move |_| Ok(vec![follow(current_user_id, Uuid::now_v7())]));
+        profiles
+            .expect_is_follow()
+            .with(eq(current_user_id), eq(target.id))
+            .times(1)
+            .returning(|_, _| Ok(None));
+
+        let profile = get_profile(&target, profiles, Some(current_user_id)).await;
+        assert!(!profile.following);
+    }
+
+    #[tokio::test]
+    async fn get_profile_is_following() {
+        let current_user_id = Uuid::now_v7();
+        let target = user("target", false);
+        let target_id = target.id;
+        let mut profiles = profiles_dao();
+        profiles
+            .expect_is_follow()
+            .with(eq(current_user_id), eq(target_id))
+            .times(1)
+            .returning(move |_, _| Ok(Some(follow(current_user_id, target_id))));
+
+        let profile = get_profile(&target, profiles, Some(current_user_id)).await;
+        assert!(profile.following);
+    }
+
+    #[tokio::test]
+    async fn follow_user_is_following() {
+        let current_user_id = Uuid::now_v7();
+        let target = user("target", false);
+        let target_id = target.id;
+        let mut profiles = profiles_dao();
+        profiles
+            .expect_is_blocked_between()
+            .returning(|_, _| Ok(false));
+        profiles
+            .expect_following_user()
+            .with(eq(current_user_id), eq(target_id))
+            .times(1)
+            .returning(move |_, _| Ok(follow(current_user_id, target_id)));
+
+        let (_, Json(res)) = ProfileRouter::follow_user(
+            Path(target.username.clone()),
+            Extension(users_dao(&target)),
+            Extension(Arc::new(profiles)),
+            RequiredAuth(current_user_id),
+        )
+        .await
+        .unwrap();
+        assert!(res.profile.following);
+        assert!(!res.profile.follow_requested);
+    }
+
+    #[tokio::test]
+    async fn follow_private_user_is_not_following_until_approved() {
+        let current_user_id = Uuid::now_v7();
+        let target = user("target", true);
+        let mut profiles = profiles_dao();
+        profiles
+            .expect_is_blocked_between()
+            .returning(|_, _| Ok(false));
+        profiles.expect_is_follow().returning(|_, _| Ok(None));
+        profiles
+            .expect_request_follow()
+            .times(1)
+            .returning(|requester_id, target_id| {
+                Ok(FollowRequestEntity {
+                    id: Uuid::now_v7(),
+                    created_at: PrimitiveDateTime::MIN,
+                    requester_id,
+                    target_id,
+                })
+            });
+
+        let (_, Json(res)) = ProfileRouter::follow_user(
+            Path(target.username.clone()),
+            Extension(users_dao(&target)),
+            Extension(Arc::new(profiles)),
+            RequiredAuth(current_user_id),
+        )
+        .await
+        .unwrap();
+        assert!(!res.profile.following);
+        assert!(res.profile.follow_requested);
+    }
+
+    #[tokio::test]
+    async fn unfollow_user_is_not_following() {
+        let current_user_id = Uuid::now_v7();
+        let target = user("target", false);
+        let target_id = target.id;
+        let mut profiles = profiles_dao();
+        // 他のユーザーをフォローしたままでもfalse
+        profiles
+            .expect_get_user_followees()
+            .returning(move |_| Ok(vec![follow(current_user_id, Uuid::now_v7())]));
+        profiles
+            .expect_unfollow_user()
+            .with(eq(current_user_id), eq(target_id))
+            .times(1)
+            .returning(move |_, _| Ok(Some(follow(current_user_id, target_id))));
+        profiles
+            .expect_delete_follow_request()
+            .returning(|_, _| Ok(false));
+
+        let params = HashMap::from([("username".to_string(), target.username.clone())]);
+        let (_, Json(res)) = ProfileRouter::unfollow_user(
+            Path(params),
+            Extension(users_dao(&target)),
+            Extension(Arc::new(profiles)),
+            RequiredAuth(current_user_id),
+        )
+        .await
+        .unwrap();
+        assert!(!res.profile.following);
+    }
+
+    // ブロックやミュートをしていなくても，解除は成功する
+    #[tokio::test]
+    async fn unblock_and_unmute_are_idempotent() {
+        let current_user_id = Uuid::now_v7();
+        let target = user("target", false);
+        let target_id = target.id;
+        let mut profiles = profiles_dao();
+        profiles
+            .expect_unblock_user()
+            .with(eq(current_user_id), eq(target_id))
+            .times(1)
+            .returning(|_, _| Ok(false));
+        profiles
+            .expect_unmute_user()
+            .with(eq(current_user_id), eq(target_id))
+            .times(1)
+            .returning(|_, _| Ok(false));
+        profiles.expect_is_follow().returning(|_, _| Ok(None));
+        let profiles = Arc::new(profiles) as DynProfilesDao;
+
+        let (status, Json(res)) = ProfileRouter::unblock_user(
+            Path(target.username.clone()),
+            Extension(users_dao(&target)),
+            Extension(profiles.clone()),
+            RequiredAuth(current_user_id),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(res.profile.username, target.username);
+
+        let (status, Json(res)) = ProfileRouter::unmute_user(
+            Path(target.username.clone()),
+            Extension(users_dao(&target)),
+            Extension(profiles),
+            RequiredAuth(current_user_id),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert!(!res.profile.following);
+    }
+
+    #[tokio::test]
+    async fn follow_deleted_user_is_not_found() {
+        let target = UserEntity {
+            deleted_at: Some(PrimitiveDateTime::MIN),
+            ..user("deleted", false)
+        };
+        let result = ProfileRouter::follow_user(
+            Path(target.username.clone()),
+            Extension(users_dao(&target)),
+            Extension(Arc::new(profiles_dao())),
+            RequiredAuth(Uuid::now_v7()),
+        )
+        .await;
+        assert!(matches!(result, Err(ConduitError::NotFound(_))));
+    }
 }
@@ -0,0 +1,215 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path},
+    handler::Handler,
+    http::{request::Parts, StatusCode},
+    routing::post,
+    Extension, Json, Router,
+};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    core::{
+        access_tokens::entity::TokenScope,
+        articles::dao_trait::DynArticlesDao,
+        profiles::dao_trait::DynProfilesDao,
+        reactions::{
+            dao_trait::DynReactionsDao,
+            dto::{ArticleReaction, ReactionsRes},
+        },
+        users::dao_trait::DynUsersDao,
+    },
+    error::{ConduitError, ConduitResult},
+    extractor::{scope, RequiredAuth},
+    services::{authorization::AuthorizationService, reactions::ReactionPolicy},
+    ArcState,
+};
+
+/// 記事へのリアクション
+/// いいねと同じスコープで呼び出せる
+pub struct ReactionRouter {
+    dyn_profiles_dao: DynProfilesDao,
+    dyn_users_dao: DynUsersDao,
+    dyn_articles_dao: DynArticlesDao,
+    dyn_reactions_dao: DynReactionsDao,
+}
+
+impl ReactionRouter {
+    pub fn new(
+        dyn_profiles_dao: DynProfilesDao,
+        dyn_users_dao: DynUsersDao,
+        dyn_articles_dao: DynArticlesDao,
+        dyn_reactions_dao: DynReactionsDao,
+    ) -> Self {
+        Self {
+            dyn_profiles_dao,
+            dyn_users_dao,
+            dyn_articles_dao,
+            dyn_reactions_dao,
+        }
+    }
+
+    pub fn to_router(&self) -> Router {
+        Router::new()
+            .route(
+                "/articles/:slug/reactions/:kind",
+                post(Self::add_reaction.layer(scope(TokenScope::FavoritesWrite)))
+                    .delete(Self::remove_reaction.layer(scope(TokenScope::FavoritesWrite))),
+            )
+            .layer(Extension(self.dyn_profiles_dao.clone()))
+            .layer(Extension(self.dyn_users_dao.clone()))
+            .layer(Extension(self.dyn_articles_dao.clone()))
+            .layer(Extension(self.dyn_reactions_dao.clone()))
+    }
+
+    // すでに同じ種類のリアクションをしている場合も，そのままの状態を返す
+    #[tracing::instrument(skip(state, article_dao, reaction_dao, user_dao, profile_dao))]
+    pub async fn add_reaction(
+        Path((slug, kind)): Path<(String, String)>,
+        RequiredAuth(current_user_id): RequiredAuth,
+        Extension(state): Extension<ArcState>,
+        Extension(profile_dao): Extension<DynProfilesDao>,
+        Extension(user_dao): Extension<DynUsersDao>,
+        Extension(article_dao): Extension<DynArticlesDao>,
+        Extension(reaction_dao): Extension<DynReactionsDao>,
+    ) -> ConduitResult<(StatusCode, Json<ReactionsRes>)> {
+        info!("received req: add reaction: {} to {}", kind, slug);
+        state.reactions.ensure_kind(&kind)?;
+        let Some(article) = article_dao.get_article_by_slug(&slug).await? else {
+            info!("article not found");
+            return Err(ConduitError::NotFound("article not found".to_string()));
+        };
+
+        // 作者にブロックされている場合はリアクションできない
+        if profile_dao
+            .is_blocking(article.author_id, current_user_id)
+            .await?
+        {
+            info!("blocked by author");
+            return Err(ConduitError::Forbidden(
+                "you cannot react to this article".to_string(),
+            ));
+        }
+        // 非公開アカウントの記事は，承認されたフォロワーでなければ存在しないものとして扱う
+        AuthorizationService::ensure_article_visible(
+            &user_dao,
+            &profile_dao,
+            Some(current_user_id),
+            article.author_id,
+        )
+        .await?;
+
+        reaction_dao
+            .add_reaction(current_user_id, article.id, &kind)
+            .await?;
+        info!("reaction added");
+
+        let reactions = Self::to_reactions(
+            &state.reactions,
+            &reaction_dao,
+            article.id,
+            Some(current_user_id),
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(ReactionsRes { reactions })))
+    }
+
+    // リアクションしていなかった場合も，そのままの状態を返す
+    #[tracing::instrument(skip(state, article_dao, reaction_dao, user_dao, profile_dao))]
+    pub async fn remove_reaction(
+        Path((slug, kind)): Path<(String, String)>,
+        RequiredAuth(current_user_id): RequiredAuth,
+        Extension(state): Extension<ArcState>,
+        Extension(profile_dao): Extension<DynProfilesDao>,
+        Extension(user_dao): Extension<DynUsersDao>,
+        Extension(article_dao): Extension<DynArticlesDao>,
+        Extension(reaction_dao): Extension<DynReactionsDao>,
+    ) -> ConduitResult<(StatusCode, Json<ReactionsRes>)> {
+        info!("received req: remove reaction: {} from {}", kind, slug);
+        state.reactions.ensure_kind(&kind)?;
+        let Some(article) = article_dao.get_article_by_slug(&slug).await? else {
+            info!("article not found");
+            return Err(ConduitError::NotFound("article not found".to_string()));
+        };
+        // 非公開アカウントの記事は，承認されたフォロワーでなければ存在しないものとして扱う
+        AuthorizationService::ensure_article_visible(
+            &user_dao,
+            &profile_dao,
+            Some(current_user_id),
+            article.author_id,
+        )
+        .await?;
+
+        reaction_dao
+            .remove_reaction(current_user_id, article.id, &kind)
+            .await?;
+        info!("reaction removed");
+
+        let reactions = Self::to_reactions(
+            &state.reactions,
+            &reaction_dao,
+            article.id,
+            Some(current_user_id),
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(ReactionsRes { reactions })))
+    }
+
+    // 記事のリアクションの件数に，呼び出したユーザーがリアクションしているかを付ける
+    pub(crate) async fn to_reactions(
+        policy: &ReactionPolicy,
+        reactions: &DynReactionsDao,
+        article_id: i32,
+        current_user_id: Option<Uuid>,
+    ) -> ConduitResult<Vec<ArticleReaction>> {
+        let counts = reactions.get_reaction_counts(article_id).await?;
+        let reacted = match current_user_id {
+            Some(user_id) => reactions.get_user_reactions(user_id, article_id).await?,
+            None => vec![],
+        };
+        Ok(policy.summarize(&counts, &reacted))
+    }
+}
+
+/// 記事のリアクションを集計する
+/// 設定とDAOの両方が必要なので，記事のハンドラーの引数が増えないようにまとめて取り出す
+pub struct ReactionSummarizer {
+    state: ArcState,
+    reactions: DynReactionsDao,
+}
+
+impl ReactionSummarizer {
+    pub(crate) async fn summarize(
+        &self,
+        article_id: i32,
+        current_user_id: Option<Uuid>,
+    ) -> ConduitResult<Vec<ArticleReaction>> {
+        ReactionRouter::to_reactions(
+            &self.state.reactions,
+            &self.reactions,
+            article_id,
+            current_user_id,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ReactionSummarizer
+where
+    S: Send + Sync,
+{
+    type Rejection = ConduitError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Extension(app_state): Extension<ArcState> =
+            Extension::from_request_parts(parts, state).await?;
+        let Extension(reactions): Extension<DynReactionsDao> =
+            Extension::from_request_parts(parts, state).await?;
+        Ok(Self {
+            state: app_state,
+            reactions,
+        })
+    }
+}
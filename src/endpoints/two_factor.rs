@@ -0,0 +1,205 @@
+use axum::{
+    http::StatusCode,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use tracing::{info, warn};
+
+use crate::{
+    core::{
+        two_factor::{
+            dao_trait::DynTwoFactorDao,
+            dto::{
+                TwoFactorCodeReq, TwoFactorEnrollment, TwoFactorEnrollmentRes, TwoFactorStatus,
+                TwoFactorStatusRes,
+            },
+            entity::UserTotpEntity,
+        },
+        users::dao_trait::DynUsersDao,
+    },
+    error::{ConduitError, ConduitResult},
+    extractor::{RequiredAuth, ValidationExtractor},
+    services::{opaque_token::OpaqueTokenService, totp::TotpService},
+    ArcState,
+};
+
+/// 認証アプリのコードかリカバリーコードを確認する
+/// 2要素認証が有効になっているユーザーに対してのみ使う ログイン時にも使う
+pub(crate) async fn verify_second_factor(
+    state: &ArcState,
+    two_factor_dao: &DynTwoFactorDao,
+    totp: &UserTotpEntity,
+    code: &str,
+) -> ConduitResult<bool> {
+    if TotpService::is_totp_code(code) {
+        // 鍵が外されていると認証アプリのコードは確認できない リカバリーコードは使える
+        let Some(cipher) = &state.totp.cipher else {
+            warn!("TOTP_ENCRYPTION_KEY is not set, cannot verify two-factor code");
+            return Ok(false);
+        };
+        let secret = cipher.decrypt(&totp.secret_ciphertext, totp.user_id.as_bytes())?;
+        let Some(step) = TotpService::verify_now(&secret, code)? else {
+            return Ok(false);
+        };
+        // 一度使ったコードは，有効期間内でも使えないようにする
+        return two_factor_dao.use_totp_step(totp.user_id, step).await;
+    }
+    let code_hash = OpaqueTokenService::hash(&TotpService::normalize_recovery_code(code));
+    two_factor_dao
+        .consume_recovery_code(totp.user_id, &code_hash)
+        .await
+}
+
+pub struct TwoFactorRouter {
+    dyn_users_dao: DynUsersDao,
+    dyn_two_factor_dao: DynTwoFactorDao,
+}
+
+impl TwoFactorRouter {
+    pub fn new(dyn_users_dao: DynUsersDao, dyn_two_factor_dao: DynTwoFactorDao) -> Self {
+        Self {
+            dyn_users_dao,
+            dyn_two_factor_dao,
+        }
+    }
+
+    pub fn to_router(&self) -> Router {
+        Router::new()
+            .route(
+                "/user/2fa",
+                get(Self::get_two_factor_status).post(Self::enroll_two_factor),
+            )
+            .route("/user/2fa/confirm", post(Self::confirm_two_factor))
+            .route("/user/2fa/disable", post(Self::disable_two_factor))
+            .layer(Extension(self.dyn_users_dao.clone()))
+            .layer(Extension(self.dyn_two_factor_dao.clone()))
+    }
+
+    #[tracing::instrument(skip(two_factor_dao))]
+    pub async fn get_two_factor_status(
+        RequiredAuth(user_id): RequiredAuth,
+        Extension(two_factor_dao): Extension<DynTwoFactorDao>,
+    ) -> ConduitResult<(StatusCode, Json<TwoFactorStatusRes>)> {
+        let totp = two_factor_dao.get_totp(user_id).await?;
+        let enabled = totp.is_some_and(|totp| totp.is_enabled());
+        let res = TwoFactorStatusRes {
+            two_factor: TwoFactorStatus { enabled },
+        };
+        Ok((StatusCode::OK, Json(res)))
+    }
+
+    /// 登録を始める 秘密鍵とリカバリーコードはこのレスポンスでしか返さない
+    /// 確認のエンドポイントでコードを確認するまでは有効にならない
+    #[tracing::instrument(skip(state, user_dao, two_factor_dao))]
+    pub async fn enroll_two_factor(
+        RequiredAuth(user_id): RequiredAuth,
+        Extension(state): Extension<ArcState>,
+        Extension(user_dao): Extension<DynUsersDao>,
+        Extension(two_factor_dao): Extension<DynTwoFactorDao>,
+    ) -> ConduitResult<(StatusCode, Json<TwoFactorEnrollmentRes>)> {
+        let cipher = state.totp.cipher()?;
+        let user_entity = user_dao.get_user_by_id(user_id).await?;
+
+        let secret = TotpService::generate_secret();
+        let secret_ciphertext = cipher.encrypt(&secret, user_id.as_bytes())?;
+        let recovery_codes = TotpService::generate_recovery_codes();
+        let recovery_code_hashes = recovery_codes
+            .iter()
+            .map(|code| OpaqueTokenService::hash(&TotpService::normalize_recovery_code(code)))
+            .collect();
+
+        let created = two_factor_dao
+            .create_pending_totp(user_id, secret_ciphertext, recovery_code_hashes)
+            .await?;
+        if !created {
+            return Err(ConduitError::Conflict(
+                "two-factor authentication is already enabled".to_string(),
+            ));
+        }
+
+        info!("two-factor enrollment started");
+        let res = TwoFactorEnrollmentRes {
+            two_factor: TwoFactorEnrollment {
+                otpauth_uri: TotpService::otpauth_uri(
+                    &secret,
+                    &state.totp.issuer,
+                    &user_entity.email,
+                )?,
+                secret: TotpService::secret_base32(&secret)?,
+                recovery_codes,
+            },
+        };
+        Ok((StatusCode::CREATED, Json(res)))
+    }
+
+    /// 認証アプリのコードを確認して有効にする
+    #[tracing::instrument(skip(state, two_factor_dao, req))]
+    pub async fn confirm_two_factor(
+        RequiredAuth(user_id): RequiredAuth,
+        Extension(state): Extension<ArcState>,
+        Extension(two_factor_dao): Extension<DynTwoFactorDao>,
+        ValidationExtractor(req): ValidationExtractor<TwoFactorCodeReq>,
+    ) -> ConduitResult<(StatusCode, Json<TwoFactorStatusRes>)> {
+        let Some(totp) = two_factor_dao.get_totp(user_id).await? else {
+            return Err(ConduitError::BadRequest(
+                "two-factor enrollment has not been started".to_string(),
+            ));
+        };
+        if totp.is_enabled() {
+            return Err(ConduitError::Conflict(
+                "two-factor authentication is already enabled".to_string(),
+            ));
+        }
+
+        // 認証アプリに正しく登録できたことを確かめるため，リカバリーコードは受け付けない
+        let secret = state
+            .totp
+            .cipher()?
+            .decrypt(&totp.secret_ciphertext, user_id.as_bytes())?;
+        let code = req.two_factor.code.unwrap();
+        let Some(step) = TotpService::verify_now(&secret, code.expose())? else {
+            info!("invalid two-factor code");
+            return Err(ConduitError::BadRequest(
+                "invalid two-factor code".to_string(),
+            ));
+        };
+        two_factor_dao.enable_totp(user_id, step).await?;
+
+        info!("two-factor authentication enabled");
+        let res = TwoFactorStatusRes {
+            two_factor: TwoFactorStatus { enabled: true },
+        };
+        Ok((StatusCode::OK, Json(res)))
+    }
+
+    /// 認証アプリのコードかリカバリーコードを確認して無効にする
+    #[tracing::instrument(skip(state, two_factor_dao, req))]
+    pub async fn disable_two_factor(
+        RequiredAuth(user_id): RequiredAuth,
+        Extension(state): Extension<ArcState>,
+        Extension(two_factor_dao): Extension<DynTwoFactorDao>,
+        ValidationExtractor(req): ValidationExtractor<TwoFactorCodeReq>,
+    ) -> ConduitResult<(StatusCode, Json<TwoFactorStatusRes>)> {
+        let totp = two_factor_dao.get_totp(user_id).await?;
+        let Some(totp) = totp.filter(|totp| totp.is_enabled()) else {
+            return Err(ConduitError::BadRequest(
+                "two-factor authentication is not enabled".to_string(),
+            ));
+        };
+
+        let code = req.two_factor.code.unwrap();
+        if !verify_second_factor(&state, &two_factor_dao, &totp, code.expose()).await? {
+            info!("invalid two-factor code");
+            return Err(ConduitError::BadRequest(
+                "invalid two-factor code".to_string(),
+            ));
+        }
+        two_factor_dao.delete_totp(user_id).await?;
+
+        info!("two-factor authentication disabled");
+        let res = TwoFactorStatusRes {
+            two_factor: TwoFactorStatus { enabled: false },
+        };
+        Ok((StatusCode::OK, Json(res)))
+    }
+}
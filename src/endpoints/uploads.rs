@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context as _;
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS},
+        StatusCode,
+    },
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    core::uploads::dto::{Upload, UploadRes, UploadVariant},
+    error::{ConduitError, ConduitResult},
+    extractor::RequiredAuth,
+    services::{
+        blob_store::{is_valid_key, Blob, DynBlobStore},
+        image_upload::ImageUploadService,
+    },
+    ArcState,
+};
+
+// multipartの境界やヘッダーの分だけ，ファイルサイズの上限より多めに受け付ける
+const MULTIPART_OVERHEAD: usize = 16 * 1024;
+
+/// 画像のアップロード
+/// アップロードはアクセストークンでは呼び出せないよう，スコープは付けない
+pub struct UploadRouter {
+    dyn_blob_store: DynBlobStore,
+    max_bytes: usize,
+}
+
+impl UploadRouter {
+    pub fn new(dyn_blob_store: DynBlobStore, max_bytes: usize) -> Self {
+        Self {
+            dyn_blob_store,
+            max_bytes,
+        }
+    }
+
+    pub fn to_router(&self) -> Router {
+        Router::new()
+            .route(
+                "/uploads",
+                post(Self::upload_image)
+                    .layer(DefaultBodyLimit::max(self.max_bytes + MULTIPART_OVERHEAD)),
+            )
+            .route("/uploads/:key", get(Self::get_upload))
+            .layer(Extension(self.dyn_blob_store.clone()))
+    }
+
+    /// `file`フィールドの画像を検証し，サイズ違いの画像と一緒に保存する
+    #[tracing::instrument(skip(state, blob_store, multipart))]
+    pub async fn upload_image(
+        RequiredAuth(user_id): RequiredAuth,
+        Extension(state): Extension<ArcState>,
+        Extension(blob_store): Extension<DynBlobStore>,
+        mut multipart: Multipart,
+    ) -> ConduitResult<(StatusCode, Json<UploadRes>)> {
+        info!("received req: upload image");
+        let mut file = None;
+        while let Some(field) = multipart.next_field().await? {
+            if field.name() == Some("file") {
+                file = Some(field.bytes().await?);
+                break;
+            }
+        }
+        let Some(file) = file else {
+            return Err(ConduitError::BadRequest("file is required".to_string()));
+        };
+
+        // デコードや縮小には時間がかかるので，非同期のワーカーを止めないよう別スレッドで行う
+        let service = ImageUploadService::new(state.uploads.clone());
+        let variants = tokio::task::spawn_blocking(move || service.process(&file))
+            .await
+            .context("unexpected error: while processing image")??;
+
+        let id = Uuid::now_v7().simple().to_string();
+        // サイズ違いの画像も同じ形式で書き出される
+        let content_type = variants[0].content_type.to_string();
+        let mut upload_variants = BTreeMap::new();
+        for variant in variants {
+            let key = format!("{}-{}.{}", id, variant.name, variant.extension);
+            let blob = Blob {
+                content_type: variant.content_type.to_string(),
+                bytes: variant.bytes,
+            };
+            blob_store.put(&key, blob).await?;
+            upload_variants.insert(
+                variant.name.to_string(),
+                UploadVariant {
+                    url: format!("/api/uploads/{}", key),
+                    width: variant.width,
+                    height: variant.height,
+                },
+            );
+        }
+        info!("image uploaded: {} by {}", id, user_id);
+
+        let original = &upload_variants["original"];
+        let upload = Upload {
+            url: original.url.clone(),
+            width: original.width,
+            height: original.height,
+            id,
+            content_type,
+            variants: upload_variants,
+        };
+        Ok((StatusCode::CREATED, Json(UploadRes { upload })))
+    }
+
+    /// 保存した画像を返す
+    /// キーは毎回新しく作り，同じキーの内容は変わらないので，長くキャッシュさせる
+    #[tracing::instrument(skip(blob_store))]
+    pub async fn get_upload(
+        Path(key): Path<String>,
+        Extension(blob_store): Extension<DynBlobStore>,
+    ) -> ConduitResult<Response> {
+        if !is_valid_key(&key) {
+            return Err(ConduitError::NotFound("upload not found".to_string()));
+        }
+        let Some(blob) = blob_store.get(&key).await? else {
+            return Err(ConduitError::NotFound("upload not found".to_string()));
+        };
+        Ok((
+            StatusCode::OK,
+            [
+                (CONTENT_TYPE, blob.content_type),
+                (
+                    CACHE_CONTROL,
+                    "public, max-age=31536000, immutable".to_string(),
+                ),
+                (X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            ],
+            blob.bytes,
+        )
+            .into_response())
+    }
+}
@@ -1,45 +1,98 @@
 use axum::{
+    extract::Query,
+    handler::Handler,
     http::StatusCode,
+    response::{IntoResponse, Response},
     routing::{get, post, put},
     Extension, Json, Router,
 };
 
-use tracing::info;
+use tracing::{info, warn};
+use uuid::Uuid;
+use validator::ValidationErrors;
 // ハンドラー周りでよくわからないエラーメッセージがでたら，
 // #[debug_handler]をつけてデバッグするといい
 #[allow(unused_imports)]
 use axum_macros::debug_handler;
 
 use crate::{
-    core::users::{
-        dao_trait::DynUsersDao,
-        dto::{
-            GetUserRes, LoginUserReq, LoginUserRes, RegisterUserReq, RegisterUserRes,
-            UpdateUserReq, UpdateUserRes,
+    core::{
+        access_tokens::entity::TokenScope,
+        email_verifications::dao_trait::DynEmailVerificationsDao,
+        login_attempts::dao_trait::DynLoginAttemptsDao,
+        login_audits::{dao_trait::DynLoginAuditsDao, entity::LoginAuditEvent},
+        profiles::dto::ProfileFieldsQuery,
+        two_factor::{
+            dao_trait::DynTwoFactorDao,
+            dto::{TwoFactorChallenge, TwoFactorChallengeRes, TwoFactorLoginReq},
+            entity::UserTotpEntity,
+        },
+        users::{
+            dao_trait::DynUsersDao,
+            dto::{
+                GetUserRes, LoginUserReq, LoginUserRes, RegisterUserReq, RegisterUserRes,
+                UpdateUserReq, UpdateUserRes,
+            },
         },
     },
+    endpoints::{email_verifications::send_verification_mail, two_factor::verify_second_factor},
     error::{ConduitError, ConduitResult},
-    extractor::{RequiredAuth, ValidationExtractor},
-    services::{hash::PasswordHashService, jwt::JwtService},
+    extractor::{scope, ClientIp, RequiredAuth, ValidationExtractor},
+    services::{
+        hash::PasswordHashService,
+        jwt::JwtService,
+        login_throttle::{LoginAttempt, LoginThrottle},
+        mailer::DynMailer,
+        opaque_token::OpaqueTokenService,
+        username::UsernameService,
+    },
     ArcState,
 };
 
 pub struct UserRouter {
     dyn_users_dao: DynUsersDao,
+    dyn_email_verifications_dao: DynEmailVerificationsDao,
+    dyn_mailer: DynMailer,
+    dyn_login_attempts_dao: DynLoginAttemptsDao,
+    dyn_login_audits_dao: DynLoginAuditsDao,
+    dyn_two_factor_dao: DynTwoFactorDao,
 }
 
 impl UserRouter {
-    pub fn new(dyn_users_dao: DynUsersDao) -> Self {
-        Self { dyn_users_dao }
+    pub fn new(
+        dyn_users_dao: DynUsersDao,
+        dyn_email_verifications_dao: DynEmailVerificationsDao,
+        dyn_mailer: DynMailer,
+        dyn_login_attempts_dao: DynLoginAttemptsDao,
+        dyn_login_audits_dao: DynLoginAuditsDao,
+        dyn_two_factor_dao: DynTwoFactorDao,
+    ) -> Self {
+        Self {
+            dyn_users_dao,
+            dyn_email_verifications_dao,
+            dyn_mailer,
+            dyn_login_attempts_dao,
+            dyn_login_audits_dao,
+            dyn_two_factor_dao,
+        }
     }
 
     pub fn to_router(&self) -> Router {
         Router::new()
             .route("/users", post(Self::register_user))
             .route("/users/login", post(Self::login_user))
-            .route("/user", get(Self::get_current_user))
+            .route("/users/login/2fa", post(Self::login_two_factor))
+            .route(
+                "/user",
+                get(Self::get_current_user.layer(scope(TokenScope::Read))),
+            )
             .route("/user", put(Self::update_user))
             .layer(Extension(self.dyn_users_dao.clone()))
+            .layer(Extension(self.dyn_email_verifications_dao.clone()))
+            .layer(Extension(self.dyn_mailer.clone()))
+            .layer(Extension(self.dyn_login_attempts_dao.clone()))
+            .layer(Extension(self.dyn_login_audits_dao.clone()))
+            .layer(Extension(self.dyn_two_factor_dao.clone()))
     }
 
     // ログ出力結果にパスワードを含まないようにする
@@ -48,12 +101,24 @@ impl UserRouter {
     pub async fn register_user(
         Extension(state): Extension<ArcState>,
         Extension(user_dao): Extension<DynUsersDao>,
+        Extension(verification_dao): Extension<DynEmailVerificationsDao>,
+        Extension(mailer): Extension<DynMailer>,
         ValidationExtractor(req): ValidationExtractor<RegisterUserReq>,
     ) -> ConduitResult<(StatusCode, Json<RegisterUserRes>)> {
-        let req = req.user;
+        let mut req = req.user;
+        let username = UsernameService::normalize(req.username.as_ref().unwrap());
+        ValidationErrors::merge(Ok(()), "user", UsernameService::validate(&username))?;
+        req.username = Some(username);
+
+        let password_check = state.password_policy.check(
+            req.password.as_ref().unwrap().expose(),
+            &[req.username.as_ref().unwrap(), req.email.as_ref().unwrap()],
+        );
+        ValidationErrors::merge(Ok(()), "user", password_check)?;
 
         info!("creating password hash user: {:?}", &req.email);
-        let hashed_user = PasswordHashService::hash_password_newuser(req)?;
+        let hasher = PasswordHashService::new(state.password_hash_params.clone());
+        let hashed_user = hasher.hash_password_newuser(req)?;
         // ここにDBへの登録処理を書く
 
         info!(
@@ -63,10 +128,16 @@ impl UserRouter {
         let user_entity = user_dao.create_user(hashed_user).await?;
 
         info!(
-            "user created successfully generating token user {:?}",
+            "user created successfully sending verification mail {:?}",
             &user_entity.email
         );
-        let token = JwtService::new(state.clone()).to_token(user_entity.id);
+        send_verification_mail(&state, &verification_dao, &mailer, &user_entity).await?;
+
+        info!(
+            "verification mail queued generating token user {:?}",
+            &user_entity.email
+        );
+        let token = JwtService::new(state.clone()).to_token(user_entity.id, user_entity.role());
         let user = user_entity.into_dto_with_generated_token(token);
         let user_res = RegisterUserRes { user };
 
@@ -75,6 +146,7 @@ impl UserRouter {
 
     #[tracing::instrument(skip(state, user_dao))]
     pub async fn get_current_user(
+        Query(fields): Query<ProfileFieldsQuery>,
         Extension(state): Extension<ArcState>,
         Extension(user_dao): Extension<DynUsersDao>,
         RequiredAuth(user_id): RequiredAuth,
@@ -86,9 +158,12 @@ impl UserRouter {
             "user retrieved successfully email{:?}, generating token",
             &user_entity.email
         );
-        let token = JwtService::new(state.clone()).to_token(user_entity.id);
+        let token = JwtService::new(state.clone()).to_token(user_entity.id, user_entity.role());
 
-        let user = user_entity.into_dto_with_generated_token(token);
+        let extension = fields.extension(&user_entity);
+        let user = user_entity
+            .into_dto_with_generated_token(token)
+            .with_extension(extension);
         let user_res = GetUserRes { user };
 
         Ok((StatusCode::OK, Json(user_res)))
@@ -96,36 +171,190 @@ impl UserRouter {
 
     #[tracing::instrument(skip_all,fields(req_user = req.user.email))]
     pub async fn login_user(
+        ClientIp(client_ip): ClientIp,
         Extension(state): Extension<ArcState>,
         Extension(user_dao): Extension<DynUsersDao>,
+        Extension(attempts_dao): Extension<DynLoginAttemptsDao>,
+        Extension(audit_dao): Extension<DynLoginAuditsDao>,
+        Extension(two_factor_dao): Extension<DynTwoFactorDao>,
         ValidationExtractor(req): ValidationExtractor<LoginUserReq>,
-    ) -> ConduitResult<(StatusCode, Json<LoginUserRes>)> {
+    ) -> ConduitResult<Response> {
         let req = req.user;
+        let email = req.email.unwrap();
+        let throttle = LoginThrottle::new(attempts_dao, state.login_throttle_policy.clone());
 
-        let user_entity = user_dao.get_user_by_email(&req.email.unwrap()).await?;
+        // 失敗が続いている場合は，パスワードを確認する前に弾く
+        let attempt = match throttle.begin_attempt(&email, client_ip.as_deref()).await {
+            Ok(attempt) => attempt,
+            Err(e) => {
+                info!("login throttled, email: {:?}", &email);
+                audit_dao
+                    .record_login_event(LoginAuditEvent::Throttled, &email, None, client_ip)
+                    .await?;
+                return Err(e);
+            }
+        };
+
+        let password = req.password.unwrap();
+        let hasher = PasswordHashService::new(state.password_hash_params.clone());
+        let user_entity = user_dao.get_user_by_email(&email).await?;
         // let else文 Someを返してきたら束縛 そうでないならelseで書いた文を実行
+        // メールアドレスの有無が分からないよう，パスワード違いと同じエラーを同じだけ時間をかけて返す
         let Some(user_entity) = user_entity else {
-            return Err(ConduitError::NotFound(String::from(
-                "User not found: email is not exist",
-            )));
+            let verified = hasher.verify_dummy_password(password.expose());
+            info!("invalid login, unknown email");
+            Self::record_login_failure(&throttle, &attempt, &audit_dao, &email, None, client_ip)
+                .await?;
+            return verified.and(Err(ConduitError::InvalidLogin));
         };
         info!(
             "user retrieved successfully, email:{:?}, verifying password",
             &user_entity.email
         );
 
-        info!(
-            "{:?}, {:?}",
-            &user_entity.password.clone(),
-            &req.password.clone().unwrap()
-        );
-        PasswordHashService::verify_password(&user_entity.password, &req.password.unwrap())
+        let verified = hasher
+            .verify_password(&user_entity.password, password.expose())
             .inspect_err(|e| {
                 info!("invalid login, user: {:?}, err: {}", &user_entity.email, e);
-            })?;
+            });
+        let needs_rehash = match verified {
+            Ok(needs_rehash) => {
+                throttle.release(attempt).await?;
+                needs_rehash
+            }
+            Err(e) => {
+                Self::record_login_failure(
+                    &throttle,
+                    &attempt,
+                    &audit_dao,
+                    &email,
+                    Some(user_entity.id),
+                    client_ip,
+                )
+                .await?;
+                return Err(e);
+            }
+        };
+
+        // ハッシュの設定が変わっていれば，平文のパスワードが手元にある今のうちに作り直す
+        // 失敗してもログイン自体は成功させる
+        if needs_rehash {
+            info!("rehashing password, user: {:?}", &user_entity.email);
+            let rehashed = match hasher.hash_password(password.expose()) {
+                Ok(hash) => user_dao.update_password(user_entity.id, &hash).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = rehashed {
+                warn!(
+                    "failed to rehash password, user: {:?}, err: {}",
+                    &user_entity.email, e
+                );
+            }
+        }
+
+        // 2要素認証が有効なら，トークンの代わりにチャレンジを返す
+        let totp = two_factor_dao.get_totp(user_entity.id).await?;
+        if totp.is_some_and(|totp| totp.is_enabled()) {
+            info!("password verified successfully, two-factor authentication required");
+            let challenge_token = OpaqueTokenService::generate();
+            two_factor_dao
+                .create_challenge(
+                    user_entity.id,
+                    &OpaqueTokenService::hash(&challenge_token),
+                    state.totp.challenge_ttl,
+                )
+                .await?;
+            let res = TwoFactorChallengeRes {
+                two_factor: TwoFactorChallenge {
+                    challenge_token,
+                    expires_in: state.totp.challenge_ttl.as_secs(),
+                },
+            };
+            return Ok((StatusCode::OK, Json(res)).into_response());
+        }
+
+        // 失敗の回数はログインを完了したときだけ戻す
+        // 2要素認証の前に戻すと，パスワードを知っていればコードを何度でも試せてしまう
+        throttle.record_success(&email).await?;
 
         info!("password verified successfully, generating token");
-        let token = JwtService::new(state.clone()).to_token(user_entity.id);
+        let token = JwtService::new(state.clone()).to_token(user_entity.id, user_entity.role());
+
+        let user = user_entity.into_dto_with_generated_token(token);
+        let user_res = LoginUserRes { user };
+
+        Ok((StatusCode::OK, Json(user_res)).into_response())
+    }
+
+    /// ログインの2段階目 チャレンジトークンと，認証アプリのコードかリカバリーコードを確認してトークンを発行する
+    #[tracing::instrument(skip_all)]
+    pub async fn login_two_factor(
+        ClientIp(client_ip): ClientIp,
+        Extension(state): Extension<ArcState>,
+        Extension(user_dao): Extension<DynUsersDao>,
+        Extension(attempts_dao): Extension<DynLoginAttemptsDao>,
+        Extension(audit_dao): Extension<DynLoginAuditsDao>,
+        Extension(two_factor_dao): Extension<DynTwoFactorDao>,
+        ValidationExtractor(req): ValidationExtractor<TwoFactorLoginReq>,
+    ) -> ConduitResult<(StatusCode, Json<LoginUserRes>)> {
+        let req = req.user;
+        let token_hash = OpaqueTokenService::hash(req.challenge_token.unwrap().expose());
+        let Some(challenge) = two_factor_dao.get_challenge(&token_hash).await? else {
+            info!("invalid or expired two-factor challenge");
+            return Err(ConduitError::InvalidLogin);
+        };
+        let user_entity = user_dao.get_user_by_id(challenge.user_id).await?;
+        let email = user_entity.email.clone();
+        let throttle = LoginThrottle::new(attempts_dao, state.login_throttle_policy.clone());
+
+        let attempt = match throttle.begin_attempt(&email, client_ip.as_deref()).await {
+            Ok(attempt) => attempt,
+            Err(e) => {
+                info!("login throttled, email: {:?}", &email);
+                audit_dao
+                    .record_login_event(
+                        LoginAuditEvent::Throttled,
+                        &email,
+                        Some(user_entity.id),
+                        client_ip,
+                    )
+                    .await?;
+                return Err(e);
+            }
+        };
+
+        // チャレンジの発行後に2要素認証が無効にされていたら，そのチャレンジも使えない
+        let totp = two_factor_dao.get_totp(user_entity.id).await?;
+        let verified = match totp.filter(UserTotpEntity::is_enabled) {
+            Some(totp) => {
+                let code = req.code.unwrap();
+                verify_second_factor(&state, &two_factor_dao, &totp, code.expose()).await?
+            }
+            None => false,
+        };
+        if !verified {
+            info!("invalid two-factor code, email: {:?}", &email);
+            let failed_count = two_factor_dao.record_challenge_failure(&token_hash).await?;
+            if failed_count >= state.totp.max_challenge_attempts {
+                two_factor_dao.delete_challenge(&token_hash).await?;
+            }
+            Self::record_login_failure(
+                &throttle,
+                &attempt,
+                &audit_dao,
+                &email,
+                Some(user_entity.id),
+                client_ip,
+            )
+            .await?;
+            return Err(ConduitError::InvalidLogin);
+        }
+        two_factor_dao.delete_challenge(&token_hash).await?;
+        throttle.release(attempt).await?;
+        throttle.record_success(&email).await?;
+
+        info!("two-factor code verified successfully, generating token");
+        let token = JwtService::new(state.clone()).to_token(user_entity.id, user_entity.role());
 
         let user = user_entity.into_dto_with_generated_token(token);
         let user_res = LoginUserRes { user };
@@ -133,28 +362,67 @@ impl UserRouter {
         Ok((StatusCode::OK, Json(user_res)))
     }
 
+    // ログイン失敗を記録し，監査ログに残す
+    async fn record_login_failure(
+        throttle: &LoginThrottle,
+        attempt: &LoginAttempt,
+        audit_dao: &DynLoginAuditsDao,
+        email: &str,
+        user_id: Option<Uuid>,
+        client_ip: Option<String>,
+    ) -> ConduitResult<()> {
+        let locked = throttle.record_failure(attempt).await?;
+        audit_dao
+            .record_login_event(LoginAuditEvent::Failed, email, user_id, client_ip.clone())
+            .await?;
+        if locked {
+            info!("login locked, email: {:?}", email);
+            audit_dao
+                .record_login_event(LoginAuditEvent::Locked, email, user_id, client_ip)
+                .await?;
+        }
+        Ok(())
+    }
+
     // #[debug_handler]
     #[tracing::instrument(skip_all,fields(req_user = req.user.email))]
     pub async fn update_user(
         RequiredAuth(user_id): RequiredAuth,
+        Query(fields): Query<ProfileFieldsQuery>,
         Extension(state): Extension<ArcState>,
         Extension(user_dao): Extension<DynUsersDao>,
+        Extension(verification_dao): Extension<DynEmailVerificationsDao>,
+        Extension(mailer): Extension<DynMailer>,
         // Request本文を消費するエキストラクターは1つのみかつ引数の最後でなければならない
         // https://docs.rs/axum/0.7.6/axum/extract/index.html
         ValidationExtractor(req): ValidationExtractor<UpdateUserReq>,
     ) -> ConduitResult<(StatusCode, Json<UpdateUserRes>)> {
-        let req = req.user;
+        let mut req = req.user;
         // Noneのフィールドを更新しないようにする
         // ユーザーをIDを使って取得
         // Noneのフィールドは取得したユーザーのフィールドで上書き
         // ユーザーを更新
 
+        if let Some(username) = req.username.take() {
+            let username = UsernameService::normalize(&username);
+            ValidationErrors::merge(Ok(()), "user", UsernameService::validate(&username))?;
+            req.username = Some(username);
+        }
+
         info!("retrieving user_id: {:?}", user_id);
         let user_entity = user_dao.get_user_by_id(user_id).await?;
+        let old_email = user_entity.email.clone();
         let user_entity = if req.password.is_some() {
             let user_entity = user_entity.update_user_entity(req);
+            // 変更後のユーザー名とメールアドレスに対して確認する
+            let password_check = state.password_policy.check(
+                &user_entity.password,
+                &[&user_entity.username, &user_entity.email],
+            );
+            ValidationErrors::merge(Ok(()), "user", password_check)?;
             info!("hashing password for user: {:?}", &user_entity.email);
-            PasswordHashService::hash_password_user(user_entity)?
+            PasswordHashService::new(state.password_hash_params.clone())
+                .hash_password_user(user_entity)?
         } else {
             user_entity.update_user_entity(req)
         };
@@ -165,12 +433,24 @@ impl UserRouter {
         );
         let user_entity = user_dao.update_user(user_entity).await?;
 
+        // メールアドレスが変わった場合は確認済みの状態がリセットされるので，確認し直してもらう
+        if user_entity.email != old_email {
+            info!(
+                "email changed, sending verification mail to {:?}",
+                &user_entity.email
+            );
+            send_verification_mail(&state, &verification_dao, &mailer, &user_entity).await?;
+        }
+
         info!(
             "user updated successfully, email:{:?}, generating token",
             &user_entity.email
         );
-        let token = JwtService::new(state.clone()).to_token(user_entity.id);
-        let user = user_entity.into_dto_with_generated_token(token);
+        let token = JwtService::new(state.clone()).to_token(user_entity.id, user_entity.role());
+        let extension = fields.extension(&user_entity);
+        let user = user_entity
+            .into_dto_with_generated_token(token)
+            .with_extension(extension);
         let user_res = UpdateUserRes { user };
 
         Ok((StatusCode::OK, Json(user_res)))
@@ -0,0 +1,37 @@
+use axum::{
+    http::{header::CACHE_CONTROL, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Extension, Json, Router,
+};
+use tracing::info;
+
+use crate::{error::ConduitResult, ArcState};
+
+/// /.well-known 以下のエンドポイント
+/// /api の外に置くので，main.rsではnestではなくmergeする
+#[derive(Default)]
+pub struct WellKnownRouter;
+
+impl WellKnownRouter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn to_router(&self) -> Router {
+        Router::new().route("/.well-known/jwks.json", get(Self::jwks))
+    }
+
+    // 他のサービスがトークンを検証するための公開鍵一覧
+    // 鍵のローテーションに追従できるよう，キャッシュは短めにする
+    #[tracing::instrument(skip_all)]
+    pub async fn jwks(Extension(state): Extension<ArcState>) -> ConduitResult<impl IntoResponse> {
+        info!("serving jwks");
+        let jwks = state.jwt_keys.jwks().clone();
+        Ok((
+            StatusCode::OK,
+            [(CACHE_CONTROL, "public, max-age=300")],
+            Json(jwks),
+        ))
+    }
+}
@@ -1,8 +1,11 @@
-use std::fmt::Display;
+use std::{fmt::Display, time::Duration};
 
 use axum::{
-    extract::rejection::{ExtensionRejection, JsonRejection},
-    http::StatusCode,
+    extract::{
+        multipart::MultipartError,
+        rejection::{ExtensionRejection, JsonRejection},
+    },
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -49,11 +52,29 @@ pub enum ConduitError {
     Conflict(String),
     #[error("Forbidden: {0}")]
     Forbidden(String),
+    // ログイン試行が多すぎる場合 Retry-Afterヘッダーで待ち時間を返す
+    #[error("Too many requests")]
+    TooManyRequests(Duration),
+    // アップロードされたファイルが大きすぎる場合
+    #[error("{0}")]
+    PayloadTooLarge(String),
+    // 対応していない形式のファイルがアップロードされた場合
+    #[error("{0}")]
+    UnsupportedMediaType(String),
+    // multipartの読み込みに失敗した場合 サイズの上限を超えた場合も含む
+    #[error(transparent)]
+    AxumMultipartError(#[from] MultipartError),
 }
 
 impl IntoResponse for ConduitError {
     fn into_response(self) -> Response {
         info!("Error: {:?}", self);
+        let retry_after = match &self {
+            Self::TooManyRequests(retry_after) => {
+                Some((retry_after.as_secs_f64().ceil() as u64).max(1))
+            }
+            _ => None,
+        };
         let (s, message) = match self {
             Self::Unauthorized => (StatusCode::UNAUTHORIZED, Self::Unauthorized.to_string()),
             // https://tex2e.github.io/rfc-translater/html/rfc9110.html
@@ -80,10 +101,22 @@ impl IntoResponse for ConduitError {
             Self::AxumExtensionRejection(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
             Self::AnyhowError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
             Self::Forbidden(e) => (StatusCode::FORBIDDEN, e),
+            Self::TooManyRequests(_) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                Self::TooManyRequests(Duration::ZERO).to_string(),
+            ),
+            Self::PayloadTooLarge(e) => (StatusCode::PAYLOAD_TOO_LARGE, e),
+            Self::UnsupportedMediaType(e) => (StatusCode::UNSUPPORTED_MEDIA_TYPE, e),
+            Self::AxumMultipartError(e) => (e.status(), e.body_text()),
         };
         let body = Json(message);
 
-        (s, body).into_response()
+        let mut res = (s, body).into_response();
+        if let Some(retry_after) = retry_after {
+            res.headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+        res
     }
 }
 
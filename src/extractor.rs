@@ -1,6 +1,8 @@
+use std::net::SocketAddr;
+
 use axum::{
     async_trait,
-    extract::{FromRequest, FromRequestParts, Request},
+    extract::{ConnectInfo, FromRequest, FromRequestParts, Request},
     http::{header::AUTHORIZATION, request::Parts},
     Extension, Json,
 };
@@ -9,7 +11,16 @@ use tracing::info;
 use uuid::Uuid;
 use validator::Validate;
 
-use crate::{error::ConduitError, services::jwt::JwtService, ArcState};
+use crate::{
+    core::access_tokens::{
+        dao_trait::DynAccessTokensDao,
+        entity::{TokenScope, ACCESS_TOKEN_PREFIX},
+    },
+    core::users::{dao_trait::DynUsersDao, entity::Role},
+    error::ConduitError,
+    services::{authorization::Actor, jwt::JwtService, opaque_token::OpaqueTokenService},
+    ArcState,
+};
 
 #[derive(Debug, Clone)]
 pub struct ValidationExtractor<T>(pub T);
@@ -60,6 +71,24 @@ where
 {
     type Rejection = ConduitError;
 
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let RequiredActor(actor) = RequiredActor::from_request_parts(parts, state).await?;
+        Ok(RequiredAuth(actor.user_id))
+    }
+}
+
+/// RequiredAuthと同じだが，ユーザーIDに加えて権限も取り出す
+/// クレームの権限は手がかりとしてだけ使い，一般ユーザー以外はDBの権限で判断する
+/// 認可の判断はservices::authorizationに任せる
+pub struct RequiredActor(pub Actor);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequiredActor
+where
+    S: Send + Sync,
+{
+    type Rejection = ConduitError;
+
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let headers = parts.headers.clone();
         let token_value = headers
@@ -85,17 +114,69 @@ where
             return Err(ConduitError::Unauthorized);
         };
 
-        let Extension(state): Extension<ArcState> = Extension::from_request_parts(parts, state)
-            .await
-            .map_err(|e| {
-                println!("error: {:?}", e);
-                ConduitError::InternalServerError
-            })?;
-        let claim = JwtService::new(state).get_claim_from_token(token_value)?;
-        Ok(RequiredAuth(claim.user_id))
+        let actor = authenticate(parts, state, token_value).await?;
+        // 一般ユーザーのクレームはそれ以上の権限を持たないので，そのまま使う
+        // 降格されたユーザーの古いトークンで強い権限を使われないよう，それ以外はDBの権限を読み直す
+        if actor.role == Role::User {
+            return Ok(RequiredActor(actor));
+        }
+        let Extension(users_dao): Extension<DynUsersDao> =
+            Extension::from_request_parts(parts, state).await?;
+        let user = users_dao.get_user_by_id(actor.user_id).await?;
+        Ok(RequiredActor(Actor::new(user.id, user.role())))
     }
 }
 
+/// ルートごとに，アクセストークンで呼び出すために必要なスコープを指定する
+/// 指定のないルートはJWTでのみ呼び出せる
+#[derive(Debug, Clone, Copy)]
+pub struct RequiredScope(pub TokenScope);
+
+/// `post(handler.layer(scope(TokenScope::ArticlesWrite)))`のように，ハンドラーごとに付ける
+pub fn scope(scope: TokenScope) -> Extension<RequiredScope> {
+    Extension(RequiredScope(scope))
+}
+
+// トークンからユーザーを取り出す JWTとアクセストークンの両方を受け付ける
+async fn authenticate<S>(parts: &mut Parts, state: &S, token: &str) -> Result<Actor, ConduitError>
+where
+    S: Send + Sync,
+{
+    if token.starts_with(ACCESS_TOKEN_PREFIX) {
+        let Some(RequiredScope(required)) = parts.extensions.get::<RequiredScope>().copied() else {
+            return Err(ConduitError::Forbidden(
+                "access tokens cannot be used for this endpoint".to_string(),
+            ));
+        };
+        let Extension(access_tokens_dao): Extension<DynAccessTokensDao> =
+            Extension::from_request_parts(parts, state).await?;
+        let access_token = access_tokens_dao
+            .use_access_token(&OpaqueTokenService::hash(token))
+            .await?
+            .ok_or(ConduitError::Unauthorized)?;
+        if !access_token.has_scope(required) {
+            return Err(ConduitError::Forbidden(format!(
+                "access token does not have the required scope: {}",
+                required
+            )));
+        }
+        // モデレーションなどの強い権限は，アクセストークンには与えない
+        return Ok(Actor::new(access_token.user_id, Role::User));
+    }
+
+    let Extension(users_dao): Extension<DynUsersDao> =
+        Extension::from_request_parts(parts, state).await?;
+    let Extension(state): Extension<ArcState> = Extension::from_request_parts(parts, state).await?;
+    let claim = JwtService::new(state).get_claim_from_token(token)?;
+
+    // 退会したユーザーのトークンは，有効期限内でも受け付けない
+    if !users_dao.is_active_user(claim.user_id).await? {
+        info!("token for deleted user: {}", claim.user_id);
+        return Err(ConduitError::Unauthorized);
+    }
+    Ok(Actor::new(claim.user_id, claim.role))
+}
+
 // #[async_trait]
 // impl<S> FromRequest<S> for RequiredAuth
 // where
@@ -155,15 +236,8 @@ where
                 let Some(token_value) = token_value.get(1) else {
                     return Err(ConduitError::Unauthorized);
                 };
-                let Extension(state): Extension<ArcState> =
-                    Extension::from_request_parts(parts, state)
-                        .await
-                        .map_err(|e| {
-                            println!("error: {:?}", e);
-                            ConduitError::InternalServerError
-                        })?;
-                let claim = JwtService::new(state).get_claim_from_token(token_value)?;
-                Some(claim.user_id)
+                let actor = authenticate(parts, state, token_value).await?;
+                Some(actor.user_id)
             }
             None => None,
         };
@@ -171,3 +245,82 @@ where
         Ok(OptionalAuth(token_value))
     }
 }
+
+/// クライアントのIPアドレスを取得する
+/// X-Forwarded-Forの先頭はクライアントが自由に書き換えられるので，信頼できるプロキシが
+/// 追加した右側のエントリを使う．プロキシの段数は`AppState::trusted_proxy_hops`で指定する
+/// 取得できなければNone
+pub struct ClientIp(pub Option<String>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = ConduitError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Extension(app_state): Extension<ArcState> =
+            Extension::from_request_parts(parts, state).await?;
+
+        let forwarded_for = parts
+            .headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .collect::<Vec<_>>();
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string());
+
+        Ok(ClientIp(select_client_ip(
+            &forwarded_for,
+            peer,
+            app_state.trusted_proxy_hops,
+        )))
+    }
+}
+
+// 信頼できるプロキシがn段あれば，右からn番目のエントリが直前のプロキシから見た接続元になる
+// プロキシを経由しない(0段)場合や，ヘッダーがない場合は接続元のアドレスを使う
+fn select_client_ip(forwarded_for: &[&str], peer: Option<String>, hops: usize) -> Option<String> {
+    if hops == 0 || forwarded_for.is_empty() {
+        return peer;
+    }
+    let index = forwarded_for.len().saturating_sub(hops);
+    Some(forwarded_for[index].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uses_entry_added_by_trusted_proxy() {
+        // クライアントが先頭に偽のアドレスを入れても，プロキシが追加した右端を使う
+        let forwarded_for = ["1.1.1.1", "203.0.113.7"];
+        let ip = select_client_ip(&forwarded_for, Some("10.0.0.1".to_string()), 1);
+        assert_eq!(ip.as_deref(), Some("203.0.113.7"));
+
+        let forwarded_for = ["1.1.1.1", "203.0.113.7", "10.0.0.2"];
+        let ip = select_client_ip(&forwarded_for, Some("10.0.0.1".to_string()), 2);
+        assert_eq!(ip.as_deref(), Some("203.0.113.7"));
+    }
+
+    #[test]
+    fn falls_back_to_peer_address() {
+        let peer = Some("198.51.100.1".to_string());
+        assert_eq!(select_client_ip(&[], peer.clone(), 1), peer);
+        assert_eq!(select_client_ip(&["1.1.1.1"], peer.clone(), 0), peer);
+    }
+
+    #[test]
+    fn short_header_uses_leftmost_entry() {
+        let ip = select_client_ip(&["203.0.113.7"], None, 3);
+        assert_eq!(ip.as_deref(), Some("203.0.113.7"));
+    }
+}
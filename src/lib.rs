@@ -1,5 +1,10 @@
 use std::sync::Arc;
 
+use services::{
+    account_deletion::AccountDeletionPolicy, favorites::FavoritePolicy, hash::PasswordHashParams,
+    image_upload::UploadPolicy, jwt::JwtKeys, login_throttle::LoginThrottlePolicy,
+    password_policy::PasswordPolicy, reactions::ReactionPolicy, totp::TotpSettings,
+};
 use sqlx::PgPool;
 
 pub mod core;
@@ -7,12 +12,27 @@ pub mod dao;
 pub mod endpoints;
 pub mod error;
 pub mod extractor;
+pub mod redact;
 pub mod services;
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
-    pub jwt_secret: String,
+    pub jwt_keys: JwtKeys,
+    // メールに載せるリンクの生成に使う
+    pub frontend_url: String,
+    // trueならメールアドレスの確認が済むまで記事を投稿できない
+    pub require_verified_email: bool,
+    // アプリの前段にある信頼できるプロキシの数．X-Forwarded-Forからクライアントを特定するのに使う
+    pub trusted_proxy_hops: usize,
+    pub login_throttle_policy: LoginThrottlePolicy,
+    pub password_hash_params: PasswordHashParams,
+    pub password_policy: PasswordPolicy,
+    pub totp: TotpSettings,
+    pub account_deletion: AccountDeletionPolicy,
+    pub uploads: UploadPolicy,
+    pub favorites: FavoritePolicy,
+    pub reactions: ReactionPolicy,
 }
 
 type ArcState = Arc<AppState>;
@@ -3,13 +3,36 @@ use std::sync::Arc;
 use axum::{routing::get, Extension, Router};
 use realworld_axum_betashuttle::{
     core::{
-        articles::dao_trait::DynArticlesDao, profiles::dao_trait::DynProfilesDao,
-        tags::dao_trait::DynTagsDao, users::dao_trait::DynUsersDao,
+        access_tokens::dao_trait::DynAccessTokensDao, account_data::dao_trait::DynAccountDataDao,
+        articles::dao_trait::DynArticlesDao,
+        email_verifications::dao_trait::DynEmailVerificationsDao,
+        login_attempts::dao_trait::DynLoginAttemptsDao, login_audits::dao_trait::DynLoginAuditsDao,
+        password_resets::dao_trait::DynPasswordResetsDao, profiles::dao_trait::DynProfilesDao,
+        reactions::dao_trait::DynReactionsDao, tags::dao_trait::DynTagsDao,
+        two_factor::dao_trait::DynTwoFactorDao, users::dao_trait::DynUsersDao,
     },
-    dao::Daos,
+    dao::{login_attempts::InMemoryLoginAttemptsDao, Daos},
     endpoints::{
-        articles::ArticleRouter, favorites::FavoritesRouter, profiles::ProfileRouter,
-        users::UserRouter,
+        access_tokens::AccessTokenRouter, account_data::AccountDataRouter, admin::AdminRouter,
+        articles::ArticleRouter, email_verifications::EmailVerificationRouter,
+        favorites::FavoritesRouter, follow_requests::FollowRequestRouter,
+        password_resets::PasswordResetRouter, profiles::ProfileRouter, reactions::ReactionRouter,
+        two_factor::TwoFactorRouter, uploads::UploadRouter, users::UserRouter,
+        well_known::WellKnownRouter,
+    },
+    services::{
+        account_deletion::{AccountDeletionJob, AccountDeletionPolicy},
+        blob_store::blob_store_from_secrets,
+        favorites::{FavoritePolicy, FavoritePurgeJob},
+        follow_suggestions::{FollowSuggestionsRefreshJob, DEFAULT_REFRESH_INTERVAL},
+        hash::{PasswordHashParams, PasswordHashService},
+        image_upload::UploadPolicy,
+        jwt::JwtKeys,
+        login_throttle::LoginThrottlePolicy,
+        mailer::mailer_from_secrets,
+        password_policy::PasswordPolicy,
+        reactions::ReactionPolicy,
+        totp::TotpSettings,
     },
     AppState,
 };
@@ -26,8 +49,39 @@ async fn main(
 ) -> shuttle_axum::ShuttleAxum {
     let state = AppState {
         pool,
-        jwt_secret: _secrets.get("JWT_SECRET").unwrap(),
+        jwt_keys: JwtKeys::from_secrets(|key| _secrets.get(key))
+            .expect("invalid jwt key configuration"),
+        frontend_url: _secrets
+            .get("FRONTEND_URL")
+            .unwrap_or_else(|| "http://localhost:3000".to_string()),
+        require_verified_email: _secrets
+            .get("REQUIRE_VERIFIED_EMAIL")
+            .is_some_and(|v| v == "true"),
+        // Shuttleではプロキシを1段経由する
+        trusted_proxy_hops: _secrets
+            .get("TRUSTED_PROXY_HOPS")
+            .map(|v| v.parse().expect("invalid TRUSTED_PROXY_HOPS"))
+            .unwrap_or(1),
+        login_throttle_policy: LoginThrottlePolicy::default(),
+        password_hash_params: PasswordHashParams::from_secrets(|key| _secrets.get(key))
+            .expect("invalid password hash configuration"),
+        password_policy: PasswordPolicy::from_secrets(|key| _secrets.get(key))
+            .expect("invalid password policy configuration"),
+        totp: TotpSettings::from_secrets(|key| _secrets.get(key))
+            .expect("invalid two-factor configuration"),
+        account_deletion: AccountDeletionPolicy::from_secrets(|key| _secrets.get(key))
+            .expect("invalid account deletion configuration"),
+        uploads: UploadPolicy::from_secrets(|key| _secrets.get(key))
+            .expect("invalid upload configuration"),
+        favorites: FavoritePolicy::from_secrets(|key| _secrets.get(key))
+            .expect("invalid favorites configuration"),
+        reactions: ReactionPolicy::from_secrets(|key| _secrets.get(key))
+            .expect("invalid reactions configuration"),
     };
+    // 存在しないユーザーでのログインに使うダミーのハッシュは，最初のログインの前に作っておく
+    PasswordHashService::new(state.password_hash_params.clone())
+        .prepare_dummy_hash()
+        .expect("failed to prepare dummy password hash");
     let state = Arc::new(state);
     let daos = Daos::new(state.pool.clone());
 
@@ -36,21 +90,101 @@ async fn main(
     let dyn_articles_dao = Arc::new(daos.articles) as DynArticlesDao;
     let dyn_tags_dao = Arc::new(daos.tags) as DynTagsDao;
     let dyn_favorite_dao = Arc::new(daos.favorites);
+    let dyn_reactions_dao = Arc::new(daos.reactions) as DynReactionsDao;
+    let dyn_password_resets_dao = Arc::new(daos.password_resets) as DynPasswordResetsDao;
+    let dyn_email_verifications_dao =
+        Arc::new(daos.email_verifications) as DynEmailVerificationsDao;
+    // 単一インスタンスで動かすならメモリ上で十分
+    let dyn_login_attempts_dao = match _secrets.get("LOGIN_ATTEMPTS_STORE").as_deref() {
+        Some("memory") => Arc::new(InMemoryLoginAttemptsDao::new()) as DynLoginAttemptsDao,
+        _ => Arc::new(daos.login_attempts) as DynLoginAttemptsDao,
+    };
+    let dyn_login_audits_dao = Arc::new(daos.login_audits) as DynLoginAuditsDao;
+    let dyn_two_factor_dao = Arc::new(daos.two_factor) as DynTwoFactorDao;
+    let dyn_access_tokens_dao = Arc::new(daos.access_tokens) as DynAccessTokensDao;
+    let dyn_account_data_dao = Arc::new(daos.account_data) as DynAccountDataDao;
+    let dyn_blob_store = blob_store_from_secrets(|key| _secrets.get(key))
+        .expect("invalid upload store configuration");
+    let dyn_mailer = mailer_from_secrets(|key| _secrets.get(key), state.pool.clone())
+        .expect("invalid mailer configuration");
+
+    // 猶予期間の過ぎた退会を定期的に処理する
+    tokio::spawn(
+        AccountDeletionJob::new(dyn_account_data_dao.clone())
+            .run(state.account_deletion.job_interval),
+    );
+    // 解除してから時間の経ったいいねを定期的に消す
+    tokio::spawn(
+        FavoritePurgeJob::new(dyn_favorite_dao.clone(), state.favorites.purge_after)
+            .run(state.favorites.purge_interval),
+    );
+    // おすすめユーザーの集計を定期的に更新する
+    tokio::spawn(
+        FollowSuggestionsRefreshJob::new(dyn_profiles_dao.clone()).run(DEFAULT_REFRESH_INTERVAL),
+    );
 
     let router = Router::new()
         .route("/", get(hello_world))
-        .nest("/api", UserRouter::new(dyn_users_dao.clone()).to_router())
+        .merge(WellKnownRouter::new().to_router())
+        .nest(
+            "/api",
+            UserRouter::new(
+                dyn_users_dao.clone(),
+                dyn_email_verifications_dao.clone(),
+                dyn_mailer.clone(),
+                dyn_login_attempts_dao.clone(),
+                dyn_login_audits_dao.clone(),
+                dyn_two_factor_dao.clone(),
+            )
+            .to_router(),
+        )
+        .nest(
+            "/api",
+            TwoFactorRouter::new(dyn_users_dao.clone(), dyn_two_factor_dao.clone()).to_router(),
+        )
+        .nest(
+            "/api",
+            AccessTokenRouter::new(dyn_access_tokens_dao.clone()).to_router(),
+        )
+        .nest(
+            "/api",
+            AccountDataRouter::new(dyn_users_dao.clone(), dyn_account_data_dao.clone()).to_router(),
+        )
+        .nest("/api", AdminRouter::new(dyn_users_dao.clone()).to_router())
+        .nest(
+            "/api",
+            EmailVerificationRouter::new(
+                dyn_users_dao.clone(),
+                dyn_email_verifications_dao.clone(),
+                dyn_mailer.clone(),
+            )
+            .to_router(),
+        )
+        .nest(
+            "/api",
+            PasswordResetRouter::new(
+                dyn_users_dao.clone(),
+                dyn_password_resets_dao.clone(),
+                dyn_mailer.clone(),
+            )
+            .to_router(),
+        )
         .nest(
             "/api",
             ProfileRouter::new(dyn_users_dao.clone(), dyn_profiles_dao.clone()).to_router(),
         )
+        .nest(
+            "/api",
+            FollowRequestRouter::new(dyn_users_dao.clone(), dyn_profiles_dao.clone()).to_router(),
+        )
         .nest(
             "/api",
             ArticleRouter::new(
                 dyn_articles_dao.clone(),
                 dyn_users_dao.clone(),
                 dyn_tags_dao.clone(),
-                dyn_favorite_dao.clone(),
+                dyn_profiles_dao.clone(),
+                dyn_reactions_dao.clone(),
             )
             .to_router(),
         )
@@ -61,9 +195,27 @@ async fn main(
                 dyn_users_dao.clone(),
                 dyn_articles_dao.clone(),
                 dyn_favorite_dao.clone(),
+                dyn_reactions_dao.clone(),
             )
             .to_router(),
         )
+        .nest(
+            "/api",
+            ReactionRouter::new(
+                dyn_profiles_dao.clone(),
+                dyn_users_dao.clone(),
+                dyn_articles_dao.clone(),
+                dyn_reactions_dao,
+            )
+            .to_router(),
+        )
+        .nest(
+            "/api",
+            UploadRouter::new(dyn_blob_store, state.uploads.max_bytes).to_router(),
+        )
+        // RequiredAuthでアクセストークンと退会済みのユーザーを確認するために使う
+        .layer(Extension(dyn_access_tokens_dao))
+        .layer(Extension(dyn_users_dao))
         .layer(Extension(state));
 
     Ok(router.into())
@@ -0,0 +1,71 @@
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+const REDACTED: &str = "[REDACTED]";
+
+/// パスワードなど，ログに出してはいけない値を包む
+///
+/// Debugでは中身を出力しないので，DTOごと`#[tracing::instrument]`や`{:?}`で出力しても漏れない
+/// シリアライズ時も中身は出さない(バリデーションエラーのパラメータなどに載るため)
+/// Displayは実装していないので，値を使うときは`expose`か`into_inner`で明示的に取り出す
+#[derive(Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct Redacted<T>(T);
+
+impl<T> Redacted<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Redacted<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> Debug for Redacted<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T> Serialize for Redacted<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Login {
+        email: String,
+        password: Option<Redacted<String>>,
+    }
+
+    #[test]
+    fn debug_does_not_print_secret() {
+        let login: Login =
+            serde_json::from_str(r#"{"email": "a@example.com", "password": "hunter2"}"#).unwrap();
+        let debug = format!("{:?}", login);
+        assert!(debug.contains("a@example.com"));
+        assert!(debug.contains(REDACTED));
+        assert!(!debug.contains("hunter2"));
+        let json = serde_json::to_string(&login.password).unwrap();
+        assert!(!json.contains("hunter2"));
+        assert_eq!(login.email, "a@example.com");
+        assert_eq!(login.password.unwrap().expose(), "hunter2");
+    }
+}
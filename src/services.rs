@@ -1,2 +1,16 @@
+pub mod account_deletion;
+pub mod authorization;
+pub mod blob_store;
+pub mod favorites;
+pub mod follow_suggestions;
 pub mod hash;
+pub mod image_upload;
 pub mod jwt;
+pub mod login_throttle;
+pub mod mailer;
+pub mod opaque_token;
+pub mod password_policy;
+pub mod reactions;
+pub mod secret_cipher;
+pub mod totp;
+pub mod username;
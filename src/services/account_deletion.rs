@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use anyhow::Context as _;
+use tracing::{info, warn};
+
+use crate::{
+    core::account_data::{dao_trait::DynAccountDataDao, entity::DeletionMode},
+    error::ConduitResult,
+};
+
+const SECS_PER_DAY: u64 = 60 * 60 * 24;
+// 1回の実行で処理する件数 多い場合は次の実行に回す
+const BATCH_SIZE: i64 = 100;
+
+/// 退会の設定
+#[derive(Debug, Clone)]
+pub struct AccountDeletionPolicy {
+    pub mode: DeletionMode,
+    // 退会を申請してから実際に削除するまでの期間 この間は取り消せる
+    pub grace_period: Duration,
+    // 猶予期間の過ぎた退会を確認する間隔
+    pub job_interval: Duration,
+}
+
+impl Default for AccountDeletionPolicy {
+    fn default() -> Self {
+        Self {
+            mode: DeletionMode::Anonymize,
+            grace_period: Duration::from_secs(SECS_PER_DAY * 14),
+            job_interval: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl AccountDeletionPolicy {
+    /// シークレットから設定を読み込む 省略した項目はデフォルト値になる
+    ///
+    /// - `ACCOUNT_DELETION_MODE`: `anonymize`(デフォルト)または`cascade`
+    /// - `ACCOUNT_DELETION_GRACE_DAYS`: 猶予期間の日数．デフォルトは14日
+    pub fn from_secrets(get: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let mut policy = Self::default();
+        if let Some(mode) = get("ACCOUNT_DELETION_MODE") {
+            policy.mode = mode
+                .parse()
+                .map_err(anyhow::Error::msg)
+                .context("invalid ACCOUNT_DELETION_MODE")?;
+        }
+        if let Some(days) = get("ACCOUNT_DELETION_GRACE_DAYS") {
+            let days: u64 = days
+                .parse()
+                .context("invalid ACCOUNT_DELETION_GRACE_DAYS")?;
+            policy.grace_period = Duration::from_secs(SECS_PER_DAY * days);
+        }
+        Ok(policy)
+    }
+}
+
+/// 猶予期間の過ぎた退会を処理するジョブ
+pub struct AccountDeletionJob {
+    account_data: DynAccountDataDao,
+}
+
+impl AccountDeletionJob {
+    pub fn new(account_data: DynAccountDataDao) -> Self {
+        Self { account_data }
+    }
+
+    /// 予約時のモードに従って削除し，処理した件数を返す
+    /// 1件の失敗で残りが止まらないよう，失敗はログに出して次に進む
+    pub async fn run_once(&self) -> ConduitResult<usize> {
+        let deletions = self.account_data.get_due_deletions(BATCH_SIZE).await?;
+        let mut processed = 0;
+        for deletion in deletions {
+            let result = match deletion.mode() {
+                DeletionMode::Anonymize => self.account_data.anonymize_user(deletion.user_id).await,
+                DeletionMode::Cascade => self.account_data.delete_user(deletion.user_id).await,
+            };
+            match result {
+                Ok(()) => {
+                    info!("account deleted: {} ({})", deletion.user_id, deletion.mode);
+                    processed += 1;
+                }
+                Err(e) => warn!("failed to delete account {}: {:?}", deletion.user_id, e),
+            }
+        }
+        Ok(processed)
+    }
+
+    /// `interval`ごとに`run_once`を繰り返す `tokio::spawn`して使う
+    pub async fn run(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.run_once().await {
+                warn!("account deletion job failed: {:?}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use mockall::predicate::eq;
+    use sqlx::types::time::PrimitiveDateTime;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        core::account_data::{dao_trait::MockAccountDataDaoTrait, entity::AccountDeletionEntity},
+        error::ConduitError,
+    };
+
+    fn deletion(user_id: Uuid, mode: DeletionMode) -> AccountDeletionEntity {
+        let now = PrimitiveDateTime::MIN;
+        AccountDeletionEntity {
+            user_id,
+            mode: mode.to_string(),
+            requested_at: now,
+            scheduled_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn run_once_follows_the_scheduled_mode() {
+        let anonymized = Uuid::now_v7();
+        let cascaded = Uuid::now_v7();
+        let mut dao = MockAccountDataDaoTrait::new();
+        dao.expect_get_due_deletions().returning(move |_| {
+            Ok(vec![
+                deletion(anonymized, DeletionMode::Anonymize),
+                deletion(cascaded, DeletionMode::Cascade),
+            ])
+        });
+        dao.expect_anonymize_user()
+            .with(eq(anonymized))
+            .times(1)
+            .returning(|_| Ok(()));
+        dao.expect_delete_user()
+            .with(eq(cascaded))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let job = AccountDeletionJob::new(Arc::new(dao));
+        assert_eq!(job.run_once().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn run_once_continues_after_failure() {
+        let failing = Uuid::now_v7();
+        let ok = Uuid::now_v7();
+        let mut dao = MockAccountDataDaoTrait::new();
+        dao.expect_get_due_deletions().returning(move |_| {
+            Ok(vec![
+                deletion(failing, DeletionMode::Cascade),
+                deletion(ok, DeletionMode::Cascade),
+            ])
+        });
+        dao.expect_delete_user()
+            .with(eq(failing))
+            .returning(|_| Err(ConduitError::InternalServerError));
+        dao.expect_delete_user().with(eq(ok)).returning(|_| Ok(()));
+
+        let job = AccountDeletionJob::new(Arc::new(dao));
+        assert_eq!(job.run_once().await.unwrap(), 1);
+    }
+
+    #[test]
+    fn policy_from_secrets() {
+        let policy = AccountDeletionPolicy::from_secrets(|key| match key {
+            "ACCOUNT_DELETION_MODE" => Some("cascade".to_string()),
+            "ACCOUNT_DELETION_GRACE_DAYS" => Some("30".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(policy.mode, DeletionMode::Cascade);
+        assert_eq!(policy.grace_period, Duration::from_secs(SECS_PER_DAY * 30));
+
+        let invalid = AccountDeletionPolicy::from_secrets(|key| match key {
+            "ACCOUNT_DELETION_MODE" => Some("soft".to_string()),
+            _ => None,
+        });
+        assert!(invalid.is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use uuid::Uuid;
+
+use crate::{
+    core::{
+        profiles::dao_trait::DynProfilesDao,
+        users::{
+            dao_trait::DynUsersDao,
+            entity::{Role, UserEntity},
+        },
+    },
+    error::{ConduitError, ConduitResult},
+};
+
+/// リクエストを行ったユーザー
+/// 権限はJWTのクレームを手がかりに，RequiredActorでDBから読み直す
+/// 昇格が反映されるのはトークンの再発行後になるが，降格はすぐに反映される
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Actor {
+    pub user_id: Uuid,
+    pub role: Role,
+}
+
+impl Actor {
+    pub fn new(user_id: Uuid, role: Role) -> Self {
+        Self { user_id, role }
+    }
+}
+
+/// 誰が何をできるかの判断をここに集める
+/// ハンドラーは`ensure_*`を呼び，許可されなければForbiddenを返す
+pub struct AuthorizationService;
+
+impl AuthorizationService {
+    /// 記事やコメントなど，作者が所有するものを編集・削除できるか
+    /// 作者本人と，モデレーター以上の権限を持つユーザーに許可する
+    pub fn can_modify_content(actor: &Actor, author_id: Uuid) -> bool {
+        actor.user_id == author_id || actor.role >= Role::Moderator
+    }
+
+    /// 他のユーザーの権限を変更できるか
+    /// 管理者が自分を降格して管理者がいなくなるのを防ぐため，自分の権限は変更できない
+    pub fn can_change_role(actor: &Actor, target_user_id: Uuid) -> bool {
+        actor.role == Role::Admin && actor.user_id != target_user_id
+    }
+
+    /// 作者の記事を見られるか
+    /// 非公開アカウントの記事は，作者本人と承認されたフォロワーにだけ見せる
+    pub fn can_view_content(
+        viewer_id: Option<Uuid>,
+        author: &UserEntity,
+        viewer_follows_author: bool,
+    ) -> bool {
+        !author.is_private || viewer_id == Some(author.id) || viewer_follows_author
+    }
+
+    /// 記事の作者を取得し，閲覧者が見られない記事なら存在しないものとしてNotFoundを返す
+    /// 見られる場合は，作者と閲覧者が作者をフォローしているかどうかを返す
+    pub async fn ensure_article_visible(
+        user_dao: &DynUsersDao,
+        profile_dao: &DynProfilesDao,
+        viewer_id: Option<Uuid>,
+        author_id: Uuid,
+    ) -> ConduitResult<(UserEntity, bool)> {
+        let author = user_dao.get_user_by_id(author_id).await?;
+        let following = match viewer_id {
+            Some(viewer_id) => profile_dao.is_follow(viewer_id, author.id).await?.is_some(),
+            None => false,
+        };
+        if !Self::can_view_content(viewer_id, &author, following) {
+            return Err(ConduitError::NotFound("article not found".to_string()));
+        }
+        Ok((author, following))
+    }
+
+    pub fn ensure_can_modify_content(actor: &Actor, author_id: Uuid) -> ConduitResult<()> {
+        if Self::can_modify_content(actor, author_id) {
+            return Ok(());
+        }
+        Err(ConduitError::Forbidden(
+            "you are not the author".to_string(),
+        ))
+    }
+
+    /// 管理者でなければForbiddenを返す
+    /// 対象のユーザーを探す前に呼び，存在するかどうかを管理者以外に知られないようにする
+    pub fn ensure_admin(actor: &Actor) -> ConduitResult<()> {
+        if actor.role != Role::Admin {
+            return Err(ConduitError::Forbidden(
+                "only admins can change roles".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn ensure_can_change_role(actor: &Actor, target_user_id: Uuid) -> ConduitResult<()> {
+        Self::ensure_admin(actor)?;
+        if !Self::can_change_role(actor, target_user_id) {
+            return Err(ConduitError::Forbidden(
+                "you cannot change your own role".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::core::{
+        profiles::dao_trait::MockProfilesDaoTrait, users::dao_trait::MockUsersDaoTrait,
+    };
+
+    fn actor(role: Role) -> Actor {
+        Actor::new(Uuid::now_v7(), role)
+    }
+
+    #[test]
+    fn author_can_modify_own_content() {
+        let author = actor(Role::User);
+        assert!(AuthorizationService::can_modify_content(
+            &author,
+            author.user_id
+        ));
+        assert!(AuthorizationService::ensure_can_modify_content(&author, author.user_id).is_ok());
+    }
+
+    #[test]
+    fn user_cannot_modify_others_content() {
+        let other = Uuid::now_v7();
+        let user = actor(Role::User);
+        assert!(!AuthorizationService::can_modify_content(&user, other));
+        assert!(matches!(
+            AuthorizationService::ensure_can_modify_content(&user, other),
+            Err(ConduitError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn moderator_and_admin_can_modify_others_content() {
+        let other = Uuid::now_v7();
+        assert!(AuthorizationService::can_modify_content(
+            &actor(Role::Moderator),
+            other
+        ));
+        assert!(AuthorizationService::can_modify_content(
+            &actor(Role::Admin),
+            other
+        ));
+    }
+
+    #[test]
+    fn only_admin_can_change_roles() {
+        let target = Uuid::now_v7();
+        assert!(AuthorizationService::ensure_can_change_role(&actor(Role::Admin), target).is_ok());
+        assert!(matches!(
+            AuthorizationService::ensure_can_change_role(&actor(Role::Moderator), target),
+            Err(ConduitError::Forbidden(_))
+        ));
+        assert!(matches!(
+            AuthorizationService::ensure_can_change_role(&actor(Role::User), target),
+            Err(ConduitError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn admin_cannot_change_own_role() {
+        let admin = actor(Role::Admin);
+        assert!(!AuthorizationService::can_change_role(
+            &admin,
+            admin.user_id
+        ));
+        assert!(AuthorizationService::ensure_can_change_role(&admin, admin.user_id).is_err());
+    }
+
+    fn author(is_private: bool) -> UserEntity {
+        let now = sqlx::types::time::PrimitiveDateTime::MIN;
+        UserEntity {
+            id: Uuid::now_v7(),
+            created_at: now,
+            updated_at: now,
+            username: "author".to_string(),
+            email: "author@example.com".to_string(),
+            password: String::new(),
+            bio: String::new(),
+            image: None,
+            email_verified_at: None,
+            role: Role::User.to_string(),
+            is_private,
+            display_name: None,
+            location: None,
+            pronouns: None,
+            links: vec![],
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn public_content_is_visible_to_everyone() {
+        let author = author(false);
+        assert!(AuthorizationService::can_view_content(None, &author, false));
+        assert!(AuthorizationService::can_view_content(
+            Some(Uuid::now_v7()),
+            &author,
+            false
+        ));
+    }
+
+    #[test]
+    fn private_content_is_visible_to_author_and_followers() {
+        let author = author(true);
+        assert!(!AuthorizationService::can_view_content(
+            None, &author, false
+        ));
+        assert!(!AuthorizationService::can_view_content(
+            Some(Uuid::now_v7()),
+            &author,
+            false
+        ));
+        assert!(AuthorizationService::can_view_content(
+            Some(Uuid::now_v7()),
+            &author,
+            true
+        ));
+        assert!(AuthorizationService::can_view_content(
+            Some(author.id),
+            &author,
+            false
+        ));
+    }
+
+    #[tokio::test]
+    async fn private_article_is_not_found_for_non_followers() {
+        let author = author(true);
+        let author_id = author.id;
+        let mut users = MockUsersDaoTrait::new();
+        users
+            .expect_get_user_by_id()
+            .returning(move |_| Ok(author.clone()));
+        let mut profiles = MockProfilesDaoTrait::new();
+        profiles.expect_is_follow().returning(|_, _| Ok(None));
+        let users: DynUsersDao = Arc::new(users);
+        let profiles: DynProfilesDao = Arc::new(profiles);
+
+        let res = AuthorizationService::ensure_article_visible(
+            &users,
+            &profiles,
+            Some(Uuid::now_v7()),
+            author_id,
+        )
+        .await;
+        assert!(matches!(res, Err(ConduitError::NotFound(_))));
+
+        let (author, following) = AuthorizationService::ensure_article_visible(
+            &users,
+            &profiles,
+            Some(author_id),
+            author_id,
+        )
+        .await
+        .unwrap();
+        assert_eq!(author.id, author_id);
+        assert!(!following);
+    }
+
+    #[test]
+    fn roles_are_ordered_by_privilege() {
+        assert!(Role::User < Role::Moderator);
+        assert!(Role::Moderator < Role::Admin);
+        assert_eq!("moderator".parse::<Role>(), Ok(Role::Moderator));
+        assert!("root".parse::<Role>().is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use anyhow::Context as _;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    core::favorites::dao_trait::DynFavoritesDao,
+    error::{ConduitError, ConduitResult},
+};
+
+const SECS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// いいねの設定
+#[derive(Debug, Clone)]
+pub struct FavoritePolicy {
+    // falseなら作者は自分の記事にいいねできない
+    pub allow_self_favorite: bool,
+    // 解除したいいねをfavoritesから消すまでの期間 履歴はfavorite_eventsに残る
+    pub purge_after: Duration,
+    // 解除したいいねを掃除する間隔
+    pub purge_interval: Duration,
+}
+
+impl Default for FavoritePolicy {
+    fn default() -> Self {
+        Self {
+            allow_self_favorite: true,
+            purge_after: Duration::from_secs(SECS_PER_DAY * 30),
+            purge_interval: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl FavoritePolicy {
+    /// シークレットから設定を読み込む 省略した項目はデフォルト値になる
+    ///
+    /// - `ALLOW_SELF_FAVORITE`: `false`なら自分の記事へのいいねを禁止する．デフォルトは`true`
+    /// - `FAVORITES_PURGE_AFTER_DAYS`: 解除したいいねを消すまでの日数．デフォルトは30日
+    pub fn from_secrets(get: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let mut policy = Self::default();
+        if let Some(allow) = get("ALLOW_SELF_FAVORITE") {
+            policy.allow_self_favorite = allow.parse().context("invalid ALLOW_SELF_FAVORITE")?;
+        }
+        if let Some(days) = get("FAVORITES_PURGE_AFTER_DAYS") {
+            let days: u64 = days.parse().context("invalid FAVORITES_PURGE_AFTER_DAYS")?;
+            policy.purge_after = Duration::from_secs(SECS_PER_DAY * days);
+        }
+        Ok(policy)
+    }
+
+    pub fn ensure_can_favorite(&self, user_id: Uuid, author_id: Uuid) -> ConduitResult<()> {
+        if !self.allow_self_favorite && user_id == author_id {
+            return Err(ConduitError::Forbidden(
+                "you cannot favorite your own article".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 解除してから時間の経ったいいねをfavoritesから消すジョブ
+pub struct FavoritePurgeJob {
+    favorites: DynFavoritesDao,
+    purge_after: Duration,
+}
+
+impl FavoritePurgeJob {
+    pub fn new(favorites: DynFavoritesDao, purge_after: Duration) -> Self {
+        Self {
+            favorites,
+            purge_after,
+        }
+    }
+
+    pub async fn run_once(&self) -> ConduitResult<u64> {
+        let purged = self
+            .favorites
+            .purge_deleted_favorites(self.purge_after)
+            .await?;
+        if purged > 0 {
+            info!("purged {} deleted favorites", purged);
+        }
+        Ok(purged)
+    }
+
+    /// `interval`ごとに`run_once`を繰り返す `tokio::spawn`して使う
+    pub async fn run(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.run_once().await {
+                warn!("favorite purge job failed: {:?}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::core::favorites::dao_trait::MockFavoritesDaoTrait;
+
+    #[test]
+    fn reads_self_favorite_setting() {
+        let policy = FavoritePolicy::from_secrets(|_| None).unwrap();
+        assert!(policy.allow_self_favorite);
+
+        let policy = FavoritePolicy::from_secrets(|key| {
+            (key == "ALLOW_SELF_FAVORITE").then(|| "false".to_string())
+        })
+        .unwrap();
+        assert!(!policy.allow_self_favorite);
+
+        assert!(FavoritePolicy::from_secrets(|_| Some("no".to_string())).is_err());
+    }
+
+    #[tokio::test]
+    async fn purge_job_uses_configured_retention() {
+        let mut dao = MockFavoritesDaoTrait::new();
+        dao.expect_purge_deleted_favorites()
+            .with(eq(Duration::from_secs(SECS_PER_DAY * 7)))
+            .times(1)
+            .returning(|_| Ok(3));
+        let policy = FavoritePolicy::from_secrets(|key| {
+            (key == "FAVORITES_PURGE_AFTER_DAYS").then(|| "7".to_string())
+        })
+        .unwrap();
+
+        let job = FavoritePurgeJob::new(Arc::new(dao), policy.purge_after);
+        assert_eq!(job.run_once().await.unwrap(), 3);
+    }
+
+    #[test]
+    fn forbids_self_favorite_only_when_configured() {
+        let author = Uuid::now_v7();
+        let reader = Uuid::now_v7();
+
+        let policy = FavoritePolicy::default();
+        assert!(policy.ensure_can_favorite(author, author).is_ok());
+
+        let policy = FavoritePolicy {
+            allow_self_favorite: false,
+            ..FavoritePolicy::default()
+        };
+        assert!(matches!(
+            policy.ensure_can_favorite(author, author),
+            Err(ConduitError::Forbidden(_))
+        ));
+        assert!(policy.ensure_can_favorite(reader, author).is_ok());
+    }
+}
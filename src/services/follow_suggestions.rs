@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+use crate::core::profiles::dao_trait::DynProfilesDao;
+
+/// おすすめユーザーの集計を更新する間隔のデフォルト
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// おすすめユーザーの計算に使う集計を定期的に更新するジョブ
+pub struct FollowSuggestionsRefreshJob {
+    profiles: DynProfilesDao,
+}
+
+impl FollowSuggestionsRefreshJob {
+    pub fn new(profiles: DynProfilesDao) -> Self {
+        Self { profiles }
+    }
+
+    /// `interval`ごとに集計を更新する `tokio::spawn`して使う
+    pub async fn run(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match self.profiles.refresh_follow_suggestions().await {
+                Ok(()) => info!("follow suggestions refreshed"),
+                Err(e) => warn!("failed to refresh follow suggestions: {:?}", e),
+            }
+        }
+    }
+}
@@ -1,6 +1,9 @@
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context as _};
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
-    Argon2, PasswordHash, PasswordVerifier,
+    Algorithm, Argon2, Params, PasswordHash, PasswordVerifier, Version,
 };
 
 use crate::{
@@ -8,55 +11,173 @@ use crate::{
         dto::{NewUser, PasswdHashedNewUser},
         entity::UserEntity,
     },
-    error::{ConduitResult, CustomArgon2Error},
+    error::{ConduitError, ConduitResult, CustomArgon2Error},
 };
 
-pub struct PasswordHashService;
+// 存在しないユーザーのログイン時に検証するダミーのハッシュ
+static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+
+/// パスワードのハッシュ化に使うArgon2の設定
+///
+/// デフォルトはOWASPチートシートの推奨値(Argon2id, m=19MiB, t=2, p=1)
+/// https://cheatsheetseries.owasp.org/cheatsheets/Password_Storage_Cheat_Sheet.html
+#[derive(Debug, Clone)]
+pub struct PasswordHashParams {
+    pub algorithm: Algorithm,
+    pub params: Params,
+}
+
+impl Default for PasswordHashParams {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::Argon2id,
+            params: Params::DEFAULT,
+        }
+    }
+}
+
+impl PasswordHashParams {
+    /// シークレットから設定を読み込む 指定がない項目はデフォルト値を使う
+    ///
+    /// - `PASSWORD_HASH_ALGORITHM`: `argon2id`, `argon2i`, `argon2d`
+    /// - `PASSWORD_HASH_MEMORY_KIB`: メモリコスト(KiB)
+    /// - `PASSWORD_HASH_ITERATIONS`: 反復回数
+    /// - `PASSWORD_HASH_PARALLELISM`: 並列度
+    pub fn from_secrets(get: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let algorithm = match get("PASSWORD_HASH_ALGORITHM").as_deref() {
+            None | Some("argon2id") => Algorithm::Argon2id,
+            Some("argon2i") => Algorithm::Argon2i,
+            Some("argon2d") => Algorithm::Argon2d,
+            Some(other) => bail!("unknown PASSWORD_HASH_ALGORITHM: {}", other),
+        };
+        let number = |key: &str, default: u32| -> anyhow::Result<u32> {
+            match get(key) {
+                Some(v) => v.parse().with_context(|| format!("invalid {}", key)),
+                None => Ok(default),
+            }
+        };
+        let params = Params::new(
+            number("PASSWORD_HASH_MEMORY_KIB", Params::DEFAULT_M_COST)?,
+            number("PASSWORD_HASH_ITERATIONS", Params::DEFAULT_T_COST)?,
+            number("PASSWORD_HASH_PARALLELISM", Params::DEFAULT_P_COST)?,
+            None,
+        )
+        .map_err(|e| anyhow::anyhow!("invalid password hash params: {}", e))?;
+        Ok(Self { algorithm, params })
+    }
+}
+
+pub struct PasswordHashService {
+    params: PasswordHashParams,
+}
+
+impl Default for PasswordHashService {
+    fn default() -> Self {
+        Self::new(PasswordHashParams::default())
+    }
+}
 
 impl PasswordHashService {
-    /// 成功した場合は何も返さない 失敗した場合はエラーを返す
+    pub fn new(params: PasswordHashParams) -> Self {
+        Self { params }
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(
+            self.params.algorithm,
+            Version::V0x13,
+            self.params.params.clone(),
+        )
+    }
+
+    /// 成功した場合は，ハッシュを現在の設定で作り直すべきかどうかを返す 失敗した場合はエラーを返す
+    /// パスワードが一致しない場合は`InvalidLogin`を返す
+    ///
+    /// 検証にはハッシュに記録されたパラメータが使われるので，古い設定のハッシュもそのまま検証できる
     pub fn verify_password(
+        &self,
         stored_hashed_password: &str,
         attempt_password: &str,
-    ) -> ConduitResult<()> {
+    ) -> ConduitResult<bool> {
         let expected = PasswordHash::new(stored_hashed_password).map_err(CustomArgon2Error)?;
-        let argon2 = Argon2::default();
-        argon2
+        self.argon2()
             .verify_password(attempt_password.as_bytes(), &expected)
-            .map_err(CustomArgon2Error)?;
-        Ok(())
+            .map_err(|e| match e {
+                argon2::password_hash::Error::Password => ConduitError::InvalidLogin,
+                e => CustomArgon2Error(e).into(),
+            })?;
+        Ok(self.needs_rehash(&expected))
+    }
+
+    /// ハッシュのアルゴリズムやパラメータが現在の設定と異なるかどうか
+    fn needs_rehash(&self, hash: &PasswordHash) -> bool {
+        let Ok(algorithm) = Algorithm::try_from(hash.algorithm) else {
+            return true;
+        };
+        let Ok(params) = Params::try_from(hash) else {
+            return true;
+        };
+        let current = &self.params.params;
+        algorithm != self.params.algorithm
+            || hash.version != Some(Version::V0x13.into())
+            || params.m_cost() != current.m_cost()
+            || params.t_cost() != current.t_cost()
+            || params.p_cost() != current.p_cost()
+    }
+
+    /// ダミーのハッシュに対して検証を行い，常に`InvalidLogin`を返す
+    ///
+    /// 存在しないメールアドレスでのログインでも実在するユーザーと同じだけ時間をかけ，
+    /// 応答時間からアカウントの有無を推測されないようにする
+    pub fn verify_dummy_password(&self, attempt_password: &str) -> ConduitResult<()> {
+        let dummy_hash = self.dummy_hash()?;
+        self.verify_password(dummy_hash, attempt_password)?;
+        Err(ConduitError::InvalidLogin)
+    }
+
+    /// ダミーのハッシュを作っておく 起動時に呼ぶ
+    ///
+    /// 最初に存在しないメールアドレスでログインされたときにハッシュを作ると，
+    /// その分だけ応答が遅くなり，アカウントの有無を推測する手がかりになる
+    pub fn prepare_dummy_hash(&self) -> ConduitResult<()> {
+        self.dummy_hash().map(|_| ())
+    }
+
+    fn dummy_hash(&self) -> ConduitResult<&'static str> {
+        if let Some(hash) = DUMMY_HASH.get() {
+            return Ok(hash);
+        }
+        let hash = self.hash_password("dummy password for timing equalization")?;
+        Ok(DUMMY_HASH.get_or_init(|| hash))
     }
 
-    pub fn hash_password_newuser(req: NewUser) -> ConduitResult<PasswdHashedNewUser> {
-        let hashed_pass = Self::hash_password(&req.password.unwrap()).map(|password| {
-            PasswdHashedNewUser::new(req.username.unwrap(), req.email.unwrap(), password)
-        })?;
+    pub fn hash_password_newuser(&self, req: NewUser) -> ConduitResult<PasswdHashedNewUser> {
+        let hashed_pass = self
+            .hash_password(req.password.unwrap().expose())
+            .map(|password| {
+                PasswdHashedNewUser::new(req.username.unwrap(), req.email.unwrap(), password)
+            })?;
         Ok(hashed_pass)
     }
 
-    pub fn hash_password_user(user: UserEntity) -> ConduitResult<UserEntity> {
-        let hashed_pass = Self::hash_password(&user.password).map(|password| UserEntity {
-            email: user.email,
-            username: user.username,
-            password,
-            bio: user.bio,
-            image: user.image,
-            ..user
-        })?;
+    pub fn hash_password_user(&self, user: UserEntity) -> ConduitResult<UserEntity> {
+        let hashed_pass = self
+            .hash_password(&user.password)
+            .map(|password| UserEntity {
+                email: user.email,
+                username: user.username,
+                password,
+                bio: user.bio,
+                image: user.image,
+                ..user
+            })?;
         Ok(hashed_pass)
     }
 
-    fn hash_password(password: &str) -> ConduitResult<String> {
+    pub fn hash_password(&self, password: &str) -> ConduitResult<String> {
         let salt = SaltString::generate(&mut OsRng);
-        // OWASPチートシートにより決定
-        // https://cheatsheetseries.owasp.org/cheatsheets/Password_Storage_Cheat_Sheet.html
-        // let argon2 = Argon2::new(
-        //     Algorithm::Argon2id,
-        //     argon2::Version::V0x13,
-        //     Params::new(19000, 2, 1, None).unwrap(),
-        // );
-        let argon2 = Argon2::default();
-        let hash = argon2
+        let hash = self
+            .argon2()
             .hash_password(password.as_bytes(), &salt)
             .map_err(CustomArgon2Error)?;
         Ok(hash.to_string())
@@ -68,23 +189,91 @@ mod tests {
 
     use super::*;
 
+    // テストを速くするため，デフォルトより軽い設定を使う
+    fn light_params() -> PasswordHashParams {
+        PasswordHashParams {
+            algorithm: Algorithm::Argon2id,
+            params: Params::new(1024, 1, 1, None).unwrap(),
+        }
+    }
+
     #[test]
     fn hash_verify_password() {
+        let service = PasswordHashService::default();
         let password = "password";
-        let hashed = PasswordHashService::hash_password(password).unwrap();
+        let hashed = service.hash_password(password).unwrap();
         println!("hashed: {:?}, from: {:?}", hashed, &password);
-        PasswordHashService::verify_password(&hashed, password).unwrap();
+        let needs_rehash = service.verify_password(&hashed, password).unwrap();
+        assert!(!needs_rehash);
     }
 
     #[test]
     fn hash_newuser_verify_password() {
+        let service = PasswordHashService::default();
         let new_user = NewUser {
             username: Some("username".to_string()),
             email: Some("email".to_string()),
-            password: Some("password".to_string()),
+            password: Some("password".to_string().into()),
         };
-        let hashed = PasswordHashService::hash_password_newuser(new_user).unwrap();
+        let hashed = service.hash_password_newuser(new_user).unwrap();
         println!("hashed: {:?}, from: {:?}", hashed.password, "password");
-        PasswordHashService::verify_password(&hashed.password, "password").unwrap();
+        service
+            .verify_password(&hashed.password, "password")
+            .unwrap();
+    }
+
+    #[test]
+    fn wrong_password_is_invalid_login() {
+        let service = PasswordHashService::default();
+        let hashed = service.hash_password("password").unwrap();
+        let result = service.verify_password(&hashed, "wrong");
+        assert!(matches!(result, Err(ConduitError::InvalidLogin)));
+    }
+
+    #[test]
+    fn dummy_password_is_always_invalid_login() {
+        let service = PasswordHashService::default();
+        for attempt in ["password", "dummy password for timing equalization"] {
+            let result = service.verify_dummy_password(attempt);
+            assert!(matches!(result, Err(ConduitError::InvalidLogin)));
+        }
+    }
+
+    #[test]
+    fn outdated_hash_needs_rehash() {
+        let old = PasswordHashService::new(light_params());
+        let hashed = old.hash_password("password").unwrap();
+
+        // 設定を変えた後でも古いハッシュで検証でき，作り直しが必要と判定される
+        let current = PasswordHashService::default();
+        assert!(current.verify_password(&hashed, "password").unwrap());
+        assert!(!old.verify_password(&hashed, "password").unwrap());
+
+        let argon2i = PasswordHashService::new(PasswordHashParams {
+            algorithm: Algorithm::Argon2i,
+            ..light_params()
+        });
+        assert!(argon2i.verify_password(&hashed, "password").unwrap());
+    }
+
+    #[test]
+    fn params_from_secrets() {
+        let params = PasswordHashParams::from_secrets(|key| match key {
+            "PASSWORD_HASH_ALGORITHM" => Some("argon2i".to_string()),
+            "PASSWORD_HASH_MEMORY_KIB" => Some("65536".to_string()),
+            "PASSWORD_HASH_ITERATIONS" => Some("3".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(params.algorithm, Algorithm::Argon2i);
+        assert_eq!(params.params.m_cost(), 65536);
+        assert_eq!(params.params.t_cost(), 3);
+        assert_eq!(params.params.p_cost(), Params::DEFAULT_P_COST);
+
+        assert!(PasswordHashParams::from_secrets(|key| match key {
+            "PASSWORD_HASH_ALGORITHM" => Some("bcrypt".to_string()),
+            _ => None,
+        })
+        .is_err());
     }
 }
@@ -0,0 +1,272 @@
+use std::io::Cursor;
+
+use anyhow::Context as _;
+use image::{
+    codecs::{jpeg::JpegEncoder, png::PngEncoder},
+    imageops::FilterType,
+    DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits,
+};
+
+use crate::error::{ConduitError, ConduitResult};
+
+const MIB: usize = 1024 * 1024;
+const JPEG_QUALITY: u8 = 85;
+
+/// 作る画像のサイズ 長辺がこの値を超える場合は縮小する
+/// originalも縮小するのは，巨大な画像をそのまま配信しないため
+pub const VARIANTS: [(&str, u32); 3] = [("original", 2048), ("medium", 800), ("thumbnail", 200)];
+
+/// アップロードの制限
+#[derive(Debug, Clone)]
+pub struct UploadPolicy {
+    pub max_bytes: usize,
+    // 画像の幅と高さの上限 小さなファイルで巨大な画像を展開させる攻撃を防ぐ
+    pub max_dimension: u32,
+}
+
+impl Default for UploadPolicy {
+    fn default() -> Self {
+        Self {
+            max_bytes: 5 * MIB,
+            max_dimension: 8000,
+        }
+    }
+}
+
+impl UploadPolicy {
+    /// シークレットから設定を読み込む 省略した項目はデフォルト値になる
+    ///
+    /// - `UPLOAD_MAX_BYTES`: ファイルサイズの上限．デフォルトは5MiB
+    /// - `UPLOAD_MAX_DIMENSION`: 幅と高さの上限．デフォルトは8000px
+    pub fn from_secrets(get: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let mut policy = Self::default();
+        if let Some(max_bytes) = get("UPLOAD_MAX_BYTES") {
+            policy.max_bytes = max_bytes.parse().context("invalid UPLOAD_MAX_BYTES")?;
+        }
+        if let Some(max_dimension) = get("UPLOAD_MAX_DIMENSION") {
+            policy.max_dimension = max_dimension
+                .parse()
+                .context("invalid UPLOAD_MAX_DIMENSION")?;
+        }
+        Ok(policy)
+    }
+}
+
+/// 先頭のバイト列から画像の形式を判定する
+/// クライアントが送ってきたContent-Typeや拡張子は信用しない
+pub fn detect_format(bytes: &[u8]) -> Option<ImageFormat> {
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some(ImageFormat::Jpeg)
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(ImageFormat::Png)
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some(ImageFormat::Gif)
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some(ImageFormat::WebP)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageVariant {
+    pub name: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub content_type: &'static str,
+    pub extension: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+pub struct ImageUploadService {
+    policy: UploadPolicy,
+}
+
+impl ImageUploadService {
+    pub fn new(policy: UploadPolicy) -> Self {
+        Self { policy }
+    }
+
+    /// 画像を検証し，`VARIANTS`の各サイズに変換する
+    ///
+    /// 一度デコードしてから書き出し直すので，EXIFなどのメタデータは残らない
+    /// 向きの情報だけは先に画素へ反映しておく
+    /// JPEGはJPEGのまま，それ以外は透過を残せるようPNGで書き出す
+    pub fn process(&self, bytes: &[u8]) -> ConduitResult<Vec<ImageVariant>> {
+        if bytes.len() > self.policy.max_bytes {
+            return Err(ConduitError::PayloadTooLarge(format!(
+                "image must be at most {} bytes",
+                self.policy.max_bytes
+            )));
+        }
+        let Some(format) = detect_format(bytes) else {
+            return Err(ConduitError::UnsupportedMediaType(
+                "only jpeg, png, gif and webp images are supported".to_string(),
+            ));
+        };
+
+        let image = self.decode(bytes, format)?;
+        VARIANTS
+            .iter()
+            .map(|&(name, max_side)| {
+                // 小さい画像は拡大しない
+                let resized = if image.width() > max_side || image.height() > max_side {
+                    image.resize(max_side, max_side, FilterType::Lanczos3)
+                } else {
+                    image.clone()
+                };
+                encode(name, &resized, format)
+            })
+            .collect()
+    }
+
+    fn decode(&self, bytes: &[u8], format: ImageFormat) -> ConduitResult<DynamicImage> {
+        let mut limits = Limits::default();
+        limits.max_image_width = Some(self.policy.max_dimension);
+        limits.max_image_height = Some(self.policy.max_dimension);
+
+        let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+        reader.limits(limits);
+        let invalid =
+            |e: image::ImageError| ConduitError::BadRequest(format!("invalid image: {}", e));
+        let mut decoder = reader.into_decoder().map_err(invalid)?;
+        let orientation = decoder.orientation().map_err(invalid)?;
+        let mut image = DynamicImage::from_decoder(decoder).map_err(invalid)?;
+        image.apply_orientation(orientation);
+        Ok(image)
+    }
+}
+
+fn encode(
+    name: &'static str,
+    image: &DynamicImage,
+    format: ImageFormat,
+) -> ConduitResult<ImageVariant> {
+    let mut bytes = Vec::new();
+    let (content_type, extension) = if format == ImageFormat::Jpeg {
+        let encoder = JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY);
+        image
+            .to_rgb8()
+            .write_with_encoder(encoder)
+            .context("failed to encode jpeg")?;
+        ("image/jpeg", "jpg")
+    } else {
+        let encoder = PngEncoder::new(&mut bytes);
+        image
+            .to_rgba8()
+            .write_with_encoder(encoder)
+            .context("failed to encode png")?;
+        ("image/png", "png")
+    };
+    Ok(ImageVariant {
+        name,
+        width: image.width(),
+        height: image.height(),
+        content_type,
+        extension,
+        bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use super::*;
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::from_pixel(width, height, Rgb([200, 100, 50]));
+        let mut bytes = Vec::new();
+        image
+            .write_with_encoder(JpegEncoder::new(&mut bytes))
+            .unwrap();
+        bytes
+    }
+
+    // JPEGのSOIの直後に，Orientationだけを持つEXIFのAPP1セグメントを差し込む
+    fn with_exif(jpeg: &[u8]) -> Vec<u8> {
+        // ヘッダー，エントリー数1，Orientation = 6 (時計回りに90度)，次のIFDはない
+        let tiff: &[u8] = &[
+            b'M', b'M', 0, 42, 0, 0, 0, 8, 0, 1, 0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0, 0, 0, 0,
+            0,
+        ];
+        let mut app1 = b"Exif\0\0".to_vec();
+        app1.extend_from_slice(tiff);
+        let len = (app1.len() + 2) as u16;
+        let mut bytes = jpeg[..2].to_vec();
+        bytes.extend_from_slice(&[0xFF, 0xE1]);
+        bytes.extend_from_slice(&len.to_be_bytes());
+        bytes.extend_from_slice(&app1);
+        bytes.extend_from_slice(&jpeg[2..]);
+        bytes
+    }
+
+    #[test]
+    fn detects_format_by_magic_bytes() {
+        assert_eq!(detect_format(&jpeg(1, 1)), Some(ImageFormat::Jpeg));
+        assert_eq!(detect_format(b"GIF89a..."), Some(ImageFormat::Gif));
+        assert_eq!(
+            detect_format(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some(ImageFormat::WebP)
+        );
+        assert_eq!(detect_format(b"<svg xmlns=..."), None);
+    }
+
+    #[test]
+    fn rejects_unsupported_and_oversized_files() {
+        let service = ImageUploadService::new(UploadPolicy::default());
+        assert!(matches!(
+            service.process(b"<svg></svg>"),
+            Err(ConduitError::UnsupportedMediaType(_))
+        ));
+
+        let service = ImageUploadService::new(UploadPolicy {
+            max_bytes: 10,
+            ..UploadPolicy::default()
+        });
+        assert!(matches!(
+            service.process(&jpeg(10, 10)),
+            Err(ConduitError::PayloadTooLarge(_))
+        ));
+
+        let service = ImageUploadService::new(UploadPolicy {
+            max_dimension: 100,
+            ..UploadPolicy::default()
+        });
+        assert!(matches!(
+            service.process(&jpeg(200, 10)),
+            Err(ConduitError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn generates_resized_variants() {
+        let service = ImageUploadService::new(UploadPolicy::default());
+        let variants = service.process(&jpeg(1600, 400)).unwrap();
+        let sizes = variants
+            .iter()
+            .map(|v| (v.name, v.width, v.height))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sizes,
+            vec![
+                ("original", 1600, 400),
+                ("medium", 800, 200),
+                ("thumbnail", 200, 50)
+            ]
+        );
+        assert!(variants.iter().all(|v| v.content_type == "image/jpeg"));
+    }
+
+    #[test]
+    fn strips_exif_and_applies_orientation() {
+        let bytes = with_exif(&jpeg(40, 20));
+        assert!(bytes.windows(4).any(|w| w == b"Exif"));
+
+        let service = ImageUploadService::new(UploadPolicy::default());
+        let original = service.process(&bytes).unwrap().remove(0);
+        assert!(!original.bytes.windows(4).any(|w| w == b"Exif"));
+        // 向きを反映して縦長になる
+        assert_eq!((original.width, original.height), (20, 40));
+    }
+}
@@ -1,15 +1,241 @@
-use std::time;
+use std::{collections::HashMap, str::FromStr, time};
 
+use anyhow::{bail, Context as _};
+use jsonwebtoken::{
+    errors::ErrorKind,
+    jwk::{AlgorithmParameters, JwkSet},
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::ArcState;
+use crate::{core::users::entity::Role, ArcState};
 
 const DEFAULT_SESSION_LEN: time::Duration = time::Duration::from_secs(60 * 60 * 2);
+const DEFAULT_ISSUER: &str = "conduit";
+const DEFAULT_AUDIENCE: &str = "conduit";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub exp: i64,
     pub user_id: Uuid,
+    // 発行者と対象を追加する前に発行されたトークンには含まれない
+    // kidを持たないトークンでのみ省略を許す
+    #[serde(default)]
+    pub iss: String,
+    #[serde(default)]
+    pub aud: String,
+    // 権限を追加する前に発行されたトークンには含まれないので，一般ユーザーとして扱う
+    #[serde(default)]
+    pub role: Role,
+}
+
+/// JWTの署名鍵と検証鍵の集合
+///
+/// 署名は1つの鍵で行い，ヘッダーの`kid`で検証鍵を選ぶ
+/// 鍵をローテーションするときは，新しい鍵で署名しつつ古い鍵もしばらく検証鍵として残しておく
+#[derive(Clone)]
+pub struct JwtKeys {
+    algorithm: Algorithm,
+    signing_kid: Option<String>,
+    encoding_key: EncodingKey,
+    verification_keys: HashMap<String, (Algorithm, DecodingKey)>,
+    // kidを持たないトークン(HS256で発行されたもの)を検証するための鍵
+    legacy_key: Option<DecodingKey>,
+    // /.well-known/jwks.json で公開する公開鍵の一覧
+    jwks: JwkSet,
+    issuer: String,
+    audience: String,
+}
+
+impl JwtKeys {
+    /// 共通鍵(HS256)で署名・検証する
+    /// 他のサービスからは検証できないので，JWKSは空になる
+    pub fn from_secret(secret: &str) -> Self {
+        Self {
+            algorithm: Algorithm::HS256,
+            signing_kid: None,
+            encoding_key: EncodingKey::from_secret(secret.as_ref()),
+            verification_keys: HashMap::new(),
+            legacy_key: Some(DecodingKey::from_secret(secret.as_ref())),
+            jwks: JwkSet { keys: vec![] },
+            issuer: DEFAULT_ISSUER.to_string(),
+            audience: DEFAULT_AUDIENCE.to_string(),
+        }
+    }
+
+    /// 非対称鍵(RS256/EdDSAなど)で署名する
+    /// `jwks`には検証に使う公開鍵をすべて渡す．署名鍵の公開鍵も`signing_kid`で含まれている必要がある
+    pub fn from_private_pem(
+        algorithm: Algorithm,
+        signing_kid: &str,
+        private_pem: &str,
+        jwks: JwkSet,
+    ) -> anyhow::Result<Self> {
+        let encoding_key = match algorithm {
+            Algorithm::RS256
+            | Algorithm::RS384
+            | Algorithm::RS512
+            | Algorithm::PS256
+            | Algorithm::PS384
+            | Algorithm::PS512 => EncodingKey::from_rsa_pem(private_pem.as_bytes()),
+            Algorithm::EdDSA => EncodingKey::from_ed_pem(private_pem.as_bytes()),
+            _ => bail!("unsupported jwt signing algorithm: {:?}", algorithm),
+        }
+        .context("invalid jwt signing key")?;
+
+        let mut verification_keys = HashMap::new();
+        for jwk in &jwks.keys {
+            let Some(kid) = jwk.common.key_id.clone() else {
+                bail!("jwks contains a key without kid");
+            };
+            // 共通鍵を公開してしまわないようにする
+            let key_algorithm = match &jwk.algorithm {
+                AlgorithmParameters::RSA(_) => Algorithm::RS256,
+                AlgorithmParameters::OctetKeyPair(_) => Algorithm::EdDSA,
+                AlgorithmParameters::EllipticCurve(_) => Algorithm::ES256,
+                AlgorithmParameters::OctetKey(_) => {
+                    bail!("jwks must not contain symmetric keys: {}", kid)
+                }
+            };
+            // algが明示されていればそちらを優先する
+            let key_algorithm = match jwk.common.key_algorithm {
+                Some(alg) => Algorithm::from_str(&alg.to_string())
+                    .with_context(|| format!("unsupported jwk alg: {}", kid))?,
+                None => key_algorithm,
+            };
+            let decoding_key =
+                DecodingKey::from_jwk(jwk).with_context(|| format!("invalid jwk: {}", kid))?;
+            verification_keys.insert(kid, (key_algorithm, decoding_key));
+        }
+
+        match verification_keys.get(signing_kid) {
+            Some((key_algorithm, _)) if *key_algorithm == algorithm => {}
+            Some(_) => bail!("jwks key {} does not match {:?}", signing_kid, algorithm),
+            None => bail!("jwks does not contain the signing key: {}", signing_kid),
+        }
+
+        Ok(Self {
+            algorithm,
+            signing_kid: Some(signing_kid.to_string()),
+            encoding_key,
+            verification_keys,
+            legacy_key: None,
+            jwks,
+            issuer: DEFAULT_ISSUER.to_string(),
+            audience: DEFAULT_AUDIENCE.to_string(),
+        })
+    }
+
+    /// kidを持たない既存のHS256トークンも受け付けるようにする
+    /// 非対称鍵へ移行した直後に，全員がログアウトされないようにするため
+    pub fn with_legacy_secret(self, secret: &str) -> Self {
+        Self {
+            legacy_key: Some(DecodingKey::from_secret(secret.as_ref())),
+            ..self
+        }
+    }
+
+    pub fn with_issuer(self, issuer: &str) -> Self {
+        Self {
+            issuer: issuer.to_string(),
+            ..self
+        }
+    }
+
+    pub fn with_audience(self, audience: &str) -> Self {
+        Self {
+            audience: audience.to_string(),
+            ..self
+        }
+    }
+
+    /// シークレットから鍵を読み込む
+    ///
+    /// - `JWT_ALGORITHM`: `HS256`(デフォルト), `RS256`, `EdDSA`など
+    /// - `JWT_SECRET`: HS256の共通鍵．非対称鍵の場合は既存トークンの検証にのみ使う
+    /// - `JWT_SIGNING_KID`, `JWT_SIGNING_KEY`: 非対称鍵の場合の署名鍵のkidとPEM
+    /// - `JWT_JWKS`: 非対称鍵の場合の検証鍵一覧(JWKS形式のJSON)
+    /// - `JWT_ISSUER`, `JWT_AUDIENCE`: 省略時は`conduit`
+    pub fn from_secrets(get: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let algorithm = match get("JWT_ALGORITHM") {
+            Some(alg) => Algorithm::from_str(&alg).context("invalid JWT_ALGORITHM")?,
+            None => Algorithm::HS256,
+        };
+        let secret = get("JWT_SECRET");
+
+        let keys = if algorithm == Algorithm::HS256 {
+            let secret = secret.context("JWT_SECRET is required for HS256")?;
+            Self::from_secret(&secret)
+        } else {
+            let kid = get("JWT_SIGNING_KID").context("JWT_SIGNING_KID is required")?;
+            let pem = get("JWT_SIGNING_KEY").context("JWT_SIGNING_KEY is required")?;
+            let jwks = get("JWT_JWKS").context("JWT_JWKS is required")?;
+            let jwks: JwkSet = serde_json::from_str(&jwks).context("invalid JWT_JWKS")?;
+            let keys = Self::from_private_pem(algorithm, &kid, &pem, jwks)?;
+            match secret {
+                Some(secret) => keys.with_legacy_secret(&secret),
+                None => keys,
+            }
+        };
+
+        let keys = match get("JWT_ISSUER") {
+            Some(issuer) => keys.with_issuer(&issuer),
+            None => keys,
+        };
+        let keys = match get("JWT_AUDIENCE") {
+            Some(audience) => keys.with_audience(&audience),
+            None => keys,
+        };
+        Ok(keys)
+    }
+
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+
+    pub(crate) fn encode(&self, user_id: Uuid, role: Role) -> String {
+        let now = chrono::Utc::now();
+        let exp = now + DEFAULT_SESSION_LEN;
+        let claims = Claims {
+            exp: exp.timestamp(),
+            user_id,
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+            role,
+        };
+        let header = Header {
+            kid: self.signing_kid.clone(),
+            ..Header::new(self.algorithm)
+        };
+        jsonwebtoken::encode(&header, &claims, &self.encoding_key).unwrap()
+    }
+
+    pub(crate) fn decode(&self, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+        let header = jsonwebtoken::decode_header(token)?;
+        // kidがあればそれに対応する鍵で，なければ旧来の共通鍵で検証する
+        let (algorithm, key, required_claims) = match &header.kid {
+            Some(kid) => {
+                let Some((algorithm, key)) = self.verification_keys.get(kid) else {
+                    return Err(ErrorKind::InvalidToken.into());
+                };
+                (*algorithm, key, &["exp", "iss", "aud"][..])
+            }
+            None => {
+                let Some(key) = &self.legacy_key else {
+                    return Err(ErrorKind::InvalidToken.into());
+                };
+                // iss/audを持たない旧来のトークンも受け付ける．含まれていれば検証する
+                (Algorithm::HS256, key, &["exp"][..])
+            }
+        };
+
+        let mut validation = Validation::new(algorithm);
+        validation.set_required_spec_claims(required_claims);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        jsonwebtoken::decode::<Claims>(token, key, &validation).map(|data| data.claims)
+    }
 }
 
 pub struct JwtService {
@@ -21,31 +247,228 @@ impl JwtService {
         Self { state }
     }
 
-    pub(crate) fn to_token(&self, user_id: Uuid) -> String {
-        let now = chrono::Utc::now();
-        let exp = now + DEFAULT_SESSION_LEN;
-        let claims = Claims {
-            exp: exp.timestamp(),
+    pub(crate) fn to_token(&self, user_id: Uuid, role: Role) -> String {
+        self.state.jwt_keys.encode(user_id, role)
+    }
+
+    pub(crate) fn get_claim_from_token(
+        &self,
+        token: &str,
+    ) -> Result<Claims, jsonwebtoken::errors::Error> {
+        self.state.jwt_keys.decode(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSA_2024_10: &str = include_str!("../../tests/keys/rsa_2024_10.pem");
+    const RSA_2024_11: &str = include_str!("../../tests/keys/rsa_2024_11.pem");
+    const ED_2024_11: &str = include_str!("../../tests/keys/ed_2024_11.pem");
+    const JWKS: &str = include_str!("../../tests/keys/jwks.json");
+
+    fn jwks() -> JwkSet {
+        serde_json::from_str(JWKS).unwrap()
+    }
+
+    #[test]
+    fn hs256_encode_decode() {
+        let keys = JwtKeys::from_secret("secret");
+        let user_id = Uuid::now_v7();
+        let token = keys.encode(user_id, Role::User);
+        let claims = keys.decode(&token).unwrap();
+        assert_eq!(claims.user_id, user_id);
+        assert_eq!(claims.iss, DEFAULT_ISSUER);
+        assert!(keys.jwks().keys.is_empty());
+    }
+
+    #[test]
+    fn rs256_encode_decode_with_kid() {
+        let keys = JwtKeys::from_private_pem(Algorithm::RS256, "rsa-2024-10", RSA_2024_10, jwks())
+            .unwrap();
+        let user_id = Uuid::now_v7();
+        let token = keys.encode(user_id, Role::User);
+
+        let header = jsonwebtoken::decode_header(&token).unwrap();
+        assert_eq!(header.kid.as_deref(), Some("rsa-2024-10"));
+        assert_eq!(header.alg, Algorithm::RS256);
+        assert_eq!(keys.decode(&token).unwrap().user_id, user_id);
+    }
+
+    #[test]
+    fn eddsa_encode_decode_with_kid() {
+        let keys =
+            JwtKeys::from_private_pem(Algorithm::EdDSA, "ed-2024-11", ED_2024_11, jwks()).unwrap();
+        let user_id = Uuid::now_v7();
+        let token = keys.encode(user_id, Role::User);
+        assert_eq!(keys.decode(&token).unwrap().user_id, user_id);
+    }
+
+    // ローテーション後も古い鍵で署名されたトークンを検証できる
+    #[test]
+    fn rotated_key_still_verifies_old_tokens() {
+        let old_keys =
+            JwtKeys::from_private_pem(Algorithm::RS256, "rsa-2024-10", RSA_2024_10, jwks())
+                .unwrap();
+        let new_keys =
+            JwtKeys::from_private_pem(Algorithm::RS256, "rsa-2024-11", RSA_2024_11, jwks())
+                .unwrap();
+        let user_id = Uuid::now_v7();
+        let old_token = old_keys.encode(user_id, Role::User);
+        assert_eq!(new_keys.decode(&old_token).unwrap().user_id, user_id);
+
+        // 古い鍵をJWKSから外すと検証できなくなる
+        let mut retired = jwks();
+        retired
+            .keys
+            .retain(|k| k.common.key_id.as_deref() != Some("rsa-2024-10"));
+        let new_keys =
+            JwtKeys::from_private_pem(Algorithm::RS256, "rsa-2024-11", RSA_2024_11, retired)
+                .unwrap();
+        assert!(new_keys.decode(&old_token).is_err());
+    }
+
+    #[test]
+    fn legacy_hs256_token_is_accepted_after_migration() {
+        let legacy = JwtKeys::from_secret("secret");
+        let user_id = Uuid::now_v7();
+        let token = legacy.encode(user_id, Role::User);
+
+        let keys = JwtKeys::from_private_pem(Algorithm::RS256, "rsa-2024-11", RSA_2024_11, jwks())
+            .unwrap();
+        assert!(keys.decode(&token).is_err());
+        let keys = keys.with_legacy_secret("secret");
+        assert_eq!(keys.decode(&token).unwrap().user_id, user_id);
+    }
+
+    #[test]
+    fn signing_kid_must_be_in_jwks() {
+        let result = JwtKeys::from_private_pem(Algorithm::RS256, "unknown", RSA_2024_10, jwks());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn issuer_and_audience_are_validated() {
+        let keys = JwtKeys::from_secret("secret");
+        let token = keys.encode(Uuid::now_v7(), Role::User);
+
+        let other_issuer = JwtKeys::from_secret("secret").with_issuer("other");
+        let err = other_issuer.decode(&token).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidIssuer);
+
+        let other_audience = JwtKeys::from_secret("secret").with_audience("other");
+        let err = other_audience.decode(&token).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidAudience);
+    }
+
+    #[test]
+    fn from_secrets_reads_asymmetric_config() {
+        let get = |key: &str| match key {
+            "JWT_ALGORITHM" => Some("RS256".to_string()),
+            "JWT_SIGNING_KID" => Some("rsa-2024-11".to_string()),
+            "JWT_SIGNING_KEY" => Some(RSA_2024_11.to_string()),
+            "JWT_JWKS" => Some(JWKS.to_string()),
+            "JWT_ISSUER" => Some("https://conduit.example.com".to_string()),
+            _ => None,
+        };
+        let keys = JwtKeys::from_secrets(get).unwrap();
+        let token = keys.encode(Uuid::now_v7(), Role::User);
+        let claims = keys.decode(&token).unwrap();
+        assert_eq!(claims.iss, "https://conduit.example.com");
+        assert_eq!(keys.jwks().keys.len(), 3);
+    }
+
+    #[test]
+    fn role_is_carried_in_claims() {
+        let keys = JwtKeys::from_secret("secret");
+        let token = keys.encode(Uuid::now_v7(), Role::Moderator);
+        assert_eq!(keys.decode(&token).unwrap().role, Role::Moderator);
+    }
+
+    // 権限を持たない古いトークンは一般ユーザーとして扱う
+    #[test]
+    fn token_without_role_is_a_user() {
+        #[derive(Serialize)]
+        struct LegacyClaims {
+            exp: i64,
+            user_id: Uuid,
+            iss: String,
+            aud: String,
+        }
+        let claims = LegacyClaims {
+            exp: (chrono::Utc::now() + DEFAULT_SESSION_LEN).timestamp(),
+            user_id: Uuid::now_v7(),
+            iss: DEFAULT_ISSUER.to_string(),
+            aud: DEFAULT_AUDIENCE.to_string(),
+        };
+        let token = jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+        let keys = JwtKeys::from_secret("secret");
+        assert_eq!(keys.decode(&token).unwrap().role, Role::User);
+    }
+
+    // iss/audを追加する前に発行されたトークンもそのまま使える
+    #[test]
+    fn baseline_token_without_issuer_and_audience_is_accepted() {
+        #[derive(Serialize)]
+        struct BaselineClaims {
+            exp: i64,
+            user_id: Uuid,
+        }
+        let user_id = Uuid::now_v7();
+        let claims = BaselineClaims {
+            exp: (chrono::Utc::now() + DEFAULT_SESSION_LEN).timestamp(),
             user_id,
         };
         let token = jsonwebtoken::encode(
-            &jsonwebtoken::Header::default(),
+            &Header::default(),
             &claims,
-            &jsonwebtoken::EncodingKey::from_secret(self.state.jwt_secret.as_ref()),
+            &EncodingKey::from_secret(b"secret"),
         )
         .unwrap();
-        token
+
+        let keys = JwtKeys::from_secret("secret");
+        let decoded = keys.decode(&token).unwrap();
+        assert_eq!(decoded.user_id, user_id);
+        assert_eq!(decoded.role, Role::User);
+
+        let keys = JwtKeys::from_private_pem(Algorithm::RS256, "rsa-2024-11", RSA_2024_11, jwks())
+            .unwrap()
+            .with_legacy_secret("secret");
+        assert_eq!(keys.decode(&token).unwrap().user_id, user_id);
     }
 
-    pub(crate) fn get_claim_from_token(
-        &self,
-        token: &str,
-    ) -> Result<Claims, jsonwebtoken::errors::Error> {
-        let token = jsonwebtoken::decode::<Claims>(
-            token,
-            &jsonwebtoken::DecodingKey::from_secret(self.state.jwt_secret.as_ref()),
-            &jsonwebtoken::Validation::default(),
-        );
-        token.map(|data| data.claims)
+    // kid付きのトークンではiss/audを省略できない
+    #[test]
+    fn token_with_kid_requires_issuer_and_audience() {
+        #[derive(Serialize)]
+        struct BaselineClaims {
+            exp: i64,
+            user_id: Uuid,
+        }
+        let claims = BaselineClaims {
+            exp: (chrono::Utc::now() + DEFAULT_SESSION_LEN).timestamp(),
+            user_id: Uuid::now_v7(),
+        };
+        let header = Header {
+            kid: Some("rsa-2024-11".to_string()),
+            ..Header::new(Algorithm::RS256)
+        };
+        let token = jsonwebtoken::encode(
+            &header,
+            &claims,
+            &EncodingKey::from_rsa_pem(RSA_2024_11.as_bytes()).unwrap(),
+        )
+        .unwrap();
+
+        let keys = JwtKeys::from_private_pem(Algorithm::RS256, "rsa-2024-11", RSA_2024_11, jwks())
+            .unwrap();
+        let err = keys.decode(&token).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::MissingRequiredClaim(_)));
     }
 }
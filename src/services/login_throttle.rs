@@ -0,0 +1,293 @@
+use std::time::Duration;
+
+use crate::{
+    core::login_attempts::dao_trait::DynLoginAttemptsDao,
+    error::{ConduitError, ConduitResult},
+};
+
+/// ログイン試行の制限の設定
+#[derive(Debug, Clone)]
+pub struct LoginThrottlePolicy {
+    // このアカウントへの失敗がこの回数に達したらロックする
+    pub max_account_failures: i32,
+    // 同じIPアドレスからの失敗がこの回数に達したらロックする
+    // NAT配下の利用者をまとめてロックしないよう，アカウントより多めにする
+    pub max_ip_failures: i32,
+    pub lockout: Duration,
+    // 失敗するたびに base_delay * 2^(失敗回数-1) だけ待たせる
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    // 最後の失敗からこの時間が経てば，失敗回数を数え直す
+    pub window: Duration,
+}
+
+impl Default for LoginThrottlePolicy {
+    fn default() -> Self {
+        Self {
+            max_account_failures: 5,
+            max_ip_failures: 20,
+            lockout: Duration::from_secs(60 * 15),
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            window: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl LoginThrottlePolicy {
+    /// 失敗回数に応じてブロックする時間と，ロックしたかどうかを返す
+    pub fn delay_for(&self, failed_count: i32, max_failures: i32) -> (Duration, bool) {
+        if failed_count >= max_failures {
+            return (self.lockout, true);
+        }
+        let exp = failed_count.saturating_sub(1).clamp(0, 16) as u32;
+        let delay = self.base_delay.saturating_mul(2u32.pow(exp));
+        (delay.min(self.max_delay), false)
+    }
+}
+
+/// 先に数えておいたログイン試行
+/// 結果が出たら，失敗ならrecord_failure，成功ならreleaseに渡す
+#[derive(Debug)]
+pub struct LoginAttempt {
+    reserved: Vec<(String, i32)>,
+}
+
+/// アカウントごととIPアドレスごとにログインの失敗を数え，失敗が続けばブロックする
+pub struct LoginThrottle {
+    attempts: DynLoginAttemptsDao,
+    policy: LoginThrottlePolicy,
+}
+
+impl LoginThrottle {
+    pub fn new(attempts: DynLoginAttemptsDao, policy: LoginThrottlePolicy) -> Self {
+        Self { attempts, policy }
+    }
+
+    fn account_key(email: &str) -> String {
+        format!("email:{}", email.to_lowercase())
+    }
+
+    fn ip_key(ip: &str) -> String {
+        format!("ip:{}", ip)
+    }
+
+    fn keys(email: &str, ip: Option<&str>) -> Vec<String> {
+        let mut keys = vec![Self::account_key(email)];
+        if let Some(ip) = ip {
+            keys.push(Self::ip_key(ip));
+        }
+        keys
+    }
+
+    fn max_failures(&self, key: &str) -> i32 {
+        if key.starts_with("ip:") {
+            self.policy.max_ip_failures
+        } else {
+            self.policy.max_account_failures
+        }
+    }
+
+    /// パスワードを確認する前に，試行を失敗として先に数えておく
+    /// ブロック中か上限に達していればTooManyRequestsを返す
+    /// 確認してから失敗を数えると，同時に送られた試行がまとめて確認をすり抜けてしまう
+    pub async fn begin_attempt(
+        &self,
+        email: &str,
+        ip: Option<&str>,
+    ) -> ConduitResult<LoginAttempt> {
+        let mut reserved: Vec<(String, i32)> = Vec::new();
+        for key in Self::keys(email, ip) {
+            let max_failures = self.max_failures(&key);
+            let failed_count = self
+                .attempts
+                .try_begin_attempt(&key, self.policy.window, max_failures)
+                .await?;
+            let Some(failed_count) = failed_count else {
+                // 先に数えた分は試行しないので戻しておく
+                for (key, _) in reserved {
+                    self.attempts.release_attempt(&key).await?;
+                }
+                // ブロックされていなければ，他の試行の結果が出るまで待ってもらう
+                let retry_after = self
+                    .attempts
+                    .blocked_for(&key)
+                    .await?
+                    .unwrap_or(self.policy.base_delay);
+                return Err(ConduitError::TooManyRequests(retry_after));
+            };
+            reserved.push((key, failed_count));
+        }
+        Ok(LoginAttempt { reserved })
+    }
+
+    /// 失敗に応じてブロックする．ロックした場合はtrueを返す
+    pub async fn record_failure(&self, attempt: &LoginAttempt) -> ConduitResult<bool> {
+        let mut locked = false;
+        for (key, failed_count) in &attempt.reserved {
+            let (delay, is_locked) = self.policy.delay_for(*failed_count, self.max_failures(key));
+            self.attempts.block(key, delay).await?;
+            locked |= is_locked;
+        }
+        Ok(locked)
+    }
+
+    /// パスワードが正しかったので，先に数えた分を戻す
+    /// 2要素認証が残っていても，パスワードが合っていれば失敗には数えない
+    pub async fn release(&self, attempt: LoginAttempt) -> ConduitResult<()> {
+        for (key, _) in attempt.reserved {
+            self.attempts.release_attempt(&key).await?;
+        }
+        Ok(())
+    }
+
+    /// ログインに成功したらアカウントの失敗回数をリセットする
+    /// IPアドレスの方は，他のアカウントへの総当たりを防ぐためにリセットしない
+    pub async fn record_success(&self, email: &str) -> ConduitResult<()> {
+        self.attempts.reset(&Self::account_key(email)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc,
+    };
+
+    use super::*;
+    use crate::dao::login_attempts::InMemoryLoginAttemptsDao;
+
+    #[test]
+    fn delay_grows_exponentially() {
+        let policy = LoginThrottlePolicy::default();
+        assert_eq!(policy.delay_for(1, 5), (Duration::from_secs(1), false));
+        assert_eq!(policy.delay_for(2, 5), (Duration::from_secs(2), false));
+        assert_eq!(policy.delay_for(4, 5), (Duration::from_secs(8), false));
+        assert_eq!(policy.delay_for(5, 5), (policy.lockout, true));
+        // 上限で頭打ちになる
+        assert_eq!(policy.delay_for(10, 20), (policy.max_delay, false));
+    }
+
+    async fn fail(throttle: &LoginThrottle, email: &str, ip: Option<&str>) -> ConduitResult<bool> {
+        let attempt = throttle.begin_attempt(email, ip).await?;
+        throttle.record_failure(&attempt).await
+    }
+
+    #[tokio::test]
+    async fn lock_after_max_failures() {
+        let policy = LoginThrottlePolicy {
+            base_delay: Duration::ZERO,
+            max_account_failures: 3,
+            ..Default::default()
+        };
+        let throttle = LoginThrottle::new(Arc::new(InMemoryLoginAttemptsDao::new()), policy);
+
+        for _ in 0..2 {
+            assert!(!fail(&throttle, "a@example.com", None).await.unwrap());
+        }
+        assert!(fail(&throttle, "A@example.com", None).await.unwrap());
+
+        let err = throttle
+            .begin_attempt("a@example.com", None)
+            .await
+            .unwrap_err();
+        let ConduitError::TooManyRequests(retry_after) = err else {
+            panic!("unexpected error: {:?}", err);
+        };
+        assert!(retry_after > Duration::from_secs(60 * 14));
+
+        // 他のアカウントには影響しない
+        throttle.begin_attempt("b@example.com", None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn ip_is_throttled_across_accounts() {
+        let policy = LoginThrottlePolicy {
+            base_delay: Duration::ZERO,
+            max_ip_failures: 2,
+            ..Default::default()
+        };
+        let throttle = LoginThrottle::new(Arc::new(InMemoryLoginAttemptsDao::new()), policy);
+
+        let ip = Some("192.0.2.1");
+        fail(&throttle, "a@example.com", ip).await.unwrap();
+        fail(&throttle, "b@example.com", ip).await.unwrap();
+
+        assert!(throttle.begin_attempt("c@example.com", ip).await.is_err());
+        throttle
+            .begin_attempt("c@example.com", Some("192.0.2.2"))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn success_resets_account_failures() {
+        let policy = LoginThrottlePolicy {
+            base_delay: Duration::from_secs(10),
+            ..Default::default()
+        };
+        let throttle = LoginThrottle::new(Arc::new(InMemoryLoginAttemptsDao::new()), policy);
+
+        fail(&throttle, "a@example.com", None).await.unwrap();
+        assert!(throttle.begin_attempt("a@example.com", None).await.is_err());
+        throttle.record_success("a@example.com").await.unwrap();
+        throttle.begin_attempt("a@example.com", None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn released_attempts_are_not_counted() {
+        let policy = LoginThrottlePolicy {
+            max_account_failures: 2,
+            ..Default::default()
+        };
+        let throttle = LoginThrottle::new(Arc::new(InMemoryLoginAttemptsDao::new()), policy);
+
+        for _ in 0..3 {
+            let attempt = throttle.begin_attempt("a@example.com", None).await.unwrap();
+            throttle.release(attempt).await.unwrap();
+        }
+        throttle.begin_attempt("a@example.com", None).await.unwrap();
+    }
+
+    // 同時に送られた試行のうち，パスワードの確認まで進めるのは上限の回数まで
+    #[tokio::test]
+    async fn concurrent_attempts_do_not_exceed_max_failures() {
+        let policy = LoginThrottlePolicy {
+            base_delay: Duration::ZERO,
+            ..Default::default()
+        };
+        let max_account_failures = policy.max_account_failures;
+        let throttle = Arc::new(LoginThrottle::new(
+            Arc::new(InMemoryLoginAttemptsDao::new()),
+            policy,
+        ));
+        let verified = Arc::new(AtomicI32::new(0));
+        // すべての試行が確認の前で揃ってから結果を記録する
+        let barrier = Arc::new(tokio::sync::Barrier::new(50));
+
+        let handles = (0..50)
+            .map(|_| {
+                let throttle = throttle.clone();
+                let verified = verified.clone();
+                let barrier = barrier.clone();
+                tokio::spawn(async move {
+                    let attempt = throttle.begin_attempt("a@example.com", None).await;
+                    if attempt.is_ok() {
+                        verified.fetch_add(1, Ordering::SeqCst);
+                    }
+                    barrier.wait().await;
+                    if let Ok(attempt) = attempt {
+                        throttle.record_failure(&attempt).await.unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(verified.load(Ordering::SeqCst), max_account_failures);
+        assert!(throttle.begin_attempt("a@example.com", None).await.is_err());
+    }
+}
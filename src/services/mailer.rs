@@ -0,0 +1,197 @@
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{bail, Context as _};
+use axum::async_trait;
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::ConduitResult;
+
+pub type DynMailer = Arc<dyn Mailer + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Mail {
+    pub fn new(to: String, subject: String, body: String) -> Self {
+        Self { to, subject, body }
+    }
+}
+
+/// メールの送信手段を差し替えられるようにするためのトレイト
+/// 本番ではSMTP，ローカルではファイルかDBのoutboxを使う
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait Mailer {
+    async fn send(&self, mail: Mail) -> ConduitResult<()>;
+}
+
+/// シークレットからメーラーを作る
+///
+/// - `MAILER`: `smtp`, `file`, `outbox`(デフォルト)
+/// - `MAIL_FROM`: 送信元アドレス (smtp)
+/// - `SMTP_HOST`, `SMTP_USERNAME`, `SMTP_PASSWORD`: SMTPサーバーの設定 (smtp)
+/// - `MAIL_OUTBOX_DIR`: メールを書き出すディレクトリ (file)
+pub fn mailer_from_secrets(
+    get: impl Fn(&str) -> Option<String>,
+    pool: PgPool,
+) -> anyhow::Result<DynMailer> {
+    let mailer = get("MAILER").unwrap_or_else(|| "outbox".to_string());
+    let mailer: DynMailer = match mailer.as_str() {
+        "smtp" => {
+            let host = get("SMTP_HOST").context("SMTP_HOST is required")?;
+            let username = get("SMTP_USERNAME").context("SMTP_USERNAME is required")?;
+            let password = get("SMTP_PASSWORD").context("SMTP_PASSWORD is required")?;
+            let from = get("MAIL_FROM").context("MAIL_FROM is required")?;
+            Arc::new(SmtpMailer::new(&host, username, password, &from)?)
+        }
+        "file" => {
+            let dir = get("MAIL_OUTBOX_DIR").unwrap_or_else(|| "mail_outbox".to_string());
+            Arc::new(FileMailer::new(dir.into()))
+        }
+        "outbox" => Arc::new(OutboxMailer::new(pool)),
+        other => bail!("unknown MAILER: {}", other),
+    };
+    Ok(mailer)
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(host: &str, username: String, password: String, from: &str) -> anyhow::Result<Self> {
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::relay(host)
+            .context("invalid SMTP_HOST")?
+            .credentials(Credentials::new(username, password))
+            .build();
+        let from = from.parse().context("invalid MAIL_FROM")?;
+        Ok(Self { transport, from })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: Mail) -> ConduitResult<()> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(mail.to.parse().context("invalid recipient address")?)
+            .subject(mail.subject)
+            .body(mail.body)
+            .context("failed to build mail")?;
+        self.transport
+            .send(message)
+            .await
+            .context("failed to send mail")?;
+        Ok(())
+    }
+}
+
+/// メールを1通ずつファイルに書き出す
+/// メールサーバーなしでローカルで動作確認するためのもの
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> ConduitResult<()> {
+        // ローカル用なので，小さなファイルを同期的に書き込むだけにしている
+        std::fs::create_dir_all(&self.dir).context("failed to create mail outbox dir")?;
+        let path = self.dir.join(format!("{}.eml", Uuid::now_v7()));
+        let content = format!(
+            "To: {}\nSubject: {}\n\n{}\n",
+            mail.to, mail.subject, mail.body
+        );
+        std::fs::write(&path, content).context("failed to write mail")?;
+        Ok(())
+    }
+}
+
+/// メールをmail_outboxテーブルに保存する
+/// 実際の送信は別のプロセスに任せるか，ローカルではテーブルを直接確認する
+pub struct OutboxMailer {
+    pool: PgPool,
+}
+
+impl OutboxMailer {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl Mailer for OutboxMailer {
+    async fn send(&self, mail: Mail) -> ConduitResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO mail_outbox (id, recipient, subject, body)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            Uuid::now_v7(),
+            mail.to,
+            mail.subject,
+            mail.body
+        )
+        .execute(&self.pool)
+        .await
+        .context("unexpected error: while inserting mail_outbox")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mail() -> Mail {
+        Mail::new(
+            "test@example.com".to_string(),
+            "subject".to_string(),
+            "body".to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn file_mailer_writes_mail() {
+        let dir = std::env::temp_dir().join(format!("mail_outbox_{}", Uuid::now_v7()));
+        let mailer = FileMailer::new(dir.clone());
+        mailer.send(mail()).await.unwrap();
+
+        let files = std::fs::read_dir(&dir).unwrap().collect::<Vec<_>>();
+        assert_eq!(files.len(), 1);
+        let content = std::fs::read_to_string(files[0].as_ref().unwrap().path()).unwrap();
+        assert!(content.contains("To: test@example.com"));
+        assert!(content.contains("Subject: subject"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[sqlx::test]
+    async fn outbox_mailer_inserts_mail(pool: PgPool) {
+        let mailer = OutboxMailer::new(pool.clone());
+        mailer.send(mail()).await.unwrap();
+
+        let row = sqlx::query!("SELECT recipient, subject, body, sent_at FROM mail_outbox")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(row.recipient, "test@example.com");
+        assert_eq!(row.subject, "subject");
+        assert_eq!(row.sent_at, None);
+    }
+}
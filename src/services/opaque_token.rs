@@ -0,0 +1,45 @@
+use rand::{distributions::Alphanumeric, rngs::OsRng, Rng as _};
+use sha2::{Digest as _, Sha256};
+
+const TOKEN_LEN: usize = 48;
+
+/// メールで送るリセットトークンなど，JWTではないランダムなトークンを扱う
+/// DBにはトークンそのものではなくハッシュを保存する
+pub struct OpaqueTokenService;
+
+impl OpaqueTokenService {
+    pub fn generate() -> String {
+        OsRng
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LEN)
+            .map(char::from)
+            .collect()
+    }
+
+    /// トークン自体が十分にランダムなので，ソルトなしのSHA-256で十分
+    pub fn hash(token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_random_token() {
+        let a = OpaqueTokenService::generate();
+        let b = OpaqueTokenService::generate();
+        assert_eq!(a.len(), TOKEN_LEN);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn hash_is_deterministic() {
+        let token = OpaqueTokenService::generate();
+        let hashed = OpaqueTokenService::hash(&token);
+        assert_eq!(hashed, OpaqueTokenService::hash(&token));
+        assert_ne!(hashed, token);
+        assert_eq!(hashed.len(), 64);
+    }
+}
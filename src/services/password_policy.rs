@@ -0,0 +1,294 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use anyhow::Context as _;
+use sha1::{Digest, Sha1};
+use validator::{ValidationError, ValidationErrors};
+
+// リポジトリに同梱している漏洩パスワードの一覧
+const BUNDLED_BREACHED_PASSWORDS: &str = include_str!("../../assets/breached_passwords.txt");
+
+// SHA-1の16進数表記のうち，先頭何文字で一覧を分けるか
+const PREFIX_LEN: usize = 5;
+
+/// 漏洩が確認されているパスワードの一覧
+///
+/// Have I Been Pwnedのrange APIと同じく，SHA-1ハッシュの先頭5文字で引いた候補の中から残りを照合する
+/// 平文のパスワードは一覧にも照合にも現れない
+#[derive(Debug, Clone, Default)]
+pub struct BreachedPasswords {
+    ranges: Arc<HashMap<String, HashSet<String>>>,
+}
+
+impl BreachedPasswords {
+    /// `HASH`または`HASH:COUNT`の形式の行を読み込む `#`で始まる行と空行は無視する
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        let mut ranges: HashMap<String, HashSet<String>> = HashMap::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let hash = line.split(':').next().unwrap_or_default();
+            if hash.len() != 40 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                anyhow::bail!("invalid sha1 hash at line {}", i + 1);
+            }
+            let hash = hash.to_ascii_uppercase();
+            let (prefix, suffix) = hash.split_at(PREFIX_LEN);
+            ranges
+                .entry(prefix.to_string())
+                .or_default()
+                .insert(suffix.to_string());
+        }
+        Ok(Self {
+            ranges: Arc::new(ranges),
+        })
+    }
+
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_BREACHED_PASSWORDS).expect("bundled breached password list is invalid")
+    }
+
+    pub fn from_file(path: &str) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read breached password list: {}", path))?;
+        Self::parse(&content)
+    }
+
+    pub fn contains(&self, password: &str) -> bool {
+        let hash = format!("{:X}", Sha1::digest(password.as_bytes()));
+        let (prefix, suffix) = hash.split_at(PREFIX_LEN);
+        self.ranges
+            .get(prefix)
+            .is_some_and(|range| range.contains(suffix))
+    }
+}
+
+/// パスワードの強度の基準
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    // 長すぎるパスワードのハッシュ化に時間をかけさせないための上限
+    pub max_length: usize,
+    // estimate_entropyで見積もったビット数の下限
+    pub min_entropy_bits: f64,
+    pub breached: BreachedPasswords,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            min_entropy_bits: 40.0,
+            breached: BreachedPasswords::bundled(),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// シークレットから設定を読み込む 指定がない項目はデフォルト値を使う
+    ///
+    /// - `PASSWORD_MIN_LENGTH`: 最小の文字数
+    /// - `BREACHED_PASSWORDS_FILE`: 同梱の一覧の代わりに使う漏洩パスワードの一覧
+    pub fn from_secrets(get: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let mut policy = Self::default();
+        if let Some(min_length) = get("PASSWORD_MIN_LENGTH") {
+            policy.min_length = min_length.parse().context("invalid PASSWORD_MIN_LENGTH")?;
+        }
+        if let Some(path) = get("BREACHED_PASSWORDS_FILE") {
+            policy.breached = BreachedPasswords::from_file(&path)?;
+        }
+        Ok(policy)
+    }
+
+    /// パスワードが基準を満たしているか確認し，違反はpasswordフィールドのエラーとして返す
+    ///
+    /// `user_inputs`にはユーザー名やメールアドレスなど，パスワードに含めてはいけない値を渡す
+    pub fn check(&self, password: &str, user_inputs: &[&str]) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let length = password.chars().count();
+        if length < self.min_length {
+            errors.add(
+                "password",
+                error(
+                    "password_too_short",
+                    format!("password must be at least {} characters", self.min_length),
+                ),
+            );
+        } else if length > self.max_length {
+            errors.add(
+                "password",
+                error(
+                    "password_too_long",
+                    format!("password must be at most {} characters", self.max_length),
+                ),
+            );
+        } else if estimate_entropy(password) < self.min_entropy_bits {
+            errors.add(
+                "password",
+                error(
+                    "password_too_weak",
+                    "password is too easy to guess: use a longer password or mix character types",
+                ),
+            );
+        }
+        if contains_user_input(password, user_inputs) {
+            errors.add(
+                "password",
+                error(
+                    "password_contains_user_input",
+                    "password must not contain the username or email",
+                ),
+            );
+        }
+        if self.breached.contains(password) {
+            errors.add(
+                "password",
+                error(
+                    "password_breached",
+                    "password has appeared in a data breach: choose a different password",
+                ),
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn error(code: &'static str, message: impl Into<Cow<'static, str>>) -> ValidationError {
+    ValidationError::new(code).with_message(message.into())
+}
+
+/// 使われている文字の種類と長さからエントロピーをビット数で見積もる
+///
+/// 直前と同じ文字の繰り返しは数えないので，`aaaaaaaa`のようなパスワードは低く見積もられる
+pub fn estimate_entropy(password: &str) -> f64 {
+    let (mut lower, mut upper, mut digit, mut symbol, mut other) =
+        (false, false, false, false, false);
+    let mut effective_length = 0;
+    let mut prev = None;
+    for c in password.chars() {
+        match c {
+            'a'..='z' => lower = true,
+            'A'..='Z' => upper = true,
+            '0'..='9' => digit = true,
+            c if c.is_ascii() => symbol = true,
+            _ => other = true,
+        }
+        if prev != Some(c) {
+            effective_length += 1;
+        }
+        prev = Some(c);
+    }
+    let pool = [
+        (lower, 26),
+        (upper, 26),
+        (digit, 10),
+        (symbol, 33),
+        (other, 100),
+    ]
+    .iter()
+    .filter(|(used, _)| *used)
+    .map(|(_, size)| size)
+    .sum::<u32>();
+    if pool == 0 {
+        return 0.0;
+    }
+    effective_length as f64 * f64::from(pool).log2()
+}
+
+// メールアドレスはローカル部も確認する 短すぎる値は誤検知が多いので無視する
+fn contains_user_input(password: &str, user_inputs: &[&str]) -> bool {
+    let password = password.to_lowercase();
+    user_inputs
+        .iter()
+        .flat_map(|input| {
+            let local = input.split_once('@').map(|(local, _)| local);
+            std::iter::once(*input).chain(local)
+        })
+        .map(str::to_lowercase)
+        .filter(|input| input.chars().count() >= 3)
+        .any(|input| password.contains(&input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(result: Result<(), ValidationErrors>) -> Vec<String> {
+        let Err(errors) = result else {
+            return vec![];
+        };
+        errors.field_errors()["password"]
+            .iter()
+            .map(|e| e.code.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn accepts_strong_password() {
+        let policy = PasswordPolicy::default();
+        let result = policy.check(
+            "correct horse battery staple",
+            &["alice", "alice@example.com"],
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn rejects_short_and_weak_passwords() {
+        let policy = PasswordPolicy::default();
+        assert_eq!(codes(policy.check("a1", &[])), vec!["password_too_short"]);
+        assert_eq!(
+            codes(policy.check("aaaaaaaaaaaa", &[])),
+            vec!["password_too_weak"]
+        );
+        assert_eq!(
+            codes(policy.check(&"Ab1!".repeat(40), &[])),
+            vec!["password_too_long"]
+        );
+    }
+
+    #[test]
+    fn rejects_password_containing_user_input() {
+        let policy = PasswordPolicy::default();
+        let inputs = ["neruneruna", "someone@example.com"];
+        assert_eq!(
+            codes(policy.check("xNeruNerunA-2024", &inputs)),
+            vec!["password_contains_user_input"]
+        );
+        assert_eq!(
+            codes(policy.check("someone!Secure42", &inputs)),
+            vec!["password_contains_user_input"]
+        );
+    }
+
+    #[test]
+    fn rejects_breached_password() {
+        let policy = PasswordPolicy::default();
+        assert_eq!(
+            codes(policy.check("password123", &[])),
+            vec!["password_breached"]
+        );
+        assert!(!policy.breached.contains("correct horse battery staple"));
+    }
+
+    #[test]
+    fn parses_hibp_format() {
+        let breached = BreachedPasswords::parse(
+            "# comment\n\n5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8:3861493\n",
+        )
+        .unwrap();
+        assert!(breached.contains("password"));
+        assert!(!breached.contains("Password"));
+        assert!(BreachedPasswords::parse("not a hash").is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashSet;
+
+use crate::{
+    core::reactions::{dto::ArticleReaction, entity::ReactionCountEntity},
+    error::{ConduitError, ConduitResult},
+};
+
+// 設定がない場合に使うリアクションの種類
+const DEFAULT_REACTION_KINDS: [(&str, &str); 6] = [
+    ("thumbs_up", "👍"),
+    ("heart", "❤️"),
+    ("laugh", "😄"),
+    ("hooray", "🎉"),
+    ("rocket", "🚀"),
+    ("eyes", "👀"),
+];
+const MAX_REACTION_KINDS: usize = 20;
+const MAX_KIND_LEN: usize = 32;
+
+/// リアクションの種類 URLには`name`を使う
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReactionKind {
+    pub name: String,
+    pub emoji: String,
+}
+
+/// リアクションの設定
+#[derive(Debug, Clone)]
+pub struct ReactionPolicy {
+    // 並び順はレスポンスでもそのまま使う
+    pub kinds: Vec<ReactionKind>,
+}
+
+impl Default for ReactionPolicy {
+    fn default() -> Self {
+        Self {
+            kinds: DEFAULT_REACTION_KINDS
+                .iter()
+                .map(|(name, emoji)| ReactionKind {
+                    name: name.to_string(),
+                    emoji: emoji.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ReactionPolicy {
+    /// シークレットから設定を読み込む 省略した場合はデフォルトの種類になる
+    ///
+    /// - `REACTION_KINDS`: `name:emoji`をカンマで区切ったもの．例: `thumbs_up:👍,heart:❤️`
+    ///   `name`は英小文字，数字，`_`で32文字まで．種類は20個まで
+    pub fn from_secrets(get: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let Some(value) = get("REACTION_KINDS") else {
+            return Ok(Self::default());
+        };
+
+        let mut kinds = Vec::new();
+        let mut names = HashSet::new();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((name, emoji)) = entry.split_once(':') else {
+                anyhow::bail!("invalid REACTION_KINDS: expected name:emoji, got {}", entry);
+            };
+            let (name, emoji) = (name.trim(), emoji.trim());
+            if !is_valid_name(name) || emoji.is_empty() {
+                anyhow::bail!("invalid REACTION_KINDS entry: {}", entry);
+            }
+            if !names.insert(name) {
+                anyhow::bail!("duplicate REACTION_KINDS entry: {}", name);
+            }
+            kinds.push(ReactionKind {
+                name: name.to_string(),
+                emoji: emoji.to_string(),
+            });
+        }
+        if kinds.is_empty() || kinds.len() > MAX_REACTION_KINDS {
+            anyhow::bail!(
+                "REACTION_KINDS must have between 1 and {} kinds",
+                MAX_REACTION_KINDS
+            );
+        }
+        Ok(Self { kinds })
+    }
+
+    pub fn ensure_kind(&self, kind: &str) -> ConduitResult<()> {
+        if !self.kinds.iter().any(|k| k.name == kind) {
+            return Err(ConduitError::BadRequest(format!(
+                "unknown reaction kind: {}",
+                kind
+            )));
+        }
+        Ok(())
+    }
+
+    /// 設定されている種類の順に，件数と呼び出したユーザーのリアクションをまとめる
+    /// リアクションのない種類も0件として含め，設定から外した種類は含めない
+    pub fn summarize(
+        &self,
+        counts: &[ReactionCountEntity],
+        reacted: &[String],
+    ) -> Vec<ArticleReaction> {
+        self.kinds
+            .iter()
+            .map(|kind| ArticleReaction {
+                kind: kind.name.clone(),
+                emoji: kind.emoji.clone(),
+                count: counts
+                    .iter()
+                    .find(|c| c.kind == kind.name)
+                    .map_or(0, |c| c.count),
+                reacted: reacted.contains(&kind.name),
+            })
+            .collect()
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_KIND_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secrets(value: &str) -> impl Fn(&str) -> Option<String> + '_ {
+        move |key| (key == "REACTION_KINDS").then(|| value.to_string())
+    }
+
+    #[test]
+    fn reads_reaction_kinds() {
+        let policy = ReactionPolicy::from_secrets(|_| None).unwrap();
+        assert_eq!(policy.kinds.len(), DEFAULT_REACTION_KINDS.len());
+
+        let policy = ReactionPolicy::from_secrets(secrets("heart:❤️, party : 🎉")).unwrap();
+        assert_eq!(
+            policy.kinds,
+            vec![
+                ReactionKind {
+                    name: "heart".to_string(),
+                    emoji: "❤️".to_string(),
+                },
+                ReactionKind {
+                    name: "party".to_string(),
+                    emoji: "🎉".to_string(),
+                },
+            ]
+        );
+
+        for invalid in ["", "heart", "Heart:❤️", "heart:", "heart:❤️,heart:💖"] {
+            assert!(
+                ReactionPolicy::from_secrets(secrets(invalid)).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_kinds() {
+        let policy = ReactionPolicy::default();
+        assert!(policy.ensure_kind("heart").is_ok());
+        assert!(matches!(
+            policy.ensure_kind("angry"),
+            Err(ConduitError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn summarizes_in_configured_order() {
+        let policy = ReactionPolicy::from_secrets(secrets("heart:❤️,eyes:👀")).unwrap();
+        let counts = vec![
+            ReactionCountEntity {
+                kind: "eyes".to_string(),
+                count: 2,
+            },
+            // 設定から外した種類
+            ReactionCountEntity {
+                kind: "rocket".to_string(),
+                count: 5,
+            },
+        ];
+        let summary = policy
+            .summarize(&counts, &["eyes".to_string()])
+            .into_iter()
+            .map(|r| (r.kind, r.count, r.reacted))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("heart".to_string(), 0, false),
+                ("eyes".to_string(), 2, true)
+            ]
+        );
+    }
+}
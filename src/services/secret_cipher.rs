@@ -0,0 +1,100 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::{anyhow, Context as _};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+use crate::error::ConduitResult;
+
+const NONCE_LEN: usize = 12;
+
+/// DBに保存する秘密の値をAES-256-GCMで暗号化する
+///
+/// 暗号文の先頭にnonceを付けて返す
+/// `aad`には行のIDなどを渡し，暗号文を別の行にコピーしても復号できないようにする
+#[derive(Clone)]
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl SecretCipher {
+    pub fn new(key: &[u8; 32]) -> Self {
+        let key = Key::<Aes256Gcm>::from_slice(key);
+        Self {
+            cipher: Aes256Gcm::new(key),
+        }
+    }
+
+    /// base64でエンコードされた32バイトの鍵から作る
+    pub fn from_base64(key: &str) -> anyhow::Result<Self> {
+        let key = STANDARD
+            .decode(key.trim())
+            .context("key is not valid base64")?;
+        let key: [u8; 32] = key
+            .try_into()
+            .map_err(|_| anyhow!("key must be 32 bytes"))?;
+        Ok(Self::new(&key))
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8], aad: &[u8]) -> ConduitResult<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad,
+                },
+            )
+            .map_err(|_| anyhow!("failed to encrypt secret"))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(sealed)
+    }
+
+    pub fn decrypt(&self, sealed: &[u8], aad: &[u8]) -> ConduitResult<Vec<u8>> {
+        if sealed.len() < NONCE_LEN {
+            return Err(anyhow!("encrypted secret is too short").into());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad,
+                },
+            )
+            .map_err(|_| anyhow!("failed to decrypt secret"))?;
+        Ok(plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypt_decrypt() {
+        let cipher = SecretCipher::new(&[7; 32]);
+        let sealed = cipher.encrypt(b"secret", b"user_a").unwrap();
+        assert!(!sealed.windows(6).any(|w| w == b"secret"));
+        assert_eq!(cipher.decrypt(&sealed, b"user_a").unwrap(), b"secret");
+
+        // 別の行や別の鍵では復号できない
+        assert!(cipher.decrypt(&sealed, b"user_b").is_err());
+        let other = SecretCipher::new(&[8; 32]);
+        assert!(other.decrypt(&sealed, b"user_a").is_err());
+    }
+
+    #[test]
+    fn from_base64() {
+        let key = STANDARD.encode([1u8; 32]);
+        assert!(SecretCipher::from_base64(&key).is_ok());
+        assert!(SecretCipher::from_base64(&STANDARD.encode([1u8; 16])).is_err());
+        assert!(SecretCipher::from_base64("not base64!").is_err());
+    }
+}
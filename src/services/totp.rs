@@ -0,0 +1,223 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context as _};
+use rand::{distributions::Uniform, rngs::OsRng, Rng, RngCore};
+use totp_rs::{Algorithm, TOTP};
+
+use crate::error::{ConduitError, ConduitResult};
+
+use super::secret_cipher::SecretCipher;
+
+const DIGITS: usize = 6;
+const STEP_SECS: u64 = 30;
+// RFC 4226の推奨値 160bit
+const SECRET_LEN: usize = 20;
+const RECOVERY_CODE_COUNT: usize = 10;
+// 紛らわしい文字(0, o, 1, l, i)を除いた英数字
+const RECOVERY_CODE_CHARS: &[u8] = b"23456789abcdefghjkmnpqrstuvwxyz";
+
+/// 2要素認証の設定
+#[derive(Clone)]
+pub struct TotpSettings {
+    // 認証アプリに表示されるサービス名
+    pub issuer: String,
+    // 秘密鍵をDBに保存するときの暗号化に使う Noneなら2要素認証を新しく有効にできない
+    pub cipher: Option<SecretCipher>,
+    // パスワードの確認後，コードの入力を待つ時間
+    pub challenge_ttl: Duration,
+    // 1つのチャレンジで許すコードの入力ミスの回数
+    pub max_challenge_attempts: i32,
+}
+
+impl TotpSettings {
+    pub fn new(cipher: Option<SecretCipher>) -> Self {
+        Self {
+            issuer: "Conduit".to_string(),
+            cipher,
+            challenge_ttl: Duration::from_secs(60 * 5),
+            max_challenge_attempts: 5,
+        }
+    }
+
+    /// シークレットから設定を読み込む
+    ///
+    /// - `TOTP_ENCRYPTION_KEY`: 秘密鍵の暗号化に使う32バイトの鍵(base64) 設定した場合だけ2要素認証を使える
+    /// - `TOTP_ISSUER`: 認証アプリに表示されるサービス名
+    pub fn from_secrets(get: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let cipher = get("TOTP_ENCRYPTION_KEY")
+            .map(|key| SecretCipher::from_base64(&key).context("invalid TOTP_ENCRYPTION_KEY"))
+            .transpose()?;
+        let mut settings = Self::new(cipher);
+        if let Some(issuer) = get("TOTP_ISSUER") {
+            settings.issuer = issuer;
+        }
+        Ok(settings)
+    }
+
+    /// 鍵が設定されていない場合，2要素認証の登録はできない
+    pub fn cipher(&self) -> ConduitResult<&SecretCipher> {
+        self.cipher.as_ref().ok_or_else(|| {
+            ConduitError::NotFound("two-factor authentication is not available".to_string())
+        })
+    }
+}
+
+/// RFC 6238のTOTP(SHA-1, 6桁, 30秒)を扱う
+pub struct TotpService;
+
+impl TotpService {
+    pub fn generate_secret() -> Vec<u8> {
+        let mut secret = vec![0u8; SECRET_LEN];
+        OsRng.fill_bytes(&mut secret);
+        secret
+    }
+
+    fn totp(secret: &[u8], issuer: Option<String>, account: String) -> ConduitResult<TOTP> {
+        let totp = TOTP::new(
+            Algorithm::SHA1,
+            DIGITS,
+            0,
+            STEP_SECS,
+            secret.to_vec(),
+            issuer,
+            account,
+        )
+        .map_err(|e| anyhow!("invalid totp parameters: {}", e))?;
+        Ok(totp)
+    }
+
+    /// 認証アプリに登録するための`otpauth://`のURI
+    pub fn otpauth_uri(secret: &[u8], issuer: &str, account: &str) -> ConduitResult<String> {
+        let totp = Self::totp(secret, Some(issuer.to_string()), account.to_string())?;
+        Ok(totp.get_url())
+    }
+
+    /// 手入力用のbase32の秘密鍵
+    pub fn secret_base32(secret: &[u8]) -> ConduitResult<String> {
+        Ok(Self::totp(secret, None, String::new())?.get_secret_base32())
+    }
+
+    /// 前後1ステップのずれまで許してコードを確認する
+    /// 一致した場合はその時間ステップを返す 再利用の確認に使う
+    pub fn verify(secret: &[u8], code: &str, unix_time: u64) -> ConduitResult<Option<i64>> {
+        let totp = Self::totp(secret, None, String::new())?;
+        let current = unix_time / STEP_SECS;
+        let steps = [current.saturating_sub(1), current, current + 1];
+        let matched = steps
+            .into_iter()
+            .find(|step| totp.check(code, step * STEP_SECS));
+        Ok(matched.map(|step| step as i64))
+    }
+
+    pub fn verify_now(secret: &[u8], code: &str) -> ConduitResult<Option<i64>> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("system time is before unix epoch")?;
+        Self::verify(secret, code, now.as_secs())
+    }
+
+    /// 認証アプリのコードの形をしているかどうか それ以外はリカバリーコードとして扱う
+    pub fn is_totp_code(code: &str) -> bool {
+        code.len() == DIGITS && code.chars().all(|c| c.is_ascii_digit())
+    }
+
+    /// `xxxxx-xxxxx`の形式のリカバリーコードを生成する
+    pub fn generate_recovery_codes() -> Vec<String> {
+        let dist = Uniform::from(0..RECOVERY_CODE_CHARS.len());
+        (0..RECOVERY_CODE_COUNT)
+            .map(|_| {
+                let chars = OsRng
+                    .sample_iter(dist)
+                    .take(10)
+                    .map(|i| RECOVERY_CODE_CHARS[i] as char)
+                    .collect::<String>();
+                format!("{}-{}", &chars[..5], &chars[5..])
+            })
+            .collect()
+    }
+
+    /// 入力されたリカバリーコードを保存時と同じ形にそろえる
+    pub fn normalize_recovery_code(code: &str) -> String {
+        code.chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .flat_map(char::to_lowercase)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 付録Bのテストベクタ(SHA-1)
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn verify_rfc6238_vectors() {
+        // 8桁のテストベクタの下6桁
+        for (time, code) in [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (2000000000, "279037"),
+        ] {
+            let step = TotpService::verify(RFC_SECRET, code, time).unwrap();
+            assert_eq!(step, Some((time / STEP_SECS) as i64));
+        }
+        assert_eq!(TotpService::verify(RFC_SECRET, "000000", 59).unwrap(), None);
+    }
+
+    #[test]
+    fn verify_allows_one_step_skew() {
+        // 59秒のコードは前後1ステップまで有効
+        assert_eq!(
+            TotpService::verify(RFC_SECRET, "287082", 59 + STEP_SECS).unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            TotpService::verify(RFC_SECRET, "287082", 59 + STEP_SECS * 2).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn otpauth_uri() {
+        let secret = TotpService::generate_secret();
+        let uri = TotpService::otpauth_uri(&secret, "Conduit", "jake@example.com").unwrap();
+        let base32 = TotpService::secret_base32(&secret).unwrap();
+        assert!(uri.starts_with("otpauth://totp/Conduit:jake%40example.com?"));
+        assert!(uri.contains(&format!("secret={}", base32)));
+        assert!(uri.contains("issuer=Conduit"));
+    }
+
+    #[test]
+    fn settings_without_key_disable_enrollment() {
+        let settings = TotpSettings::from_secrets(|_| None).unwrap();
+        assert!(matches!(settings.cipher(), Err(ConduitError::NotFound(_))));
+
+        let key = "A".repeat(43) + "=";
+        let settings =
+            TotpSettings::from_secrets(|k| (k == "TOTP_ENCRYPTION_KEY").then(|| key.clone()))
+                .unwrap();
+        assert!(settings.cipher().is_ok());
+
+        assert!(TotpSettings::from_secrets(
+            |k| (k == "TOTP_ENCRYPTION_KEY").then(|| "short".into())
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn recovery_codes() {
+        let codes = TotpService::generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        for code in &codes {
+            assert_eq!(code.len(), 11);
+            assert!(!TotpService::is_totp_code(code));
+            assert_eq!(
+                TotpService::normalize_recovery_code(&code.to_uppercase().replace('-', " - ")),
+                code.replace('-', "")
+            );
+        }
+        assert!(TotpService::is_totp_code("123456"));
+    }
+}
@@ -0,0 +1,152 @@
+use std::borrow::Cow;
+
+use unicode_normalization::UnicodeNormalization;
+use validator::{ValidationError, ValidationErrors};
+
+pub const MIN_USERNAME_LENGTH: usize = 3;
+pub const MAX_USERNAME_LENGTH: usize = 50;
+
+// ルーティングや運営アカウントと紛らわしい名前
+const RESERVED_USERNAMES: &[&str] = &[
+    "admin",
+    "administrator",
+    "anonymous",
+    "api",
+    "articles",
+    "conduit",
+    "editor",
+    "help",
+    "login",
+    "logout",
+    "me",
+    "moderator",
+    "null",
+    "profile",
+    "profiles",
+    "register",
+    "root",
+    "settings",
+    "suggestions",
+    "support",
+    "system",
+    "tags",
+    "undefined",
+    "user",
+    "users",
+];
+
+pub struct UsernameService;
+
+impl UsernameService {
+    /// ユーザー名をNFKCで正規化し，前後の空白を取り除く
+    ///
+    /// 全角英数字や合成済み文字などの見た目が同じ名前を同じ表記にそろえる
+    /// 大文字小文字はそのまま残し，区別しないのはDBでの比較に任せる
+    pub fn normalize(username: &str) -> String {
+        username.nfkc().collect::<String>().trim().to_string()
+    }
+
+    /// 正規化済みのユーザー名を検証し，違反はusernameフィールドのエラーとして返す
+    pub fn validate(username: &str) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let length = username.chars().count();
+        if !(MIN_USERNAME_LENGTH..=MAX_USERNAME_LENGTH).contains(&length) {
+            errors.add(
+                "username",
+                error(
+                    "username_length",
+                    format!(
+                        "username must be between {} and {} characters",
+                        MIN_USERNAME_LENGTH, MAX_USERNAME_LENGTH
+                    ),
+                ),
+            );
+        }
+        // URLのパスにそのまま使えるよう，文字種を絞る
+        let valid_chars = username
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        let starts_with_alphanumeric = username.chars().next().is_some_and(char::is_alphanumeric);
+        if !valid_chars || !starts_with_alphanumeric {
+            errors.add(
+                "username",
+                error(
+                    "username_invalid_chars",
+                    "username must start with a letter or digit and contain only letters, digits, '_', '-' and '.'",
+                ),
+            );
+        }
+        if Self::is_reserved(username) {
+            errors.add(
+                "username",
+                error("username_reserved", "username is reserved"),
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn is_reserved(username: &str) -> bool {
+        let username = username.to_lowercase();
+        RESERVED_USERNAMES.contains(&username.as_str())
+    }
+}
+
+fn error(code: &'static str, message: impl Into<Cow<'static, str>>) -> ValidationError {
+    ValidationError::new(code).with_message(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(result: Result<(), ValidationErrors>) -> Vec<String> {
+        let Err(errors) = result else {
+            return vec![];
+        };
+        errors.field_errors()["username"]
+            .iter()
+            .map(|e| e.code.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn normalize_username() {
+        // 全角英数字は半角に，合成用の濁点は合成済み文字にそろえる
+        assert_eq!(UsernameService::normalize("  Ｊａｋｅ１２ "), "Jake12");
+        assert_eq!(UsernameService::normalize("か\u{3099}き"), "がき");
+        assert_eq!(UsernameService::normalize("Jake"), "Jake");
+    }
+
+    #[test]
+    fn validate_username() {
+        assert!(UsernameService::validate("jake").is_ok());
+        assert!(UsernameService::validate("jake_the.dog-2").is_ok());
+        assert!(UsernameService::validate("ねるねる").is_ok());
+
+        assert_eq!(
+            codes(UsernameService::validate("ab")),
+            vec!["username_length"]
+        );
+        assert_eq!(
+            codes(UsernameService::validate("jake/../admin")),
+            vec!["username_invalid_chars"]
+        );
+        assert_eq!(
+            codes(UsernameService::validate(".jake")),
+            vec!["username_invalid_chars"]
+        );
+        assert_eq!(
+            codes(UsernameService::validate("jake smith")),
+            vec!["username_invalid_chars"]
+        );
+        assert_eq!(
+            codes(UsernameService::validate("Admin")),
+            vec!["username_reserved"]
+        );
+    }
+}